@@ -0,0 +1,399 @@
+//! Finding the floating point expressions of a crate without its types.
+//!
+//! An expression is considered to be an `f64` if it is a float literal, a cast to `f64`, a
+//! variable annotated as `f64` or initialized with such an expression, a field which is declared
+//! as `f64` in one of the crate's structures, a constant from the `f64` module, a call to a method
+//! known to Herbie on such an expression, or an arithmetic operation on such an expression. The
+//! code of macro invocations is not analysed, so the ranges of variables are only known from the
+//! `#[herbie_range]` attributes of items, which apply to the parameters of the functions.
+
+use herbie_lint_core::lisp::herbie_name;
+use herbie_lint_core::range::{intersect, parse_range};
+use std::collections::{HashMap, HashSet};
+use std::mem;
+use syntax::ast::*;
+use syntax::codemap::Span;
+use syntax::visit::{self, FnKind, Visitor};
+
+/// An expression worth analysing.
+#[derive(Clone, Debug)]
+pub struct Candidate<'a> {
+    pub expr: &'a Expr,
+    /// The innermost expression containing `expr`, if any, in the same item.
+    pub parent: Option<&'a Expr>,
+    /// The known ranges of the variables in scope, by name.
+    pub ranges: HashMap<String, (f64, f64)>,
+}
+
+/// Return the floating point expressions of `krate` which are worth analysing, outer expressions
+/// first. Expressions marked with `#[herbie_ignore]`, or in an item marked with it, are skipped.
+pub fn find_exprs(krate: &Crate) -> Vec<Candidate> {
+    let mut fields = FloatFields { names: HashSet::new() };
+    visit::walk_crate(&mut fields, krate);
+
+    let mut finder = Finder {
+        candidates: Vec::new(),
+        float_fields: fields.names,
+        scopes: vec![HashMap::new()],
+        parents: Vec::new(),
+        ignored: krate.attrs.iter().any(is_herbie_ignore),
+        in_body: false,
+        module_ignored: krate.attrs.iter().any(is_herbie_ignore),
+        ranges: HashMap::new(),
+        module_ranges: HashMap::new(),
+    };
+    add_ranges(&mut finder.ranges, &krate.attrs);
+    finder.module_ranges = finder.ranges.clone();
+    visit::walk_crate(&mut finder, krate);
+
+    finder.candidates
+}
+
+pub fn is_herbie_ignore(attr: &Attribute) -> bool {
+    if let MetaItemKind::Word(ref word) = attr.node.value.node {
+        word == &"herbie_ignore"
+    }
+    else {
+        false
+    }
+}
+
+/// Add the ranges of the valid `#[herbie_range(x = "lo..hi")]` attributes among `attrs`. The
+/// plugin reports the invalid ones.
+fn add_ranges(ranges: &mut HashMap<String, (f64, f64)>, attrs: &[Attribute]) {
+    for attr in attrs {
+        let items = match attr.node.value.node {
+            MetaItemKind::List(ref name, ref items) if name == &"herbie_range" => items,
+            _ => continue,
+        };
+
+        let attr_ranges = items.iter().map(|item| {
+            match item.node {
+                MetaItemKind::NameValue(ref name, ref lit) => {
+                    match lit.node {
+                        LitKind::Str(ref range, _) => {
+                            parse_range(range).map(|range| (name.to_string(), range))
+                        }
+                        _ => None,
+                    }
+                }
+                _ => None,
+            }
+        }).collect::<Option<Vec<_>>>();
+
+        for (name, range) in attr_ranges.unwrap_or_else(Vec::new) {
+            intersect(ranges, name, range);
+        }
+    }
+}
+
+/// Check whether a type is `f64`.
+fn is_f64(ty: &Ty) -> bool {
+    match ty.node {
+        TyKind::Path(None, ref path) => path.segments.last().map_or(false, |segment| {
+            segment.identifier.name.as_str() == "f64"
+        }),
+        TyKind::Paren(ref ty) => is_f64(ty),
+        _ => false,
+    }
+}
+
+/// Collects the names of the fields declared as `f64`.
+struct FloatFields {
+    names: HashSet<Name>,
+}
+
+impl<'v> Visitor<'v> for FloatFields {
+    fn visit_struct_field(&mut self, field: &'v StructField) {
+        if let Some(ident) = field.ident {
+            if is_f64(&field.ty) {
+                self.names.insert(ident.name);
+            }
+        }
+    }
+
+    fn visit_mac(&mut self, _: &'v Mac) {
+        // macros are not expanded
+    }
+}
+
+/// Collects the names bound by a pattern.
+struct Bindings {
+    names: Vec<Name>,
+}
+
+impl<'v> Visitor<'v> for Bindings {
+    fn visit_pat(&mut self, pat: &'v Pat) {
+        if let PatKind::Ident(_, ident, _) = pat.node {
+            self.names.push(ident.node.name);
+        }
+        visit::walk_pat(self, pat);
+    }
+
+    fn visit_mac(&mut self, _: &'v Mac) {
+        // macros are not expanded
+    }
+}
+
+/// A variable in scope.
+#[derive(Debug)]
+struct Var {
+    float: bool,
+    /// The range given to the variable, if it is a parameter of a function.
+    range: Option<(f64, f64)>,
+}
+
+struct Finder<'a> {
+    candidates: Vec<Candidate<'a>>,
+    float_fields: HashSet<Name>,
+    /// The variables in scope, innermost scope last.
+    scopes: Vec<HashMap<Name, Var>>,
+    /// The expressions containing the current one, innermost last.
+    parents: Vec<&'a Expr>,
+    ignored: bool,
+    /// Whether the current node is in the body of a function, whose attributes do not apply to
+    /// the items nested in it.
+    in_body: bool,
+    /// Whether the crate or one of the modules containing the current node is ignored, which
+    /// applies to all the items they contain.
+    module_ignored: bool,
+    /// The ranges given by the attributes of the items containing the current node, which apply
+    /// to the parameters of their functions, as `ignored`.
+    ranges: HashMap<String, (f64, f64)>,
+    /// The ranges given by the attributes of the crate and the modules containing the current
+    /// node, as `module_ignored`.
+    module_ranges: HashMap<String, (f64, f64)>,
+}
+
+impl<'a> Finder<'a> {
+    fn is_float_var(&self, name: Name) -> bool {
+        let var = self.scopes.iter().rev().filter_map(|scope| scope.get(&name)).next();
+        var.map_or(false, |var| var.float)
+    }
+
+    /// Return the known ranges of the variables in scope.
+    fn ranges(&self) -> HashMap<String, (f64, f64)> {
+        let mut vars = HashMap::new();
+        for scope in &self.scopes {
+            vars.extend(scope.iter());
+        }
+
+        vars.into_iter().filter_map(|(name, var)| {
+            var.range.map(|range| (name.to_string(), range))
+        }).collect()
+    }
+
+    /// Check whether an expression looks like an `f64`, see the module documentation.
+    fn is_float(&self, expr: &Expr) -> bool {
+        match expr.node {
+            ExprKind::Lit(ref lit) => {
+                match lit.node {
+                    LitKind::Float(_, FloatTy::F64) | LitKind::FloatUnsuffixed(..) => true,
+                    _ => false,
+                }
+            }
+            ExprKind::Cast(_, ref ty) | ExprKind::Type(_, ref ty) => is_f64(ty),
+            ExprKind::Paren(ref inner) | ExprKind::Unary(UnOp::Neg, ref inner) => {
+                self.is_float(inner)
+            }
+            ExprKind::Binary(op, ref lhs, ref rhs) => {
+                ::source::binop(op.node).is_some() && (self.is_float(lhs) || self.is_float(rhs))
+            }
+            ExprKind::MethodCall(ref fun, _, ref params) => {
+                herbie_name(&fun.node.name.as_str(), params.len()).is_some()
+                && self.is_float(&params[0])
+            }
+            ExprKind::Path(None, ref path) => {
+                if path.segments.len() == 1 && !path.global {
+                    self.is_float_var(path.segments[0].identifier.name)
+                }
+                else {
+                    // e.g. `f64::EPSILON` or `std::f64::consts::PI`
+                    path.segments.iter().rev().skip(1).any(|segment| {
+                        segment.identifier.name.as_str() == "f64"
+                    })
+                }
+            }
+            ExprKind::Field(_, ref name) => self.float_fields.contains(&name.node.name),
+            ExprKind::Block(ref block) => block.expr.as_ref().map_or(false, |expr| {
+                self.is_float(expr)
+            }),
+            _ => false,
+        }
+    }
+
+    /// Bind the names of a pattern, with their type or initializer if known.
+    fn bind(&mut self, pat: &Pat, ty: Option<&Ty>, init: Option<&Expr>) {
+        match pat.node {
+            PatKind::Ident(_, ident, None) => {
+                let float = match (ty, init) {
+                    (Some(ty), _) => is_f64(ty),
+                    (None, Some(init)) => self.is_float(init),
+                    (None, None) => false,
+                };
+                self.bind_name(ident.node.name, float);
+            }
+            PatKind::Tuple(ref pats, None) => {
+                for (i, pat) in pats.iter().enumerate() {
+                    let ty = ty.and_then(|ty| match ty.node {
+                        TyKind::Tup(ref tys) => tys.get(i).map(|ty| &**ty),
+                        _ => None,
+                    });
+                    let init = init.and_then(|init| match init.node {
+                        ExprKind::Tup(ref exprs) => exprs.get(i).map(|expr| &**expr),
+                        _ => None,
+                    });
+                    self.bind(pat, ty, init);
+                }
+            }
+            _ => {
+                // the names still shadow the outer ones
+                let mut bindings = Bindings { names: Vec::new() };
+                bindings.visit_pat(pat);
+                for name in bindings.names {
+                    self.bind_name(name, false);
+                }
+            }
+        }
+    }
+
+    fn bind_name(&mut self, name: Name, float: bool) {
+        let var = Var { float: float, range: None };
+        self.scopes.last_mut().expect("There is always a scope").insert(name, var);
+    }
+
+    /// Walk an item, whose attributes apply to the items it contains, but not to those nested in
+    /// the body of a function unless it is a module.
+    fn walk_item<F: FnOnce(&mut Self)>(&mut self, attrs: &[Attribute], is_mod: bool, walk: F) {
+        let (ignored, in_body, module_ignored) = (self.ignored, self.in_body, self.module_ignored);
+        let parents = mem::replace(&mut self.parents, Vec::new());
+        let (ranges, module_ranges) = (self.ranges.clone(), self.module_ranges.clone());
+
+        if in_body {
+            self.ignored = self.module_ignored;
+            self.ranges = self.module_ranges.clone();
+        }
+        self.ignored |= attrs.iter().any(is_herbie_ignore);
+        add_ranges(&mut self.ranges, attrs);
+        if is_mod {
+            self.module_ignored = self.ignored;
+            self.module_ranges = self.ranges.clone();
+        }
+        self.in_body = false;
+
+        walk(self);
+
+        self.ignored = ignored;
+        self.in_body = in_body;
+        self.module_ignored = module_ignored;
+        self.parents = parents;
+        self.ranges = ranges;
+        self.module_ranges = module_ranges;
+    }
+
+    /// Check whether an expression is worth analysing.
+    fn is_candidate(&self, expr: &Expr) -> bool {
+        match expr.node {
+            // there is nothing to analyse in a mere variable or literal, and the expression in
+            // parentheses is analysed on its own
+            ExprKind::Field(..) | ExprKind::Lit(..) | ExprKind::Paren(..) | ExprKind::Path(..)
+            | ExprKind::TupField(..) => false,
+            // `a op= b` has type `()`, the interesting type is the one of `a`
+            ExprKind::AssignOp(_, ref lhs, _) => self.is_float(lhs),
+            _ => self.is_float(expr),
+        }
+    }
+}
+
+impl<'a> Visitor<'a> for Finder<'a> {
+    fn visit_item(&mut self, item: &'a Item) {
+        if let ItemKind::Const(ref ty, _) = item.node {
+            self.bind_name(item.ident.name, is_f64(ty));
+        }
+        if let ItemKind::Static(ref ty, _, _) = item.node {
+            self.bind_name(item.ident.name, is_f64(ty));
+        }
+
+        let is_mod = if let ItemKind::Mod(..) = item.node { true } else { false };
+        self.walk_item(&item.attrs, is_mod, |this| visit::walk_item(this, item));
+    }
+
+    fn visit_trait_item(&mut self, item: &'a TraitItem) {
+        self.walk_item(&item.attrs, false, |this| visit::walk_trait_item(this, item));
+    }
+
+    fn visit_impl_item(&mut self, item: &'a ImplItem) {
+        self.walk_item(&item.attrs, false, |this| visit::walk_impl_item(this, item));
+    }
+
+    fn visit_fn(&mut self, kind: FnKind<'a>, decl: &'a FnDecl, body: &'a Block, span: Span,
+                _: NodeId) {
+        self.scopes.push(HashMap::new());
+        for arg in &decl.inputs {
+            self.bind(&arg.pat, Some(&arg.ty), None);
+        }
+
+        // closures have no attributes, their parameters shadow the ranges of the item
+        let is_closure = match kind {
+            FnKind::Closure => true,
+            _ => false,
+        };
+        if !is_closure {
+            let scope = self.scopes.last_mut().expect("There is always a scope");
+            for (name, var) in scope.iter_mut() {
+                var.range = self.ranges.get(&*name.as_str()).cloned();
+            }
+        }
+
+        visit::walk_fn(self, kind, decl, body, span);
+        self.scopes.pop();
+    }
+
+    fn visit_block(&mut self, block: &'a Block) {
+        let in_body = self.in_body;
+        self.in_body = true;
+        self.scopes.push(HashMap::new());
+
+        visit::walk_block(self, block);
+
+        self.scopes.pop();
+        self.in_body = in_body;
+    }
+
+    fn visit_local(&mut self, local: &'a Local) {
+        let ignored = self.ignored;
+        self.ignored |= local.attrs().iter().any(is_herbie_ignore);
+
+        if let Some(ref init) = local.init {
+            self.visit_expr(init);
+        }
+        let (ty, init) = (local.ty.as_ref(), local.init.as_ref());
+        self.bind(&local.pat, ty.map(|ty| &**ty), init.map(|init| &**init));
+
+        self.ignored = ignored;
+    }
+
+    fn visit_expr(&mut self, expr: &'a Expr) {
+        let ignored = self.ignored;
+        self.ignored |= expr.attrs().iter().any(is_herbie_ignore);
+
+        if !self.ignored && self.is_candidate(expr) {
+            let ranges = self.ranges();
+            self.candidates.push(Candidate {
+                expr: expr,
+                parent: self.parents.last().cloned(),
+                ranges: ranges,
+            });
+        }
+
+        self.parents.push(expr);
+        visit::walk_expr(self, expr);
+        self.parents.pop();
+
+        self.ignored = ignored;
+    }
+
+    fn visit_mac(&mut self, _: &'a Mac) {
+        // macros are not expanded
+    }
+}
@@ -0,0 +1,191 @@
+//! Applying suggestions to the source files, and previewing them as unified diffs.
+
+use herbie_lint_core::suggestion::Suggestion;
+use std::cmp;
+
+/// Number of unchanged lines shown around the changes of a diff.
+const CONTEXT: usize = 3;
+
+/// Follows the last line of a file which does not end with a newline in a diff.
+const NO_NEWLINE: &'static str = "\\ No newline at end of file\n";
+
+/// A replacement of the code between two byte offsets of a file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Edit {
+    pub byte_start: usize,
+    pub byte_end: usize,
+    pub replacement: String,
+}
+
+impl<'a> From<&'a Suggestion> for Edit {
+    fn from(suggestion: &'a Suggestion) -> Edit {
+        Edit {
+            byte_start: suggestion.byte_start,
+            byte_end: suggestion.byte_end,
+            replacement: suggestion.suggested_replacement.clone(),
+        }
+    }
+}
+
+/// Two edits which change the same code, by their index in the edits given to `apply`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Overlap(pub usize, pub usize);
+
+/// Sort edits by their position, and check that they do not overlap.
+fn sort(edits: &[Edit]) -> Result<Vec<(usize, &Edit)>, Overlap> {
+    let mut sorted = edits.iter().enumerate().collect::<Vec<_>>();
+    sorted.sort_by_key(|&(_, edit)| (edit.byte_start, edit.byte_end));
+
+    for pair in sorted.windows(2) {
+        let ((lidx, lhs), (ridx, rhs)) = (pair[0], pair[1]);
+        // two insertions at the same place would also be ambiguous
+        if lhs.byte_end > rhs.byte_start || lhs.byte_start == rhs.byte_start {
+            return Err(Overlap(lidx, ridx));
+        }
+    }
+
+    Ok(sorted)
+}
+
+/// Apply edits to `text`, which is left as is outside of the edited code.
+pub fn apply(text: &str, edits: &[Edit]) -> Result<String, Overlap> {
+    let sorted = try!(sort(edits));
+    Ok(apply_sorted(text, 0, sorted.iter().map(|&(_, edit)| edit)))
+}
+
+/// Apply sorted edits to `text`, which starts at the byte offset `offset` of the file.
+fn apply_sorted<'a, I: Iterator<Item = &'a Edit>>(text: &str, offset: usize, edits: I) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut last = 0;
+
+    for edit in edits {
+        result.push_str(&text[last..edit.byte_start - offset]);
+        result.push_str(&edit.replacement);
+        last = edit.byte_end - offset;
+    }
+    result.push_str(&text[last..]);
+
+    result
+}
+
+/// Consecutive lines changed by some edits, the lines of an edit being adjacent or shared with
+/// those of the previous ones.
+struct Region<'a> {
+    /// The first line, 0-based.
+    first: usize,
+    /// The line after the last one.
+    end: usize,
+    edits: Vec<&'a Edit>,
+}
+
+/// Return the unified diff between `text` and the result of applying `edits` to it, or an empty
+/// string if there is no change. The lines of the diff end with a newline.
+pub fn diff(file_name: &str, text: &str, edits: &[Edit]) -> Result<String, Overlap> {
+    let sorted = try!(sort(edits));
+
+    // the offset of the start of each line
+    let mut line_starts = vec![0];
+    line_starts.extend(text.match_indices('\n').map(|(idx, _)| idx + 1).filter(|&idx| {
+        idx < text.len()
+    }));
+    let line_of = |offset: usize| match line_starts.binary_search(&offset) {
+        Ok(line) => line,
+        Err(line) => line - 1,
+    };
+    let line = |line: usize| {
+        let end = line_starts.get(line + 1).map_or(text.len(), |&end| end);
+        text[line_starts[line]..end].trim_right_matches('\n')
+    };
+
+    let mut regions: Vec<Region> = Vec::new();
+    for &(_, edit) in &sorted {
+        let first = line_of(edit.byte_start);
+        let end = line_of(cmp::max(edit.byte_end.saturating_sub(1), edit.byte_start)) + 1;
+
+        if let Some(region) = regions.last_mut() {
+            if first <= region.end {
+                region.end = end;
+                region.edits.push(edit);
+                continue;
+            }
+        }
+
+        regions.push(Region { first: first, end: end, edits: vec![edit] });
+    }
+
+    let mut result = String::new();
+    if regions.is_empty() {
+        return Ok(result);
+    }
+
+    result.push_str(&format!("--- a/{}\n+++ b/{}\n", file_name, file_name));
+
+    // the number of lines added by the previous hunks
+    let mut delta = 0isize;
+    let mut regions = regions.into_iter().peekable();
+    while let Some(mut region) = regions.next() {
+        let start = region.first.saturating_sub(CONTEXT);
+        let mut hunk = String::new();
+        let mut old_count = region.first - start;
+        let mut new_count = old_count;
+
+        for idx in start..region.first {
+            hunk.push_str(&format!(" {}\n", line(idx)));
+        }
+
+        loop {
+            let text_start = line_starts[region.first];
+            let text_end = line_starts.get(region.end).map_or(text.len(), |&end| end);
+            let old = &text[text_start..text_end];
+            let new = apply_sorted(old, text_start, region.edits.iter().cloned());
+
+            for old_line in old.lines() {
+                hunk.push_str(&format!("-{}\n", old_line));
+            }
+            // only possible on the last line of the file
+            if !old.ends_with('\n') {
+                hunk.push_str(NO_NEWLINE);
+            }
+            for new_line in new.lines() {
+                hunk.push_str(&format!("+{}\n", new_line));
+            }
+            if !new.is_empty() && !new.ends_with('\n') {
+                hunk.push_str(NO_NEWLINE);
+            }
+            old_count += region.end - region.first;
+            new_count += new.lines().count();
+
+            // regions whose contexts touch are in the same hunk
+            let next = match regions.peek() {
+                Some(next) if next.first <= region.end + 2 * CONTEXT => Some(next.first),
+                _ => None,
+            };
+            let context_end = next.unwrap_or(cmp::min(region.end + CONTEXT, line_starts.len()));
+
+            for idx in region.end..context_end {
+                hunk.push_str(&format!(" {}\n", line(idx)));
+            }
+            if context_end > region.end && context_end == line_starts.len()
+            && !text.ends_with('\n') {
+                hunk.push_str(NO_NEWLINE);
+            }
+            old_count += context_end - region.end;
+            new_count += context_end - region.end;
+
+            match next {
+                Some(..) => region = regions.next().expect("The next region was peeked"),
+                None => break,
+            }
+        }
+
+        result.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            start + 1, old_count, (start as isize + delta) as usize + 1, new_count
+        ));
+        result.push_str(&hunk);
+
+        delta += new_count as isize - old_count as isize;
+    }
+
+    Ok(result)
+}
@@ -0,0 +1,25 @@
+//! A standalone *herbie-lint*, which parses Rust source files instead of being a rustc plugin, so
+//! that it works on stable Rust. Without the types of the crate, floating point expressions are
+//! found with heuristics, see `finder`.
+
+#![cfg_attr(feature="clippy", feature(plugin))]
+#![cfg_attr(feature="clippy", plugin(clippy))]
+
+#![deny(
+    missing_copy_implementations,
+    missing_debug_implementations,
+    trivial_casts,
+    trivial_numeric_casts,
+    unsafe_code,
+    unused_import_braces,
+    unused_qualifications,
+)]
+
+extern crate herbie_lint_core;
+extern crate syntex_syntax as syntax;
+
+pub mod finder;
+pub mod fix;
+pub mod lint;
+pub mod review;
+pub mod source;
@@ -0,0 +1,120 @@
+//! The analysis of the expressions found in a crate, as done by the plugin: they are matched with
+//! the database, or given to Herbie.
+
+use finder::{self, Candidate};
+use herbie_lint_core::check::{Checker, Message};
+use herbie_lint_core::conf::Conf;
+use herbie_lint_core::herbie::InitError;
+use herbie_lint_core::lisp::LispExpr;
+use herbie_lint_core::matcher::{self, Bindings};
+use herbie_lint_core::report::Entry;
+use herbie_lint_core::suggestion::Suggestion;
+use source::{self, AstSource};
+use syntax::ast::{Crate, Expr, ExprKind};
+use syntax::codemap::{CodeMap, Span};
+use syntax::parse::ParseSess;
+
+#[derive(Debug)]
+pub struct Linter {
+    checker: Checker,
+    /// The suggestions reported so far.
+    pub suggestions: Vec<Suggestion>,
+    /// The analysed expressions, for the report.
+    pub entries: Vec<Entry>,
+    /// The right-hand sides of the compound assignments which were reported, which are then not
+    /// reported on their own.
+    assigned: Vec<Span>,
+}
+
+impl Linter {
+    /// Create a linter using the databases of `conf`.
+    pub fn new(conf: Conf) -> Result<Linter, InitError> {
+        Ok(Linter {
+            checker: try!(Checker::new(conf)),
+            suggestions: Vec::new(),
+            entries: Vec::new(),
+            assigned: Vec::new(),
+        })
+    }
+
+    pub fn conf(&self) -> &Conf {
+        self.checker.conf()
+    }
+
+    /// Check the expressions of a crate parsed in `sess`, whose diagnostic handler reports the
+    /// unstable expressions.
+    pub fn check_crate(&mut self, sess: &ParseSess, krate: &Crate) {
+        for candidate in finder::find_exprs(krate) {
+            self.check_expr(sess, &candidate);
+        }
+    }
+
+    fn check_expr(&mut self, sess: &ParseSess, candidate: &Candidate) {
+        let (expr, handler) = (candidate.expr, &sess.span_diagnostic);
+        let codemap = sess.codemap();
+        // the suggestion for `a op= b` already rewrites `b`, assignments are found first
+        if self.assigned.contains(&expr.span) {
+            return;
+        }
+
+        let outcome = self.checker.check(
+            &AstSource::with_ranges(codemap, &candidate.ranges),
+            expr,
+            source::location(codemap, expr.span),
+            |cmdout, bindings| to_rust(codemap, candidate, cmdout, bindings),
+            || {
+                handler.span_note_without_error(
+                    expr.span,
+                    "Calling Herbie on the following expression, it might take a while"
+                );
+            }
+        );
+
+        for message in outcome.messages {
+            match message {
+                Message::Warning(message) => handler.span_warn(expr.span, &message),
+                Message::Note(message) => handler.span_note_without_error(expr.span, &message),
+            }
+        }
+
+        self.entries.extend(outcome.entries);
+
+        if let Some(suggestion) = outcome.suggestion {
+            handler.struct_span_warn(expr.span, &suggestion.lint_message())
+                   .span_suggestion(expr.span,
+                                    &suggestion.message,
+                                    suggestion.suggested_replacement.clone())
+                   .emit();
+
+            if let ExprKind::AssignOp(_, _, ref rhs) = expr.node {
+                let mut rhs = rhs;
+                while let ExprKind::Paren(ref inner) = rhs.node {
+                    rhs = inner;
+                }
+                self.assigned.push(rhs.span);
+            }
+            self.suggestions.push(suggestion);
+        }
+    }
+}
+
+/// Print `cmdout` as Rust code replacing the candidate, unless it has no Rust equivalent.
+fn to_rust(
+    codemap: &CodeMap,
+    candidate: &Candidate,
+    cmdout: &LispExpr,
+    bindings: &Bindings<Expr>
+) -> Option<String> {
+    let expr = candidate.expr;
+    let source = AstSource::new(codemap);
+    match expr.node {
+        ExprKind::AssignOp(_, ref lhs, _) => {
+            matcher::to_rust_assign(&source, cmdout, bindings, lhs, source::position(codemap, lhs))
+        }
+        _ => {
+            let position = source::position(codemap, expr);
+            let prec = source::context_prec(candidate.parent, expr);
+            matcher::to_rust(&source, cmdout, bindings, position, prec)
+        }
+    }
+}
@@ -0,0 +1,262 @@
+extern crate herbie_lint_cli;
+extern crate herbie_lint_core;
+extern crate syntex_syntax as syntax;
+
+use herbie_lint_cli::fix::{self, Edit, Overlap};
+use herbie_lint_cli::lint::Linter;
+use herbie_lint_cli::review;
+use herbie_lint_core::suggestion::{Applicability, Suggestion};
+use herbie_lint_core::{conf, herbie, report, sarif, suggestion};
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::process;
+use syntax::parse::{self, ParseSess};
+
+const USAGE: &'static str = "\
+Check Rust source files for numerically unstable expressions.
+
+Usage:
+    herbie-lint [--diff | --fix | --review] [FILE...]
+
+Each FILE is the root of a crate, e.g. `src/lib.rs`, whose modules are checked
+too. Defaults to `src/lib.rs` and `src/main.rs` if they exist.
+
+The configuration is the one of the plugin, read from `Herbie.toml` or from
+`Cargo.toml` in the current directory and its parents. Exits with status 1 if
+an unstable expression was found, or with `--fix` and `--review` if a
+suggestion was not applied or rejected.
+
+Options:
+    --diff      Print the changes `--fix` would make as a unified diff
+    --fix       Apply the suggestions which are exact rewrites to the files, and
+                print the changes as a unified diff. The other suggestions are
+                only reported, and files where suggestions overlap are left
+                untouched
+    --review    Walk through the suggestions, and accept, reject or skip each
+                of them. The accepted suggestions are applied to the files, and
+                the expressions of the rejected ones are recorded in the ignore
+                file, `Herbie.ignore` by default, so that they are not reported
+                anymore
+";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Mode {
+    Check,
+    Diff,
+    Fix,
+    Review,
+}
+
+fn main() {
+    let mut args = env::args().skip(1).collect::<Vec<_>>();
+
+    let mode = match args.first().map(|arg| &arg[..]) {
+        Some("--diff") => Mode::Diff,
+        Some("--fix") => Mode::Fix,
+        Some("--review") => Mode::Review,
+        _ => Mode::Check,
+    };
+    if mode != Mode::Check {
+        args.remove(0);
+    }
+
+    if args.iter().any(|arg| arg.starts_with('-')) {
+        usage();
+    }
+
+    let files = if args.is_empty() {
+        let files = ["src/lib.rs", "src/main.rs"].iter()
+                                                  .filter(|file| Path::new(file).exists())
+                                                  .map(|&file| file.to_owned())
+                                                  .collect::<Vec<_>>();
+        if files.is_empty() {
+            usage();
+        }
+        files
+    }
+    else {
+        args
+    };
+
+    let conf = match conf::read_conf() {
+        Ok(conf) => conf,
+        Err(err) => fail(&err.to_string()),
+    };
+
+    for warning in &conf.warnings {
+        let _ = writeln!(io::stderr(), "herbie-lint: warning: Herbie configuration: {}", warning);
+    }
+
+    let mut linter = match Linter::new(conf) {
+        Ok(linter) => linter,
+        Err(err) => fail(&format!("Could not initialize Herbie-Lint: {}", err)),
+    };
+
+    let sess = ParseSess::new();
+    for file in &files {
+        match parse::parse_crate_from_file(Path::new(file), vec![], &sess) {
+            Ok(krate) => linter.check_crate(&sess, &krate),
+            Err(mut err) => {
+                err.emit();
+                fail(&format!("Could not parse {}", file));
+            }
+        }
+    }
+
+    if let Some(ref report_path) = linter.conf().report_path {
+        if let Err(err) = report::write(report_path, &linter.entries) {
+            fail(&format!("Could not write report to {}: {}", report_path, err));
+        }
+    }
+
+    if let Some(ref fixes_path) = linter.conf().fixes_path {
+        if let Err(err) = suggestion::write_json(fixes_path, &linter.suggestions) {
+            fail(&format!("Could not write suggestions to {}: {}", fixes_path, err));
+        }
+    }
+
+    if let Some(ref sarif_path) = linter.conf().sarif_path {
+        if let Err(err) = sarif::write(sarif_path, &linter.suggestions) {
+            fail(&format!("Could not write SARIF file {}: {}", sarif_path, err));
+        }
+    }
+
+    let success = match mode {
+        Mode::Check => linter.suggestions.is_empty(),
+        Mode::Diff => fix(&linter.suggestions, false) && linter.suggestions.is_empty(),
+        Mode::Fix => fix(&linter.suggestions, true),
+        Mode::Review => review(&linter),
+    };
+
+    if !success {
+        process::exit(1);
+    }
+}
+
+/// Print the changes of the suggestions which are exact rewrites as a unified diff, and apply
+/// them if `write`. Return whether every suggestion was applicable.
+fn fix(suggestions: &[Suggestion], write: bool) -> bool {
+    let mut complete = true;
+    let mut files = BTreeMap::new();
+
+    for suggestion in suggestions {
+        if suggestion.applicability == Applicability::MachineApplicable {
+            files.entry(&suggestion.file_name[..]).or_insert_with(Vec::new).push(suggestion);
+        }
+        else {
+            complete = false;
+        }
+    }
+
+    for (file_name, suggestions) in files {
+        let edits = suggestions.iter().map(|&s| Edit::from(s)).collect::<Vec<_>>();
+
+        let text = match read_file(file_name) {
+            Ok(text) => text,
+            Err(err) => {
+                warn(&format!("Could not read {}: {}", file_name, err));
+                complete = false;
+                continue;
+            }
+        };
+
+        match fix::diff(file_name, &text, &edits) {
+            Ok(diff) => print!("{}", diff),
+            Err(Overlap(lhs, rhs)) => {
+                warn(&format!(
+                    "Not fixing {}: the suggestions at {}:{} and {}:{} overlap",
+                    file_name,
+                    suggestions[lhs].line_start,
+                    suggestions[lhs].column_start,
+                    suggestions[rhs].line_start,
+                    suggestions[rhs].column_start
+                ));
+                complete = false;
+                continue;
+            }
+        }
+
+        if write {
+            let fixed = fix::apply(&text, &edits).expect("The edits were checked by `diff`");
+            complete &= write_file(file_name, &fixed);
+        }
+    }
+
+    complete
+}
+
+/// Review the suggestions interactively, and apply the accepted ones. Return whether every
+/// suggestion was either accepted or rejected.
+fn review(linter: &Linter) -> bool {
+    let mut ignores = match herbie::read_ignores(linter.conf()) {
+        Ok(ignores) => ignores,
+        Err(err) => fail(&err.to_string()),
+    };
+
+    let mut texts = HashMap::new();
+    for suggestion in &linter.suggestions {
+        if !texts.contains_key(&suggestion.file_name) {
+            match read_file(&suggestion.file_name) {
+                Ok(text) => texts.insert(suggestion.file_name.clone(), text),
+                Err(err) => fail(&format!("Could not read {}: {}", suggestion.file_name, err)),
+            };
+        }
+    }
+
+    let stdin = io::stdin();
+    let (accepted, summary) = match review::review(
+        &linter.suggestions, &texts, &mut ignores, stdin.lock(), io::stdout()
+    ) {
+        Ok(result) => result,
+        Err(err) => fail(&format!("Review failed: {}", err)),
+    };
+
+    let mut complete = summary.skipped == 0;
+    for (file_name, edits) in accepted {
+        let fixed = fix::apply(&texts[&file_name], &edits).expect("Overlaps are skipped");
+        complete &= write_file(&file_name, &fixed);
+    }
+
+    println!(
+        "{} accepted, {} rejected, {} skipped",
+        summary.accepted,
+        summary.rejected,
+        summary.skipped
+    );
+
+    complete
+}
+
+fn read_file(file_name: &str) -> io::Result<String> {
+    let mut text = String::new();
+    try!(File::open(file_name).and_then(|mut file| file.read_to_string(&mut text)));
+    Ok(text)
+}
+
+/// Replace the content of a file, and return whether it succeeded.
+fn write_file(file_name: &str, text: &str) -> bool {
+    match File::create(file_name).and_then(|mut file| file.write_all(text.as_bytes())) {
+        Ok(()) => true,
+        Err(err) => {
+            warn(&format!("Could not write {}: {}", file_name, err));
+            false
+        }
+    }
+}
+
+fn usage() -> ! {
+    let _ = write!(io::stderr(), "{}", USAGE);
+    process::exit(2)
+}
+
+fn warn(message: &str) {
+    let _ = writeln!(io::stderr(), "herbie-lint: {}", message);
+}
+
+fn fail(message: &str) -> ! {
+    let _ = writeln!(io::stderr(), "herbie-lint: {}", message);
+    process::exit(2)
+}
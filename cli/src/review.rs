@@ -0,0 +1,108 @@
+//! The interactive review of the suggestions: each of them is either accepted and applied,
+//! rejected and recorded in the ignore file, or skipped.
+
+use fix::{self, Edit};
+use herbie_lint_core::ignore::Ignores;
+use herbie_lint_core::suggestion::{Applicability, Suggestion};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, Error as IOError, Write};
+
+const PROMPT: &'static str = "Accept, reject (never report it again), skip, or quit? [a/r/s/q] ";
+
+/// What the reviewer decided for each suggestion.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Summary {
+    pub accepted: usize,
+    pub rejected: usize,
+    pub skipped: usize,
+}
+
+/// Walk through the suggestions, showing each of them on `output` and reading the decision of the
+/// reviewer from `input`, one line per suggestion. `texts` has the content of each file of the
+/// suggestions. The expressions of the rejected suggestions are added to `ignores`, and the
+/// accepted suggestions are returned as edits by file. Suggestions which overlap one that was
+/// already accepted are skipped, as well as the remaining ones if the reviewer quits or the input
+/// ends.
+pub fn review<R: BufRead, W: Write>(
+    suggestions: &[Suggestion],
+    texts: &HashMap<String, String>,
+    ignores: &mut Ignores,
+    mut input: R,
+    mut output: W
+) -> Result<(BTreeMap<String, Vec<Edit>>, Summary), IOError> {
+    let mut accepted: BTreeMap<String, Vec<Edit>> = BTreeMap::new();
+    let mut summary = Summary::default();
+    let mut done = false;
+
+    for (idx, suggestion) in suggestions.iter().enumerate() {
+        if done {
+            summary.skipped += 1;
+            continue;
+        }
+
+        let text = &texts[&suggestion.file_name];
+        let original = &text[suggestion.byte_start..suggestion.byte_end];
+        let edit = Edit::from(suggestion);
+
+        try!(writeln!(output, "[{}/{}] {}:{}:{}: {}",
+                      idx + 1,
+                      suggestions.len(),
+                      suggestion.file_name,
+                      suggestion.line_start,
+                      suggestion.column_start,
+                      suggestion.lint_message()));
+
+        let mut edits = accepted.get(&suggestion.file_name).cloned().unwrap_or_else(Vec::new);
+        edits.push(edit.clone());
+        if fix::apply(text, &edits).is_err() {
+            try!(writeln!(output, "Skipped: it overlaps a suggestion which was accepted\n"));
+            summary.skipped += 1;
+            continue;
+        }
+
+        for line in original.lines() {
+            try!(writeln!(output, "- {}", line));
+        }
+        for line in suggestion.suggested_replacement.lines() {
+            try!(writeln!(output, "+ {}", line));
+        }
+        if suggestion.applicability != Applicability::MachineApplicable {
+            try!(writeln!(output, "{}", suggestion.message));
+        }
+
+        loop {
+            try!(write!(output, "{}", PROMPT));
+            try!(output.flush());
+
+            let mut answer = String::new();
+            if try!(input.read_line(&mut answer)) == 0 {
+                try!(writeln!(output, ""));
+                answer = "q".into();
+            }
+
+            match answer.trim() {
+                "a" | "accept" => {
+                    let file_name = suggestion.file_name.clone();
+                    accepted.entry(file_name).or_insert_with(Vec::new).push(edit);
+                    summary.accepted += 1;
+                }
+                "r" | "reject" => {
+                    try!(ignores.add(&suggestion.file_name, original));
+                    summary.rejected += 1;
+                }
+                "s" | "skip" => summary.skipped += 1,
+                "q" | "quit" => {
+                    summary.skipped += 1;
+                    done = true;
+                }
+                _ => continue,
+            }
+
+            break;
+        }
+
+        try!(writeln!(output, ""));
+    }
+
+    Ok((accepted, summary))
+}
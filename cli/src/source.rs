@@ -0,0 +1,246 @@
+//! The AST of a parsed source file, as a source language for the matcher.
+
+use herbie_lint_core::lisp::{self, Position, Prec, herbie_name};
+use herbie_lint_core::matcher::{Shape, Source};
+use herbie_lint_core::suggestion::Location;
+use std::collections::HashMap;
+use std;
+use syntax::ast::{BinOpKind, Expr, ExprKind, FloatTy, LitKind, Name, Path, UnOp};
+use syntax::codemap::{CodeMap, Span};
+
+#[derive(Clone, Copy)]
+pub struct AstSource<'a> {
+    codemap: &'a CodeMap,
+    /// The known ranges of the variables, see `Candidate::ranges`.
+    ranges: Option<&'a HashMap<String, (f64, f64)>>,
+}
+
+impl<'a> AstSource<'a> {
+    pub fn new(codemap: &'a CodeMap) -> AstSource<'a> {
+        AstSource { codemap: codemap, ranges: None }
+    }
+
+    /// A source where the variables have the known `ranges`.
+    pub fn with_ranges(
+        codemap: &'a CodeMap,
+        ranges: &'a HashMap<String, (f64, f64)>
+    ) -> AstSource<'a> {
+        AstSource { codemap: codemap, ranges: Some(ranges) }
+    }
+}
+
+impl<'a> std::fmt::Debug for AstSource<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        f.write_str("AstSource")
+    }
+}
+
+/// Identifies the variables of the AST: unqualified paths, fields of paths, and casts.
+#[derive(Debug)]
+pub enum Key {
+    /// A cast, by its snippet.
+    Cast(String),
+    Field(Path, Name),
+    Path(Path),
+    TupField(Path, usize),
+}
+
+/// Paths are compared without their spans.
+fn same_path(lhs: &Path, rhs: &Path) -> bool {
+    lhs.global == rhs.global && lhs.segments == rhs.segments
+}
+
+impl PartialEq for Key {
+    fn eq(&self, other: &Key) -> bool {
+        match (self, other) {
+            (&Key::Cast(ref lhs), &Key::Cast(ref rhs)) => lhs == rhs,
+            (&Key::Field(ref lpath, lname), &Key::Field(ref rpath, rname)) => {
+                same_path(lpath, rpath) && lname == rname
+            }
+            (&Key::Path(ref lpath), &Key::Path(ref rpath)) => same_path(lpath, rpath),
+            (&Key::TupField(ref lpath, lidx), &Key::TupField(ref rpath, ridx)) => {
+                same_path(lpath, rpath) && lidx == ridx
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Convert an arithmetic operator of the AST.
+pub fn binop(op: BinOpKind) -> Option<lisp::BinOp> {
+    match op {
+        BinOpKind::Add => Some(lisp::BinOp::Add),
+        BinOpKind::Sub => Some(lisp::BinOp::Sub),
+        BinOpKind::Mul => Some(lisp::BinOp::Mul),
+        BinOpKind::Div => Some(lisp::BinOp::Div),
+        BinOpKind::Rem => Some(lisp::BinOp::Rem),
+        _ => None,
+    }
+}
+
+/// Return the unqualified path an expression is, if any.
+fn unqualified_path(expr: &Expr) -> Option<&Path> {
+    match expr.node {
+        ExprKind::Path(None, ref path) => Some(path),
+        _ => None,
+    }
+}
+
+impl<'a> Source for AstSource<'a> {
+    type Expr = Expr;
+    type Key = Key;
+
+    fn shape<'e>(&self, expr: &'e Expr) -> Shape<'e, Expr, Key> {
+        match expr.node {
+            // `a op= b` is analysed as `a op b`
+            ExprKind::Binary(op, ref lhs, ref rhs) | ExprKind::AssignOp(op, ref lhs, ref rhs) => {
+                binop(op.node).map_or(Shape::Unknown, |op| Shape::Binary(op, lhs, rhs))
+            }
+            ExprKind::Cast(ref inner, _) => {
+                // without types, a cast from `f64` cannot be told from a cast from an integer
+                match cast_lit(inner) {
+                    Some(lit) => Shape::Const(lit),
+                    None => Shape::Var(Key::Cast(self.snippet(expr))),
+                }
+            }
+            ExprKind::Field(ref obj, ref name) => {
+                unqualified_path(obj).map_or(Shape::Opaque, |path| {
+                    Shape::Var(Key::Field(path.clone(), name.node.name))
+                })
+            }
+            ExprKind::Lit(ref lit) => {
+                match lit.node {
+                    LitKind::Float(ref f, FloatTy::F64) | LitKind::FloatUnsuffixed(ref f) => {
+                        f.parse().ok().map_or(Shape::Unknown, Shape::Lit)
+                    }
+                    _ => Shape::Unknown,
+                }
+            }
+            ExprKind::MethodCall(ref fun, ref types, ref params) if types.is_empty() => {
+                match herbie_name(&fun.node.name.as_str(), params.len()) {
+                    Some(name) => Shape::Fun(name, params.iter().map(|param| &**param).collect()),
+                    None => Shape::Unknown,
+                }
+            }
+            ExprKind::Paren(ref inner) => Shape::Transparent(inner),
+            ExprKind::Path(None, ref path) => Shape::Var(Key::Path(path.clone())),
+            ExprKind::TupField(ref tup, ref idx) => {
+                unqualified_path(tup).map_or(Shape::Opaque, |path| {
+                    Shape::Var(Key::TupField(path.clone(), idx.node))
+                })
+            }
+            ExprKind::Unary(UnOp::Neg, ref inner) => Shape::Unary(lisp::UnOp::Neg, inner),
+            ExprKind::Unary(UnOp::Deref, _) => Shape::Opaque,
+            _ => Shape::Unknown,
+        }
+    }
+
+    fn snippet(&self, expr: &Expr) -> String {
+        snippet(self.codemap, expr.span)
+    }
+
+    fn prec(&self, expr: &Expr) -> Prec {
+        prec(expr)
+    }
+
+    fn range(&self, expr: &Expr) -> Option<(f64, f64)> {
+        self.ranges.and_then(|ranges| var_name(expr).and_then(|name| ranges.get(&name).cloned()))
+    }
+}
+
+/// Return the name of a variable.
+fn var_name(expr: &Expr) -> Option<String> {
+    unqualified_path(expr).and_then(|path| {
+        if path.segments.len() == 1 && !path.global {
+            Some(path.segments[0].identifier.name.to_string())
+        }
+        else {
+            None
+        }
+    })
+}
+
+/// Return the precedence of an expression.
+fn prec(expr: &Expr) -> Prec {
+    match expr.node {
+        ExprKind::Binary(op, _, _) => binop(op.node).map_or(Prec::Min, Prec::of_binop),
+        ExprKind::Cast(..) | ExprKind::Type(..) => Prec::Cast,
+        ExprKind::AddrOf(..) | ExprKind::Box(..) | ExprKind::Unary(..) => Prec::Unary,
+        ExprKind::Assign(..) | ExprKind::AssignOp(..) | ExprKind::Block(..)
+        | ExprKind::Closure(..) | ExprKind::ForLoop(..) | ExprKind::If(..)
+        | ExprKind::IfLet(..) | ExprKind::Loop(..) | ExprKind::Match(..) | ExprKind::Range(..)
+        | ExprKind::While(..) | ExprKind::WhileLet(..) => Prec::Min,
+        _ => Prec::Max,
+    }
+}
+
+/// Return the value of a constant casted to `f64`, e.g. `2 as f64` or `1.5f32 as f64`.
+fn cast_lit(expr: &Expr) -> Option<f64> {
+    if let ExprKind::Lit(ref lit) = expr.node {
+        match lit.node {
+            LitKind::Int(i, _) => Some(i as f64),
+            LitKind::Float(ref f, FloatTy::F32) => f.parse::<f32>().ok().map(|f| f as f64),
+            LitKind::Float(ref f, FloatTy::F64) | LitKind::FloatUnsuffixed(ref f) => f.parse().ok(),
+            _ => None,
+        }
+    }
+    else {
+        None
+    }
+}
+
+/// Return the code of a span, or `..` if it is not available.
+pub fn snippet(codemap: &CodeMap, span: Span) -> String {
+    codemap.span_to_snippet(span).unwrap_or_else(|_| "..".into())
+}
+
+/// Return where the code of an expression starts.
+pub fn position(codemap: &CodeMap, expr: &Expr) -> Position {
+    let loc = codemap.lookup_char_pos(expr.span.lo);
+    let indent = loc.file.get_line(loc.line - 1).map_or(0, |line| {
+        line.chars().take_while(|c| c.is_whitespace()).count()
+    });
+
+    Position { indent: indent, column: loc.col.0 }
+}
+
+/// Return where the code of a span is.
+pub fn location(codemap: &CodeMap, span: Span) -> Location {
+    let lo = codemap.lookup_char_pos(span.lo);
+    let hi = codemap.lookup_char_pos(span.hi);
+
+    Location {
+        file_name: lo.file.name.clone(),
+        byte_start: (span.lo.0 - lo.file.start_pos.0) as usize,
+        byte_end: (span.hi.0 - lo.file.start_pos.0) as usize,
+        line_start: lo.line,
+        line_end: hi.line,
+        column_start: lo.col.0 + 1,
+        column_end: hi.col.0 + 1,
+    }
+}
+
+/// Return the precedence an expression needs to have in order not to be parenthesized in its
+/// parent expression, and whether it needs to be strictly higher. The parser does not number the
+/// nodes, so children are told apart by their spans.
+pub fn context_prec(parent: Option<&Expr>, expr: &Expr) -> (Prec, bool) {
+    let parent = if let Some(parent) = parent {
+        parent
+    }
+    else {
+        return (Prec::Min, false);
+    };
+
+    match parent.node {
+        ExprKind::Binary(op, ref lhs, _) => {
+            (binop(op.node).map_or(Prec::Min, Prec::of_binop), lhs.span != expr.span)
+        }
+        ExprKind::AddrOf(..) | ExprKind::Box(..) | ExprKind::Unary(..) => (Prec::Unary, false),
+        ExprKind::Cast(..) | ExprKind::Type(..) => (Prec::Cast, false),
+        ExprKind::Field(..) | ExprKind::TupField(..) => (Prec::Max, false),
+        ExprKind::Index(ref base, _) if base.span == expr.span => (Prec::Max, false),
+        ExprKind::MethodCall(_, _, ref params) if params[0].span == expr.span => (Prec::Max, false),
+        // including parentheses, which are kept around the suggestion
+        _ => (Prec::Min, false),
+    }
+}
@@ -0,0 +1,101 @@
+extern crate herbie_lint_cli;
+extern crate syntex_syntax as syntax;
+
+use herbie_lint_cli::finder;
+use std::collections::HashMap;
+use syntax::parse::{self, ParseSess};
+
+/// Return the snippets of the expressions found in `code`.
+fn find(code: &str) -> Vec<String> {
+    let sess = ParseSess::new();
+    let krate = parse::parse_crate_from_source_str("test.rs".into(), code.into(), vec![], &sess)
+        .unwrap_or_else(|_| panic!("Could not parse {}", code));
+
+    finder::find_exprs(&krate).iter().map(|candidate| {
+        sess.codemap().span_to_snippet(candidate.expr.span).unwrap()
+    }).collect()
+}
+
+#[test]
+fn test_floats() {
+    assert_eq!(find("fn f(a: f64, b: u32) { a + 1.; b + 1; }"), vec!["a + 1."]);
+    assert_eq!(find("fn f() { let a = 2.; let b = 2; a * b; b * b; }"), vec!["a * b"]);
+    assert_eq!(find("fn f(n: u32) { (n as f64).sqrt(); n.pow(2); }"),
+               vec!["(n as f64).sqrt()", "n as f64"]);
+    assert_eq!(find("fn f(x: f64) { let (a, b): (u8, f64) = (1, x); a * b; }"), vec!["a * b"]);
+    assert_eq!(find("fn f() { std::f64::consts::PI * 2; }"), vec!["std::f64::consts::PI * 2"]);
+
+    // fields are known from the structures of the crate
+    assert_eq!(find("struct S { x: f64, n: u8 } fn f(s: S) { s.x + 1.; s.n + 1; }"),
+               vec!["s.x + 1."]);
+
+    // compound assignments are checked on their left-hand side
+    assert_eq!(find("fn f(mut x: f64, y: f64) { x += y * x; }"), vec!["x += y * x", "y * x"]);
+}
+
+#[test]
+fn test_scopes() {
+    // the parameter is shadowed
+    assert_eq!(find("fn f(a: f64) { let a = 1; a * a; }"), Vec::<String>::new());
+    assert_eq!(find("fn f(a: f64) { { let a = 1; } a * a; }"), vec!["a * a"]);
+    assert_eq!(find("fn f(a: u8) { let g = |a: f64| a * a; a * a; }"), vec!["a * a"]);
+    assert_eq!(find("const K: f64 = 1.; fn f(n: u8) { n + K; }"), vec!["n + K"]);
+}
+
+/// Return the known ranges of the expressions found in `code`, as sorted strings.
+fn ranges(code: &str) -> Vec<Vec<String>> {
+    let sess = ParseSess::new();
+    let krate = parse::parse_crate_from_source_str("test.rs".into(), code.into(), vec![], &sess)
+        .unwrap_or_else(|_| panic!("Could not parse {}", code));
+
+    finder::find_exprs(&krate).iter().map(|candidate| {
+        let ranges: &HashMap<_, _> = &candidate.ranges;
+        let mut ranges = ranges.iter().map(|(var, &(lo, hi))| {
+            format!("{}: {}..{}", var, lo, hi)
+        }).collect::<Vec<_>>();
+        ranges.sort();
+        ranges
+    }).collect()
+}
+
+#[test]
+fn test_ranges() {
+    let code = "#[herbie_range(a = \"1..2\", b = \"0..1\")] fn f(a: f64) { a * a; }";
+    assert_eq!(ranges(code), vec![vec!["a: 1..2"]]);
+
+    // the attributes of the enclosing items are intersected
+    let code = "#[herbie_range(a = \"0..4\")] mod m { \
+                #[herbie_range(a = \"1..8\", b = \"0..1\")] fn f(a: f64, b: f64) { a * b; } }";
+    assert_eq!(ranges(code), vec![vec!["a: 1..4", "b: 0..1"]]);
+
+    // shadowed variables and invalid attributes are not known
+    let code = "#[herbie_range(a = \"1..2\")] fn f(a: f64) { let a = 2. * a; a * a; }";
+    assert_eq!(ranges(code), vec![vec!["a: 1..2"], vec![]]);
+    let code = "#[herbie_range(a = \"1..2\")] fn f(a: f64) { let g = |a: f64| a * a; }";
+    assert_eq!(ranges(code), vec![Vec::<String>::new()]);
+    let code = "#[herbie_range(a = \"1..2\", b = \"2\")] fn f(a: f64) { a * a; }";
+    assert_eq!(ranges(code), vec![Vec::<String>::new()]);
+
+    // only the ranges of the modules apply to the items nested in functions
+    let code = "#[herbie_range(a = \"1..2\")] fn f() { fn g(a: f64) { a * a; } }";
+    assert_eq!(ranges(code), vec![Vec::<String>::new()]);
+}
+
+#[test]
+fn test_ignore() {
+    let code = "#[herbie_ignore] fn f(a: f64) { a * a; fn g(b: f64) { b * b; } }";
+    assert_eq!(find(code), vec!["b * b"]);
+
+    let code = "#[herbie_ignore] mod m { fn f(a: f64) { a * a; } } fn g(b: f64) { b * b; }";
+    assert_eq!(find(code), vec!["b * b"]);
+
+    // the attributes of modules apply to the items nested in their functions
+    let code = "#[herbie_ignore] mod m { fn f() { fn g(a: f64) { a * a; } } } \
+                fn h(b: f64) { b * b; }";
+    assert_eq!(find(code), vec!["b * b"]);
+    let code = "#[herbie_ignore] fn f() { mod m { fn g(a: f64) { a * a; } } }";
+    assert_eq!(find(code), vec!["a * a"]);
+
+    assert_eq!(find("fn f(a: f64) { #[herbie_ignore] let b = a * a; b + a; }"), vec!["b + a"]);
+    assert_eq!(find("#![herbie_ignore] fn f(a: f64) { a * a; }"), Vec::<String>::new());
+}
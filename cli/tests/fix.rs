@@ -0,0 +1,129 @@
+extern crate herbie_lint_cli;
+
+use herbie_lint_cli::fix::{self, Edit, Overlap};
+
+/// Return an edit replacing the first occurrence of `old` in `text` by `new`.
+fn edit(text: &str, old: &str, new: &str) -> Edit {
+    let start = text.find(old).unwrap();
+    Edit { byte_start: start, byte_end: start + old.len(), replacement: new.into() }
+}
+
+const CODE: &'static str = "\
+fn f(a: f64, b: f64, c: f64) -> f64 {
+    let d = (a/b + c) * b;
+    (a*a + b*b).sqrt() + d
+}
+
+fn g(a: f64, b: f64) {
+    let x = 1.;
+    let y = 2.;
+    let z = 3.;
+    let w = 4.;
+    (a*a  +  b*b).sqrt();
+}
+";
+
+#[test]
+fn test_apply() {
+    let edits = vec![
+        edit(CODE, "(a*a  +  b*b).sqrt()", "a.hypot(b)"),
+        edit(CODE, "(a/b + c) * b", "c * b + a"),
+    ];
+
+    // the code outside of the edits is kept as is
+    let fixed = CODE.replace("(a/b + c) * b", "c * b + a")
+                    .replace("(a*a  +  b*b).sqrt()", "a.hypot(b)");
+    assert_eq!(fix::apply(CODE, &edits).unwrap(), fixed);
+    assert_eq!(fix::apply(CODE, &[]).unwrap(), CODE);
+}
+
+#[test]
+fn test_overlap() {
+    let edits = vec![
+        edit(CODE, "(a*a + b*b).sqrt()", "a.hypot(b)"),
+        edit(CODE, "(a/b + c) * b", "c * b + a"),
+        edit(CODE, "b*b", "b.powi(2)"),
+    ];
+    assert_eq!(fix::apply(CODE, &edits), Err(Overlap(0, 2)));
+    assert_eq!(fix::diff("f.rs", CODE, &edits), Err(Overlap(0, 2)));
+
+    let edits = vec![edit(CODE, "let", "let mut "), edit(CODE, "let d", "let e")];
+    assert_eq!(fix::apply(CODE, &edits), Err(Overlap(0, 1)));
+}
+
+#[test]
+fn test_diff() {
+    assert_eq!(fix::diff("f.rs", CODE, &[]).unwrap(), "");
+
+    let edits = vec![
+        edit(CODE, "(a*a + b*b).sqrt()", "a.hypot(b)"),
+        edit(CODE, "(a/b + c) * b", "c * b + a"),
+    ];
+    assert_eq!(fix::diff("f.rs", CODE, &edits).unwrap(), "\
+--- a/f.rs
++++ b/f.rs
+@@ -1,6 +1,6 @@
+ fn f(a: f64, b: f64, c: f64) -> f64 {
+-    let d = (a/b + c) * b;
+-    (a*a + b*b).sqrt() + d
++    let d = c * b + a;
++    a.hypot(b) + d
+ }
+\x20
+ fn g(a: f64, b: f64) {
+");
+
+    // distant changes are in different hunks, whose lines are counted after the previous ones
+    let edits = vec![
+        edit(CODE, "(a*a  +  b*b).sqrt()", "a.hypot(b)"),
+        edit(CODE, "    (a*a + b*b).sqrt() + d\n", "    let e = a.hypot(b);\n    e + d\n"),
+    ];
+    assert_eq!(fix::diff("f.rs", CODE, &edits).unwrap(), "\
+--- a/f.rs
++++ b/f.rs
+@@ -1,6 +1,7 @@
+ fn f(a: f64, b: f64, c: f64) -> f64 {
+     let d = (a/b + c) * b;
+-    (a*a + b*b).sqrt() + d
++    let e = a.hypot(b);
++    e + d
+ }
+\x20
+ fn g(a: f64, b: f64) {
+@@ -8,5 +9,5 @@
+     let y = 2.;
+     let z = 3.;
+     let w = 4.;
+-    (a*a  +  b*b).sqrt();
++    a.hypot(b);
+ }
+");
+
+    // a last line without a newline is marked as such
+    let code = "let x = (a*a + b*b).sqrt();";
+    let edits = vec![edit(code, "(a*a + b*b).sqrt()", "a.hypot(b)")];
+    assert_eq!(fix::diff("f.rs", code, &edits).unwrap(), "\
+--- a/f.rs
++++ b/f.rs
+@@ -1,1 +1,1 @@
+-let x = (a*a + b*b).sqrt();
+\\ No newline at end of file
++let x = a.hypot(b);
+\\ No newline at end of file
+");
+
+    let code = CODE.trim_right_matches('\n');
+    let edits = vec![edit(code, "(a*a  +  b*b).sqrt()", "a.hypot(b)")];
+    assert_eq!(fix::diff("f.rs", code, &edits).unwrap(), "\
+--- a/f.rs
++++ b/f.rs
+@@ -8,5 +8,5 @@
+     let y = 2.;
+     let z = 3.;
+     let w = 4.;
+-    (a*a  +  b*b).sqrt();
++    a.hypot(b);
+ }
+\\ No newline at end of file
+");
+}
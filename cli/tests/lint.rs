@@ -0,0 +1,123 @@
+extern crate herbie_lint_cli;
+extern crate herbie_lint_core;
+extern crate syntex_syntax as syntax;
+
+use herbie_lint_cli::lint::Linter;
+use herbie_lint_core::conf::{Conf, Database, UseHerbieConf};
+use herbie_lint_core::report::Status;
+use herbie_lint_core::suggestion::{Applicability, Suggestion};
+use std::env::temp_dir;
+use std::fs::{File, create_dir_all, remove_dir_all};
+use std::io::Write;
+use syntax::parse::{self, ParseSess};
+
+const DB_PATH: &'static str = "../tests/compile-fail/general/Herbie.db";
+const RANGES_DB_PATH: &'static str = "../tests/compile-fail/ranges/Herbie.db";
+
+/// Return the suggestions for `code`, from the database only.
+fn suggestions(code: &str) -> Vec<Suggestion> {
+    suggestions_with_conf(code, Conf::default())
+}
+
+fn suggestions_with_conf(code: &str, conf: Conf) -> Vec<Suggestion> {
+    linter(code, DB_PATH, conf).suggestions
+}
+
+/// Return the linter after checking `code` with the database at `db_path` only.
+fn linter(code: &str, db_path: &str, mut conf: Conf) -> Linter {
+    conf.databases = vec![Database { path: db_path.into(), user: false, writable: false }];
+    conf.use_herbie = UseHerbieConf::No;
+
+    let sess = ParseSess::new();
+    let krate = parse::parse_crate_from_source_str("test.rs".into(), code.into(), vec![], &sess)
+        .unwrap_or_else(|_| panic!("Could not parse {}", code));
+
+    let mut linter = Linter::new(conf).unwrap();
+    linter.check_crate(&sess, &krate);
+    linter
+}
+
+fn lint(code: &str) -> Vec<(String, Applicability)> {
+    suggestions(code).into_iter().map(|suggestion| {
+        (suggestion.suggested_replacement, suggestion.applicability)
+    }).collect()
+}
+
+#[test]
+fn test_suggestions() {
+    let code = "fn f(a: f64, b: f64, c: f64) { (a/b + c) * b; }";
+    assert_eq!(lint(code), vec![("c * b + a".into(), Applicability::MachineApplicable)]);
+
+    // the parentheses of the user are kept, and the context is taken into account
+    let code = "fn f(a: f64, b: f64) { 2. * (a*a + b*b).sqrt(); -(a*a + b*b).sqrt(); }";
+    assert_eq!(lint(code), vec![
+        ("a.hypot(b)".into(), Applicability::MachineApplicable),
+        ("a.hypot(b)".into(), Applicability::MachineApplicable),
+    ]);
+
+    let code = "fn f(a: u8, b: u8, c: u8) { (a/b + c) * b; }";
+    assert!(lint(code).is_empty());
+}
+
+#[test]
+fn test_assignments() {
+    // only the most accurate of the matching substitutions is reported
+    let code = "fn f(mut x: f64, y: f64, z: f64) { x += y * ((x - z) / y); }";
+    assert_eq!(lint(code), vec![("x = 2.0 * x - z".into(), Applicability::MachineApplicable)]);
+
+    // the right-hand side is not reported on its own when the whole assignment is
+    let code = "fn f(mut x: f64, y: f64) { x *= (y*y + 1.).sqrt(); x *= ((y*y + 1.).sqrt()); }";
+    assert_eq!(lint(code), vec![
+        ("x *= 1.0f64.hypot(y)".into(), Applicability::MachineApplicable),
+        ("x *= 1.0f64.hypot(y)".into(), Applicability::MachineApplicable),
+    ]);
+}
+
+#[test]
+fn test_ranges() {
+    // the database's rewrite of `exp(a) - 1` is worse for `a` in `[1, 2]`
+    let code = "fn f(a: f64) { a.exp() - 1.; }";
+    assert_eq!(linter(code, RANGES_DB_PATH, Conf::default()).suggestions.len(), 1);
+
+    let code = "#[herbie_range(a = \"1..2\")] fn f(a: f64) { a.exp() - 1.; }";
+    let checked = linter(code, RANGES_DB_PATH, Conf::default());
+    assert!(checked.suggestions.is_empty());
+    let statuses = checked.entries.iter().map(|entry| {
+        (&entry.expression[..], entry.status)
+    }).collect::<Vec<_>>();
+    assert!(statuses.contains(&("a.exp() - 1.", Status::NotOnRange)));
+
+    // contradictory ranges are not used
+    let code = "#[herbie_range(a = \"0..1\")] mod m { \
+                #[herbie_range(a = \"2..3\")] fn f(a: f64) { a.exp() - 1.; } }";
+    assert_eq!(linter(code, RANGES_DB_PATH, Conf::default()).suggestions.len(), 1);
+}
+
+#[test]
+fn test_suggestion_location() {
+    let code = "fn f(a: f64, b: f64) -> f64 {\n    (a*a + b*b).sqrt()\n}\n";
+    let suggestion = &suggestions(code)[0];
+
+    assert_eq!(suggestion.file_name, "test.rs");
+    assert_eq!(&code[suggestion.byte_start..suggestion.byte_end], "(a*a + b*b).sqrt()");
+    assert_eq!((suggestion.line_start, suggestion.column_start), (2, 5));
+    assert_eq!((suggestion.line_end, suggestion.column_end), (2, 23));
+}
+
+#[test]
+fn test_ignore_file() {
+    let root = temp_dir().join("herbie-lint-test-lint");
+    let _ = remove_dir_all(&root);
+    create_dir_all(&root).unwrap();
+    let path = root.join("Herbie.ignore");
+    File::create(&path).unwrap().write_all(b"test.rs: (a/b + c)   * b\n").unwrap();
+
+    let mut conf = Conf::default();
+    conf.ignore_path = path.to_string_lossy().into_owned();
+    let code = "fn f(a: f64, b: f64, c: f64) { (a/b + c) * b; (a*a + b*b).sqrt(); }";
+    let suggestions = suggestions_with_conf(code, conf);
+
+    remove_dir_all(root).unwrap();
+    assert_eq!(suggestions.len(), 1);
+    assert_eq!(suggestions[0].suggested_replacement, "a.hypot(b)");
+}
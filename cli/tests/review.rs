@@ -0,0 +1,91 @@
+extern crate herbie_lint_cli;
+extern crate herbie_lint_core;
+extern crate syntex_syntax as syntax;
+
+use herbie_lint_cli::fix::Edit;
+use herbie_lint_cli::lint::Linter;
+use herbie_lint_cli::review::{self, Summary};
+use herbie_lint_core::conf::{Conf, Database, UseHerbieConf};
+use herbie_lint_core::ignore::Ignores;
+use herbie_lint_core::suggestion::Suggestion;
+use std::collections::HashMap;
+use std::env::temp_dir;
+use std::fs::{create_dir_all, remove_dir_all};
+use syntax::parse::{self, ParseSess};
+
+const DB_PATH: &'static str = "../tests/compile-fail/general/Herbie.db";
+
+const CODE: &'static str = "fn f(a: f64, b: f64, c: f64) { (a/b + c) * b; (a*a + b*b).sqrt(); }";
+
+/// Return the suggestions for `code`, from the database only.
+fn suggestions(code: &str) -> Vec<Suggestion> {
+    let mut conf = Conf::default();
+    conf.databases = vec![Database { path: DB_PATH.into(), user: false, writable: false }];
+    conf.use_herbie = UseHerbieConf::No;
+
+    let sess = ParseSess::new();
+    let krate = parse::parse_crate_from_source_str("test.rs".into(), code.into(), vec![], &sess)
+        .unwrap_or_else(|_| panic!("Could not parse {}", code));
+
+    let mut linter = Linter::new(conf).unwrap();
+    linter.check_crate(&sess, &krate);
+    linter.suggestions
+}
+
+/// Review `suggestions` of `CODE` with the answers in `input`, and return the accepted edits, the
+/// summary, the output and the ignored expressions.
+fn review(
+    name: &str,
+    suggestions: &[Suggestion],
+    input: &str
+) -> (Vec<Edit>, Summary, String, Ignores) {
+    let dir = temp_dir().join(name);
+    let _ = remove_dir_all(&dir);
+    create_dir_all(&dir).unwrap();
+
+    let mut ignores = Ignores::read(dir.join("Herbie.ignore")).unwrap();
+    let mut texts = HashMap::new();
+    texts.insert("test.rs".into(), CODE.into());
+
+    let mut output = vec![];
+    let (mut accepted, summary) = review::review(
+        suggestions, &texts, &mut ignores, input.as_bytes(), &mut output
+    ).unwrap();
+    assert!(accepted.keys().all(|file_name| file_name == "test.rs"));
+
+    remove_dir_all(dir).unwrap();
+    let edits = accepted.remove("test.rs").unwrap_or_else(Vec::new);
+    (edits, summary, String::from_utf8(output).unwrap(), ignores)
+}
+
+#[test]
+fn test_review() {
+    let (edits, summary, output, ignores) = review(
+        "herbie-lint-test-review", &suggestions(CODE), "x\na\nr\n"
+    );
+
+    assert_eq!(edits, vec![Edit { byte_start: 31, byte_end: 44, replacement: "c * b + a".into() }]);
+    assert_eq!(summary, Summary { accepted: 1, rejected: 1, skipped: 0 });
+    assert!(output.starts_with("[1/2] test.rs:1:32: Numerically unstable expression: 7.6 → 0.0"));
+    assert!(output.contains("\n- (a/b + c) * b\n+ c * b + a\n"));
+    assert!(output.contains("\n- (a*a + b*b).sqrt()\n+ a.hypot(b)\n"));
+    assert!(ignores.contains("test.rs", "(a*a + b*b).sqrt()"));
+    assert!(!ignores.contains("test.rs", "(a/b + c) * b"));
+
+    // the remaining suggestions are skipped when the input ends
+    let (edits, summary, _, _) = review("herbie-lint-test-review-eof", &suggestions(CODE), "s\n");
+    assert!(edits.is_empty());
+    assert_eq!(summary, Summary { accepted: 0, rejected: 0, skipped: 2 });
+}
+
+#[test]
+fn test_review_overlap() {
+    let mut twice = suggestions(CODE);
+    twice.extend(suggestions(CODE));
+
+    let (edits, summary, output, _) = review("herbie-lint-test-review-overlap", &twice, "a\ns\n");
+    assert_eq!(edits.len(), 1);
+    assert_eq!(summary, Summary { accepted: 1, rejected: 0, skipped: 3 });
+    assert!(output.contains("[3/4] test.rs:1:32: Numerically unstable expression: 7.6 → 0.0"));
+    assert!(output.contains("Skipped: it overlaps a suggestion which was accepted\n"));
+}
@@ -0,0 +1,1058 @@
+//! Arbitrary precision binary floating point arithmetic, used as the reference to measure the
+//! error of `f64` computations, see the `estimate` module.
+//!
+//! Numbers have an arbitrary exponent and a mantissa of a fixed number of bits given by a
+//! `Context`. Operations truncate their results to that precision, so they are only accurate up
+//! to a few units in the last place: callers are expected to increase the precision until the
+//! results they are interested in are stable.
+//! Operations on infinities and NaNs are done with `f64`, as they are exact there.
+
+use std::cell::RefCell;
+use std::cmp::{self, Ordering};
+use std;
+
+/// A number of a `Context`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BigFloat {
+    Nan,
+    Inf { neg: bool },
+    Zero { neg: bool },
+    /// `mant * 2^exp`, where the little-endian `mant` has the precision of its context and its
+    /// most significant bit set.
+    Finite { neg: bool, mant: Vec<u32>, exp: i64 },
+}
+
+impl BigFloat {
+    pub fn is_nan(&self) -> bool {
+        *self == BigFloat::Nan
+    }
+
+    /// Whether the number is `Finite` or `Zero`.
+    pub fn is_finite(&self) -> bool {
+        match *self {
+            BigFloat::Finite { .. } | BigFloat::Zero { .. } => true,
+            BigFloat::Inf { .. } | BigFloat::Nan => false,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        if let BigFloat::Zero { .. } = *self { true } else { false }
+    }
+
+    /// Whether the sign bit is set. NaNs have no sign.
+    pub fn is_neg(&self) -> bool {
+        match *self {
+            BigFloat::Finite { neg, .. } | BigFloat::Inf { neg } | BigFloat::Zero { neg } => neg,
+            BigFloat::Nan => false,
+        }
+    }
+
+    pub fn neg(&self) -> BigFloat {
+        self.with_sign(!self.is_neg())
+    }
+
+    pub fn abs(&self) -> BigFloat {
+        self.with_sign(false)
+    }
+
+    fn with_sign(&self, sign: bool) -> BigFloat {
+        match *self {
+            BigFloat::Finite { ref mant, exp, .. } => {
+                BigFloat::Finite { neg: sign, mant: mant.clone(), exp: exp }
+            }
+            BigFloat::Inf { .. } => BigFloat::Inf { neg: sign },
+            BigFloat::Zero { .. } => BigFloat::Zero { neg: sign },
+            BigFloat::Nan => BigFloat::Nan,
+        }
+    }
+
+    /// Multiply by `2^n`, which is exact.
+    pub fn ldexp(&self, n: i64) -> BigFloat {
+        match *self {
+            BigFloat::Finite { neg, ref mant, exp } => {
+                BigFloat::Finite { neg: neg, mant: mant.clone(), exp: exp + n }
+            }
+            _ => self.clone(),
+        }
+    }
+
+    /// Return `e` such that `2^(e-1) <= |self| < 2^e`, for finite non-zero numbers.
+    fn magnitude(&self) -> Option<i64> {
+        match *self {
+            BigFloat::Finite { ref mant, exp, .. } => Some(exp + 32 * mant.len() as i64),
+            _ => None,
+        }
+    }
+
+    /// Round to the nearest `f64`, ties to even.
+    pub fn to_f64(&self) -> f64 {
+        let (neg, mant, exp) = match *self {
+            BigFloat::Finite { neg, ref mant, exp } => (neg, mant, exp),
+            BigFloat::Inf { neg: true } => return std::f64::NEG_INFINITY,
+            BigFloat::Inf { neg: false } => return std::f64::INFINITY,
+            BigFloat::Zero { neg: true } => return -0.,
+            BigFloat::Zero { neg: false } => return 0.,
+            BigFloat::Nan => return std::f64::NAN,
+        };
+
+        let n = mant.len();
+        let top = (mant[n - 1] as u64) << 32 | mant[n - 2] as u64;
+        let sticky = mant[..n - 2].iter().any(|&word| word != 0);
+        // `self` is `top * 2^exp`, plus what is left in `sticky`
+        let exp = exp + 32 * (n as i64 - 2);
+        let magnitude = exp + 64;
+
+        let abs = if magnitude > 1024 {
+            std::f64::INFINITY
+        }
+        else {
+            // the number of bits that do not fit in a double, subnormals having fewer bits
+            let shift = if magnitude > -1022 { 11 } else { -1010 - magnitude };
+
+            if shift > 64 {
+                0.
+            }
+            else {
+                let (q, half, rest) = if shift == 64 {
+                    (0, true, top << 1 != 0 || sticky)
+                }
+                else {
+                    let shift = shift as u32;
+                    let half = (top >> (shift - 1)) & 1 == 1;
+                    let rest = top & ((1 << (shift - 1)) - 1) != 0 || sticky;
+                    (top >> shift, half, rest)
+                };
+
+                let q = if half && (rest || q & 1 == 1) { q + 1 } else { q };
+                ldexp(q as f64, exp + shift)
+            }
+        };
+
+        if neg { -abs } else { abs }
+    }
+}
+
+/// Return `x * 2^n`, exactly if the result is representable.
+pub fn ldexp(mut x: f64, mut n: i64) -> f64 {
+    while n > 1000 {
+        x *= 2f64.powi(1000);
+        n -= 1000;
+    }
+
+    while n < -1000 {
+        x *= 2f64.powi(-1000);
+        n += 1000;
+    }
+
+    x * 2f64.powi(n as i32)
+}
+
+/// Return `(m, e)` such that `x = m * 2^e` and `2^52 <= m < 2^53`, for finite positive numbers.
+fn decode(x: f64) -> (u64, i64) {
+    let mut exp = x.log2().floor() as i64 - 52;
+    let mut mant = ldexp(x, -exp);
+
+    // `log2` is not exact
+    if mant >= 2f64.powi(53) {
+        mant /= 2.;
+        exp += 1;
+    }
+    else if mant < 2f64.powi(52) {
+        mant *= 2.;
+        exp -= 1;
+    }
+
+    (mant as u64, exp)
+}
+
+/// Return the memory representation of a double.
+pub fn f64_to_bits(x: f64) -> u64 {
+    let sign = if x.is_sign_negative() { 1 << 63 } else { 0 };
+
+    if x.is_nan() {
+        0x7ff8_0000_0000_0000
+    }
+    else if x.is_infinite() {
+        sign | 0x7ff0_0000_0000_0000
+    }
+    else if x == 0. {
+        sign
+    }
+    else {
+        let (mut mant, mut exp) = decode(x.abs());
+
+        if exp < -1074 {
+            mant >>= -1074 - exp;
+            exp = -1074;
+        }
+
+        if mant < 1 << 52 {
+            sign | mant
+        }
+        else {
+            sign | ((exp + 1075) as u64) << 52 | (mant & ((1 << 52) - 1))
+        }
+    }
+}
+
+/// Return the double with the given memory representation.
+pub fn f64_from_bits(bits: u64) -> f64 {
+    let exp = (bits >> 52 & 0x7ff) as i64;
+    let mant = bits & ((1 << 52) - 1);
+
+    let abs = if exp == 0x7ff {
+        if mant == 0 { std::f64::INFINITY } else { std::f64::NAN }
+    }
+    else if exp == 0 {
+        ldexp(mant as f64, -1074)
+    }
+    else {
+        ldexp((mant | 1 << 52) as f64, exp - 1075)
+    };
+
+    if bits >> 63 == 1 { -abs } else { abs }
+}
+
+/// Operations on naturals, as little-endian vectors of words.
+fn nat_trim(a: &mut Vec<u32>) {
+    while a.last() == Some(&0) {
+        a.pop();
+    }
+}
+
+fn nat_bits(a: &[u32]) -> usize {
+    match a.last() {
+        Some(&last) => 32 * a.len() - last.leading_zeros() as usize,
+        None => 0,
+    }
+}
+
+fn nat_bit(a: &[u32], bit: usize) -> bool {
+    a.get(bit / 32).map_or(false, |&word| (word >> (bit % 32)) & 1 == 1)
+}
+
+fn nat_cmp(a: &[u32], b: &[u32]) -> Ordering {
+    for i in (0..cmp::max(a.len(), b.len())).rev() {
+        let (x, y) = (a.get(i).cloned().unwrap_or(0), b.get(i).cloned().unwrap_or(0));
+        if x != y {
+            return x.cmp(&y);
+        }
+    }
+
+    Ordering::Equal
+}
+
+fn nat_shl(a: &[u32], bits: usize) -> Vec<u32> {
+    let (words, bits) = (bits / 32, bits % 32);
+    let mut result = vec![0; words];
+
+    if bits == 0 {
+        result.extend_from_slice(a);
+    }
+    else {
+        let mut carry = 0;
+        for &word in a {
+            result.push(word << bits | carry);
+            carry = word >> (32 - bits);
+        }
+        result.push(carry);
+    }
+
+    result
+}
+
+fn nat_shr(a: &[u32], bits: usize) -> Vec<u32> {
+    let (words, bits) = (bits / 32, bits % 32);
+
+    if words >= a.len() {
+        return vec![];
+    }
+
+    let a = &a[words..];
+    if bits == 0 {
+        return a.to_vec();
+    }
+
+    (0..a.len()).map(|i| {
+        let high = if i + 1 < a.len() { a[i + 1] << (32 - bits) } else { 0 };
+        a[i] >> bits | high
+    }).collect()
+}
+
+fn nat_add(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let (a, b) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let mut result = Vec::with_capacity(a.len() + 1);
+    let mut carry = 0;
+
+    for (i, &word) in a.iter().enumerate() {
+        let sum = word as u64 + b.get(i).map_or(0, |&word| word as u64) + carry;
+        result.push(sum as u32);
+        carry = sum >> 32;
+    }
+
+    result.push(carry as u32);
+    result
+}
+
+/// Return `a - b`, where `a >= b`.
+fn nat_sub(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(a.len());
+    let mut borrow = 0;
+
+    for (i, &word) in a.iter().enumerate() {
+        let diff = word as i64 - b.get(i).map_or(0, |&word| word as i64) - borrow;
+        if diff < 0 {
+            result.push((diff + (1 << 32)) as u32);
+            borrow = 1;
+        }
+        else {
+            result.push(diff as u32);
+            borrow = 0;
+        }
+    }
+
+    result
+}
+
+fn nat_mul(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = vec![0; a.len() + b.len()];
+
+    for (i, &x) in a.iter().enumerate() {
+        let mut carry = 0;
+        for (j, &y) in b.iter().enumerate() {
+            let product = x as u64 * y as u64 + result[i + j] as u64 + carry;
+            result[i + j] = product as u32;
+            carry = product >> 32;
+        }
+        result[i + b.len()] = carry as u32;
+    }
+
+    result
+}
+
+fn nat_div_small(a: &[u32], d: u32) -> Vec<u32> {
+    let mut result = vec![0; a.len()];
+    let mut rem = 0;
+
+    for i in (0..a.len()).rev() {
+        let current = rem << 32 | a[i] as u64;
+        result[i] = (current / d as u64) as u32;
+        rem = current % d as u64;
+    }
+
+    result
+}
+
+/// The number of times the argument of `exp` is halved before using its Taylor series.
+const EXP_HALVINGS: i64 = 8;
+/// The number of times the argument of `atan` is halved before using its Taylor series.
+const ATAN_HALVINGS: i64 = 3;
+
+/// The precision of `BigFloat`s, and the constants at that precision.
+#[derive(Debug)]
+pub struct Context {
+    words: usize,
+    ln2: BigFloat,
+    pi: BigFloat,
+    /// A context with more precision, to reduce the arguments of trigonometric functions.
+    wide: RefCell<Option<Box<Context>>>,
+}
+
+impl Context {
+    /// Create a context with mantissas of at least `bits` bits, and at least 128.
+    pub fn new(bits: usize) -> Context {
+        let mut context = Context {
+            words: cmp::max(4, (bits + 31) / 32),
+            ln2: BigFloat::Nan,
+            pi: BigFloat::Nan,
+            wide: RefCell::new(None),
+        };
+
+        // ln(2) = 2 atanh(1/3)
+        let third = context.div_small(&context.one(), 3);
+        context.ln2 = context.atanh_series(&third).ldexp(1);
+
+        // Machin's formula: π = 16 atan(1/5) - 4 atan(1/239)
+        context.pi = context.sub(&context.atan_inv(5).ldexp(4), &context.atan_inv(239).ldexp(2));
+
+        context
+    }
+
+    /// The precision of the mantissas, in bits.
+    pub fn bits(&self) -> usize {
+        32 * self.words
+    }
+
+    pub fn pi(&self) -> BigFloat {
+        self.pi.clone()
+    }
+
+    pub fn one(&self) -> BigFloat {
+        self.from_f64(1.)
+    }
+
+    /// Round a natural number times `2^exp` to the precision of the context.
+    fn make(&self, neg: bool, mut mant: Vec<u32>, exp: i64) -> BigFloat {
+        nat_trim(&mut mant);
+
+        let bits = nat_bits(&mant);
+        if bits == 0 {
+            return BigFloat::Zero { neg: neg };
+        }
+
+        let target = self.bits();
+        let (mut mant, exp) = if bits > target {
+            (nat_shr(&mant, bits - target), exp + (bits - target) as i64)
+        }
+        else {
+            (nat_shl(&mant, target - bits), exp - (target - bits) as i64)
+        };
+
+        // the shifts can leave a leading zero word
+        mant.truncate(self.words);
+
+        BigFloat::Finite { neg: neg, mant: mant, exp: exp }
+    }
+
+    /// Convert a double, exactly.
+    pub fn from_f64(&self, x: f64) -> BigFloat {
+        if x.is_nan() {
+            BigFloat::Nan
+        }
+        else if x.is_infinite() {
+            BigFloat::Inf { neg: x < 0. }
+        }
+        else if x == 0. {
+            BigFloat::Zero { neg: x.is_sign_negative() }
+        }
+        else {
+            let (mant, exp) = decode(x.abs());
+            self.make(x < 0., vec![mant as u32, (mant >> 32) as u32], exp)
+        }
+    }
+
+    /// Compute `f` with `f64`, for operands which are not finite.
+    fn fallback1<F: Fn(f64) -> f64>(&self, x: &BigFloat, f: F) -> BigFloat {
+        self.from_f64(f(x.to_f64()))
+    }
+
+    fn fallback2<F: Fn(f64, f64) -> f64>(&self, x: &BigFloat, y: &BigFloat, f: F) -> BigFloat {
+        self.from_f64(f(x.to_f64(), y.to_f64()))
+    }
+
+    /// Whether `term` is too small to change `sum`.
+    fn is_negligible(&self, term: &BigFloat, sum: &BigFloat) -> bool {
+        match (term.magnitude(), sum.magnitude()) {
+            (Some(term), Some(sum)) => term < sum - self.bits() as i64 - 2,
+            (None, _) => true,
+            (Some(..), None) => false,
+        }
+    }
+
+    /// Compare two finite numbers.
+    fn cmp(&self, x: &BigFloat, y: &BigFloat) -> Ordering {
+        let diff = self.sub(x, y);
+
+        if diff.is_zero() {
+            Ordering::Equal
+        }
+        else if diff.is_neg() {
+            Ordering::Less
+        }
+        else {
+            Ordering::Greater
+        }
+    }
+
+    /// Round a finite number to the nearest integer, ties away from zero.
+    fn round(&self, x: &BigFloat) -> BigFloat {
+        match *x {
+            BigFloat::Finite { neg, ref mant, exp } if exp < 0 => {
+                let shift = -exp as usize;
+
+                if shift > self.bits() {
+                    return BigFloat::Zero { neg: neg };
+                }
+
+                let int = nat_shr(mant, shift);
+                let int = if nat_bit(mant, shift - 1) { nat_add(&int, &[1]) } else { int };
+                self.make(neg, int, 0)
+            }
+            _ => x.clone(),
+        }
+    }
+
+    /// Return the remainder of the division of an integer by 4.
+    fn mod4(&self, x: &BigFloat) -> u32 {
+        match *x {
+            BigFloat::Finite { neg, ref mant, exp } => {
+                let low = if exp >= 2 {
+                    0
+                }
+                else if exp >= 0 {
+                    (mant[0] << exp) & 3
+                }
+                else {
+                    nat_shr(mant, -exp as usize)[0] & 3
+                };
+
+                if neg { (4 - low) % 4 } else { low }
+            }
+            _ => 0,
+        }
+    }
+
+    /// Return whether a finite number is an odd integer, or `None` if it is not an integer.
+    fn integer_parity(&self, x: &BigFloat) -> Option<bool> {
+        match *x {
+            BigFloat::Finite { ref mant, exp, .. } => {
+                if exp > 0 {
+                    Some(false)
+                }
+                else {
+                    let shift = -exp as usize;
+                    let int = nat_shr(mant, shift);
+
+                    let is_integer = shift < self.bits()
+                                  && nat_cmp(&nat_shl(&int, shift), mant) == Ordering::Equal;
+
+                    if !is_integer {
+                        None
+                    }
+                    else {
+                        Some(nat_bit(mant, shift))
+                    }
+                }
+            }
+            BigFloat::Zero { .. } => Some(false),
+            _ => None,
+        }
+    }
+
+    pub fn add(&self, x: &BigFloat, y: &BigFloat) -> BigFloat {
+        match (x, y) {
+            (&BigFloat::Finite { neg: x_neg, mant: ref x_mant, exp: x_exp },
+             &BigFloat::Finite { neg: y_neg, mant: ref y_mant, exp: y_exp }) => {
+                // a much smaller operand does not change the result
+                let (x_magnitude, y_magnitude) = (x.magnitude(), y.magnitude());
+                if x_magnitude > y_magnitude.map(|m| m + self.bits() as i64 + 64) {
+                    return self.make(x_neg, x_mant.clone(), x_exp);
+                }
+                if y_magnitude > x_magnitude.map(|m| m + self.bits() as i64 + 64) {
+                    return self.make(y_neg, y_mant.clone(), y_exp);
+                }
+
+                // align the mantissas
+                let exp = cmp::min(x_exp, y_exp);
+                let x_mant = nat_shl(x_mant, (x_exp - exp) as usize);
+                let y_mant = nat_shl(y_mant, (y_exp - exp) as usize);
+
+                if x_neg == y_neg {
+                    self.make(x_neg, nat_add(&x_mant, &y_mant), exp)
+                }
+                else {
+                    match nat_cmp(&x_mant, &y_mant) {
+                        Ordering::Greater => self.make(x_neg, nat_sub(&x_mant, &y_mant), exp),
+                        Ordering::Less => self.make(y_neg, nat_sub(&y_mant, &x_mant), exp),
+                        Ordering::Equal => BigFloat::Zero { neg: false },
+                    }
+                }
+            }
+            (&BigFloat::Zero { neg: x_neg }, &BigFloat::Zero { neg: y_neg }) => {
+                BigFloat::Zero { neg: x_neg && y_neg }
+            }
+            (&BigFloat::Zero { .. }, &BigFloat::Finite { .. }) => y.clone(),
+            (&BigFloat::Finite { .. }, &BigFloat::Zero { .. }) => x.clone(),
+            _ => self.fallback2(x, y, |x, y| x + y),
+        }
+    }
+
+    pub fn sub(&self, x: &BigFloat, y: &BigFloat) -> BigFloat {
+        self.add(x, &y.neg())
+    }
+
+    pub fn mul(&self, x: &BigFloat, y: &BigFloat) -> BigFloat {
+        match (x, y) {
+            (&BigFloat::Finite { neg: x_neg, mant: ref x_mant, exp: x_exp },
+             &BigFloat::Finite { neg: y_neg, mant: ref y_mant, exp: y_exp }) => {
+                self.make(x_neg != y_neg, nat_mul(x_mant, y_mant), x_exp + y_exp)
+            }
+            _ if x.is_finite() && y.is_finite() => {
+                BigFloat::Zero { neg: x.is_neg() != y.is_neg() }
+            }
+            _ => self.fallback2(x, y, |x, y| x * y),
+        }
+    }
+
+    /// Divide by a small integer.
+    fn div_small(&self, x: &BigFloat, d: u32) -> BigFloat {
+        match *x {
+            BigFloat::Finite { neg, ref mant, exp } => {
+                self.make(neg, nat_div_small(&nat_shl(mant, 32), d), exp - 32)
+            }
+            _ => self.fallback1(x, |x| x / d as f64),
+        }
+    }
+
+    /// Return the leading words of a finite number as a double, and the exponent of their last
+    /// bit.
+    fn approx(&self, x: &BigFloat) -> (f64, i64) {
+        match *x {
+            BigFloat::Finite { ref mant, exp, .. } => {
+                let n = mant.len();
+                let top = (mant[n - 1] as u64) << 32 | mant[n - 2] as u64;
+                (top as f64, exp + 32 * (n as i64 - 2))
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Return `1/|x|` for finite non-zero numbers, by Newton's method.
+    fn recip(&self, x: &BigFloat) -> BigFloat {
+        let x = x.abs();
+        let (top, exp) = self.approx(&x);
+        let one = self.one();
+
+        let mut result = self.from_f64(1. / top).ldexp(-exp);
+        let mut bits = 50;
+        while bits < self.bits() + 64 {
+            let error = self.sub(&one, &self.mul(&x, &result));
+            result = self.add(&result, &self.mul(&result, &error));
+            bits *= 2;
+        }
+
+        result
+    }
+
+    pub fn div(&self, x: &BigFloat, y: &BigFloat) -> BigFloat {
+        match (x, y) {
+            (&BigFloat::Finite { .. }, &BigFloat::Finite { neg, .. }) => {
+                let result = self.mul(x, &self.recip(y));
+                if neg { result.neg() } else { result }
+            }
+            (&BigFloat::Zero { .. }, &BigFloat::Finite { .. }) => {
+                BigFloat::Zero { neg: x.is_neg() != y.is_neg() }
+            }
+            _ => self.fallback2(x, y, |x, y| x / y),
+        }
+    }
+
+    pub fn sqrt(&self, x: &BigFloat) -> BigFloat {
+        match *x {
+            BigFloat::Finite { neg: true, .. } => BigFloat::Nan,
+            BigFloat::Finite { .. } => {
+                let (top, exp) = self.approx(x);
+                let (top, exp) = if exp % 2 == 0 { (top, exp) } else { (2. * top, exp - 1) };
+                let one = self.one();
+
+                // Newton's method on `1/sqrt(x)`
+                let mut inv = self.from_f64(1. / top.sqrt()).ldexp(-exp / 2);
+                let mut bits = 50;
+                while bits < self.bits() + 64 {
+                    let error = self.sub(&one, &self.mul(x, &self.mul(&inv, &inv)));
+                    inv = self.add(&inv, &self.mul(&inv, &error).ldexp(-1));
+                    bits *= 2;
+                }
+
+                let result = self.mul(x, &inv);
+                let error = self.sub(x, &self.mul(&result, &result));
+                self.add(&result, &self.mul(&inv, &error).ldexp(-1))
+            }
+            BigFloat::Zero { .. } => x.clone(),
+            _ => self.fallback1(x, f64::sqrt),
+        }
+    }
+
+    /// Return `atanh(z)` by its Taylor series, for small `z`.
+    fn atanh_series(&self, z: &BigFloat) -> BigFloat {
+        let z2 = self.mul(z, z);
+        let mut power = z.clone();
+        let mut sum = z.clone();
+
+        for k in 1.. {
+            power = self.mul(&power, &z2);
+            let term = self.div_small(&power, 2 * k + 1);
+            if self.is_negligible(&term, &sum) {
+                break;
+            }
+            sum = self.add(&sum, &term);
+        }
+
+        sum
+    }
+
+    /// Return `atan(z)` by its Taylor series, for small `z`.
+    fn atan_series(&self, z: &BigFloat) -> BigFloat {
+        let z2 = self.mul(z, z);
+        let mut power = z.clone();
+        let mut sum = z.clone();
+
+        for k in 1.. {
+            power = self.mul(&power, &z2);
+            let term = self.div_small(&power, 2 * k + 1);
+            if self.is_negligible(&term, &sum) {
+                break;
+            }
+            sum = if k % 2 == 1 { self.sub(&sum, &term) } else { self.add(&sum, &term) };
+        }
+
+        sum
+    }
+
+    /// Return `atan(1/n)`.
+    fn atan_inv(&self, n: u32) -> BigFloat {
+        let mut power = self.div_small(&self.one(), n);
+        let mut sum = power.clone();
+
+        for k in 1.. {
+            power = self.div_small(&power, n * n);
+            let term = self.div_small(&power, 2 * k + 1);
+            if self.is_negligible(&term, &sum) {
+                break;
+            }
+            sum = if k % 2 == 1 { self.sub(&sum, &term) } else { self.add(&sum, &term) };
+        }
+
+        sum
+    }
+
+    pub fn exp(&self, x: &BigFloat) -> BigFloat {
+        match *x {
+            BigFloat::Finite { neg, .. } => {
+                // the result does not fit in a double anyway
+                if x.magnitude() > Some(32) {
+                    return if neg {
+                        BigFloat::Zero { neg: false }
+                    }
+                    else {
+                        BigFloat::Inf { neg: false }
+                    };
+                }
+
+                // exp(x) = 2^k exp(r), where |r| <= ln(2)/2
+                let k = self.round(&self.div(x, &self.ln2));
+                let r = self.sub(x, &self.mul(&k, &self.ln2)).ldexp(-EXP_HALVINGS);
+
+                let mut term = self.one();
+                let mut sum = self.one();
+                for n in 1.. {
+                    term = self.div_small(&self.mul(&term, &r), n);
+                    if self.is_negligible(&term, &sum) {
+                        break;
+                    }
+                    sum = self.add(&sum, &term);
+                }
+
+                for _ in 0..EXP_HALVINGS {
+                    sum = self.mul(&sum, &sum);
+                }
+
+                sum.ldexp(k.to_f64() as i64)
+            }
+            BigFloat::Zero { .. } => self.one(),
+            _ => self.fallback1(x, f64::exp),
+        }
+    }
+
+    pub fn exp_m1(&self, x: &BigFloat) -> BigFloat {
+        match *x {
+            // the Taylor series does not lose precision for small arguments
+            BigFloat::Finite { .. } if x.magnitude() <= Some(0) => {
+                let mut term = x.clone();
+                let mut sum = x.clone();
+                for n in 2.. {
+                    term = self.div_small(&self.mul(&term, x), n);
+                    if self.is_negligible(&term, &sum) {
+                        break;
+                    }
+                    sum = self.add(&sum, &term);
+                }
+
+                sum
+            }
+            BigFloat::Finite { .. } => self.sub(&self.exp(x), &self.one()),
+            BigFloat::Zero { .. } => x.clone(),
+            _ => self.fallback1(x, f64::exp_m1),
+        }
+    }
+
+    pub fn ln(&self, x: &BigFloat) -> BigFloat {
+        match *x {
+            BigFloat::Finite { neg: true, .. } => BigFloat::Nan,
+            BigFloat::Finite { .. } => {
+                // ln(x) = e ln(2) + ln(f), where sqrt(1/2) <= f < sqrt(2)
+                let mut e = x.magnitude().unwrap_or(0);
+                let mut f = x.ldexp(-e);
+                if f.to_f64() < std::f64::consts::FRAC_1_SQRT_2 {
+                    f = f.ldexp(1);
+                    e -= 1;
+                }
+
+                // ln(f) = 2 atanh((f - 1)/(f + 1))
+                let one = self.one();
+                let z = self.div(&self.sub(&f, &one), &self.add(&f, &one));
+                let ln_f = self.atanh_series(&z).ldexp(1);
+
+                self.add(&self.mul(&self.from_f64(e as f64), &self.ln2), &ln_f)
+            }
+            BigFloat::Zero { .. } => BigFloat::Inf { neg: true },
+            _ => self.fallback1(x, f64::ln),
+        }
+    }
+
+    pub fn ln_1p(&self, x: &BigFloat) -> BigFloat {
+        match *x {
+            // ln(1 + x) = 2 atanh(x/(2 + x)), without rounding `1 + x`
+            BigFloat::Finite { .. } if x.magnitude() <= Some(-1) => {
+                let z = self.div(x, &self.add(&self.from_f64(2.), x));
+                self.atanh_series(&z).ldexp(1)
+            }
+            BigFloat::Finite { .. } => self.ln(&self.add(&self.one(), x)),
+            BigFloat::Zero { .. } => x.clone(),
+            _ => self.fallback1(x, f64::ln_1p),
+        }
+    }
+
+    /// Return `(k, r)` such that `x = k π/2 + r` and `|r| <= π/4`, for finite numbers.
+    fn reduce(&self, x: &BigFloat) -> (BigFloat, BigFloat) {
+        let half_pi = self.pi.ldexp(-1);
+        let k = self.round(&self.div(x, &half_pi));
+        let r = self.sub(x, &self.mul(&k, &half_pi));
+
+        (k, r)
+    }
+
+    /// Return the sine and cosine of a finite number.
+    fn sin_cos(&self, x: &BigFloat) -> (BigFloat, BigFloat) {
+        // the reduction of large arguments needs as many more bits as the integer part of `x`
+        let magnitude = x.magnitude().unwrap_or(0);
+        let (k, r) = if magnitude <= 32 {
+            self.reduce(x)
+        }
+        else {
+            let bits = magnitude as usize + self.bits() + 64;
+
+            let mut wide = self.wide.borrow_mut();
+            if wide.as_ref().map_or(true, |wide| wide.bits() < bits) {
+                *wide = Some(Box::new(Context::new(bits.next_power_of_two())));
+            }
+
+            let wide = wide.as_ref().expect("The wide context was just created");
+            wide.reduce(x)
+        };
+
+        let r2 = self.mul(&r, &r);
+        let mut term = r.clone();
+        let mut sin = r.clone();
+        for n in 1.. {
+            term = self.div_small(&self.mul(&term, &r2), (2 * n) * (2 * n + 1));
+            if self.is_negligible(&term, &sin) {
+                break;
+            }
+            sin = if n % 2 == 1 { self.sub(&sin, &term) } else { self.add(&sin, &term) };
+        }
+
+        // cos(r) >= sqrt(1/2), there is no cancellation
+        let cos = self.sqrt(&self.sub(&self.one(), &self.mul(&sin, &sin)));
+
+        match self.mod4(&k) {
+            0 => (sin, cos),
+            1 => (cos, sin.neg()),
+            2 => (sin.neg(), cos.neg()),
+            _ => (cos.neg(), sin),
+        }
+    }
+
+    pub fn sin(&self, x: &BigFloat) -> BigFloat {
+        match *x {
+            BigFloat::Finite { .. } => self.sin_cos(x).0,
+            BigFloat::Zero { .. } => x.clone(),
+            _ => self.fallback1(x, f64::sin),
+        }
+    }
+
+    pub fn cos(&self, x: &BigFloat) -> BigFloat {
+        match *x {
+            BigFloat::Finite { .. } => self.sin_cos(x).1,
+            BigFloat::Zero { .. } => self.one(),
+            _ => self.fallback1(x, f64::cos),
+        }
+    }
+
+    pub fn tan(&self, x: &BigFloat) -> BigFloat {
+        match *x {
+            BigFloat::Finite { .. } => {
+                let (sin, cos) = self.sin_cos(x);
+                self.div(&sin, &cos)
+            }
+            BigFloat::Zero { .. } => x.clone(),
+            _ => self.fallback1(x, f64::tan),
+        }
+    }
+
+    pub fn atan(&self, x: &BigFloat) -> BigFloat {
+        match *x {
+            BigFloat::Finite { neg, .. } => {
+                let one = self.one();
+
+                // atan(x) = π/2 - atan(1/x)
+                let abs = x.abs();
+                let invert = self.cmp(&abs, &one) == Ordering::Greater;
+                let mut z = if invert { self.recip(&abs) } else { abs };
+
+                // atan(z) = 2 atan(z/(1 + sqrt(1 + z²)))
+                for _ in 0..ATAN_HALVINGS {
+                    let root = self.sqrt(&self.add(&one, &self.mul(&z, &z)));
+                    z = self.div(&z, &self.add(&one, &root));
+                }
+
+                let mut result = self.atan_series(&z).ldexp(ATAN_HALVINGS);
+                if invert {
+                    result = self.sub(&self.pi.ldexp(-1), &result);
+                }
+
+                if neg { result.neg() } else { result }
+            }
+            BigFloat::Zero { .. } => x.clone(),
+            _ => self.fallback1(x, f64::atan),
+        }
+    }
+
+    pub fn atan2(&self, y: &BigFloat, x: &BigFloat) -> BigFloat {
+        match (y, x) {
+            (&BigFloat::Finite { neg: y_neg, .. }, &BigFloat::Finite { neg: x_neg, .. }) => {
+                let result = self.atan(&self.div(y, x));
+
+                if !x_neg {
+                    result
+                }
+                else if y_neg {
+                    self.sub(&result, &self.pi)
+                }
+                else {
+                    self.add(&result, &self.pi)
+                }
+            }
+            (&BigFloat::Finite { neg, .. }, &BigFloat::Zero { .. }) => {
+                let half_pi = self.pi.ldexp(-1);
+                if neg { half_pi.neg() } else { half_pi }
+            }
+            (&BigFloat::Zero { .. }, &BigFloat::Finite { neg: false, .. }) => y.clone(),
+            (&BigFloat::Zero { neg }, &BigFloat::Finite { neg: true, .. }) => {
+                if neg { self.pi.neg() } else { self.pi() }
+            }
+            _ => self.fallback2(y, x, f64::atan2),
+        }
+    }
+
+    pub fn asin(&self, x: &BigFloat) -> BigFloat {
+        match *x {
+            BigFloat::Finite { neg, .. } => {
+                let one = self.one();
+
+                match self.cmp(&x.abs(), &one) {
+                    Ordering::Greater => BigFloat::Nan,
+                    Ordering::Equal => {
+                        let half_pi = self.pi.ldexp(-1);
+                        if neg { half_pi.neg() } else { half_pi }
+                    }
+                    // asin(x) = atan(x/sqrt((1 - x)(1 + x)))
+                    Ordering::Less => {
+                        let cos = self.sqrt(&self.mul(&self.sub(&one, x), &self.add(&one, x)));
+                        self.atan(&self.div(x, &cos))
+                    }
+                }
+            }
+            BigFloat::Zero { .. } => x.clone(),
+            _ => self.fallback1(x, f64::asin),
+        }
+    }
+
+    pub fn acos(&self, x: &BigFloat) -> BigFloat {
+        if !x.is_finite() {
+            return self.fallback1(x, f64::acos);
+        }
+
+        let one = self.one();
+
+        match self.cmp(&x.abs(), &one) {
+            Ordering::Greater => BigFloat::Nan,
+            Ordering::Equal => if x.is_neg() { self.pi() } else { BigFloat::Zero { neg: false } },
+            // acos(x) = 2 atan(sqrt((1 - x)/(1 + x)))
+            Ordering::Less => {
+                let tan = self.sqrt(&self.div(&self.sub(&one, x), &self.add(&one, x)));
+                self.atan(&tan).ldexp(1)
+            }
+        }
+    }
+
+    pub fn sinh(&self, x: &BigFloat) -> BigFloat {
+        match *x {
+            // sinh(x) = (expm1(x) - expm1(-x))/2, without cancellation
+            BigFloat::Finite { .. } => {
+                self.sub(&self.exp_m1(x), &self.exp_m1(&x.neg())).ldexp(-1)
+            }
+            BigFloat::Zero { .. } => x.clone(),
+            _ => self.fallback1(x, f64::sinh),
+        }
+    }
+
+    pub fn cosh(&self, x: &BigFloat) -> BigFloat {
+        if x.is_finite() {
+            self.add(&self.exp(x), &self.exp(&x.neg())).ldexp(-1)
+        }
+        else {
+            self.fallback1(x, f64::cosh)
+        }
+    }
+
+    pub fn tanh(&self, x: &BigFloat) -> BigFloat {
+        match *x {
+            // tanh(x) = expm1(2x)/(expm1(2x) + 2)
+            BigFloat::Finite { .. } => {
+                let e = self.exp_m1(&x.ldexp(1));
+                if e.is_finite() {
+                    self.div(&e, &self.add(&e, &self.from_f64(2.)))
+                }
+                else {
+                    self.one()
+                }
+            }
+            BigFloat::Zero { .. } => x.clone(),
+            _ => self.fallback1(x, f64::tanh),
+        }
+    }
+
+    pub fn hypot(&self, x: &BigFloat, y: &BigFloat) -> BigFloat {
+        if x.is_finite() && y.is_finite() {
+            self.sqrt(&self.add(&self.mul(x, x), &self.mul(y, y)))
+        }
+        else {
+            self.fallback2(x, y, f64::hypot)
+        }
+    }
+
+    pub fn powf(&self, x: &BigFloat, y: &BigFloat) -> BigFloat {
+        if !x.is_finite() || !y.is_finite() || x.is_zero() {
+            return self.fallback2(x, y, f64::powf);
+        }
+
+        if y.is_zero() {
+            return self.one();
+        }
+
+        // x^y = exp(y ln(x)), negative numbers only have integer powers
+        let (abs, odd) = if x.is_neg() {
+            match self.integer_parity(y) {
+                Some(odd) => (x.abs(), odd),
+                None => return BigFloat::Nan,
+            }
+        }
+        else {
+            (x.clone(), false)
+        };
+
+        let result = self.exp(&self.mul(y, &self.ln(&abs)));
+        if odd { result.neg() } else { result }
+    }
+}
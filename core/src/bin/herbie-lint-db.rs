@@ -0,0 +1,362 @@
+extern crate herbie_lint_core;
+extern crate rusqlite;
+
+use herbie_lint_core::conf;
+use herbie_lint_core::db::{self, Row};
+use herbie_lint_core::estimate::{self, Estimator};
+use herbie_lint_core::lisp::{LispExpr, Parser};
+use rusqlite as sql;
+use std::collections::HashMap;
+use std::env;
+use std::io::{self, Write};
+use std::process;
+
+const USAGE: &'static str = "\
+Inspect and curate a Herbie database.
+
+Usage:
+    herbie-lint-db [--db PATH] list
+    herbie-lint-db [--db PATH] search NAME
+    herbie-lint-db [--db PATH] delete ID...
+    herbie-lint-db [--db PATH] estimate [ID...]
+    herbie-lint-db [--db PATH] merge SOURCE...
+    herbie-lint-db [--db PATH] stats
+    herbie-lint-db [--db PATH] validate
+
+Commands:
+    list        Print every entry, as both Lisp and Rust
+    search      Print the entries using the function NAME, e.g. `sqrt`, or recorded
+                for a function NAME of the user's code
+    delete      Delete the entries with the given ids
+    estimate    Measure the bits of error of the entries with the given ids, or
+                of all the entries, without Herbie, and print them with the
+                stored ones
+    merge       Merge the entries of the SOURCE databases, keeping the best result
+                for each expression, and report conflicting entries. The database
+                is created if it does not exist
+    stats       Print statistics about the database
+    validate    Check that every entry parses, exit with status 1 otherwise
+
+Options:
+    --db PATH   Path to the database. Defaults to the writable database of
+                Herbie.toml, or its first database, or \"Herbie.db\".
+";
+
+fn main() {
+    let mut args = env::args().skip(1).collect::<Vec<_>>();
+
+    let db_path = if args.first().map_or(false, |arg| arg == "--db") {
+        if args.len() < 2 {
+            usage();
+        }
+        let path = args.remove(1);
+        args.remove(0);
+        path
+    }
+    else {
+        match conf::read_conf() {
+            Ok(conf) => {
+                for warning in &conf.warnings {
+                    let _ = writeln!(io::stderr(), "herbie-lint-db: warning: {}", warning);
+                }
+
+                match conf.writable_database().or_else(|| conf.databases.first()) {
+                    Some(database) => database.path.clone(),
+                    None => fail("No database in Herbie.toml"),
+                }
+            }
+            Err(err) => fail(&err.to_string()),
+        }
+    };
+
+    let (command, params) = match args.split_first() {
+        Some((command, params)) => (command.clone(), params),
+        None => usage(),
+    };
+
+    let connection = match &command[..] {
+        "delete" => db::open(&db_path, true),
+        "merge" => db::create(&db_path),
+        _ => db::open(&db_path, false),
+    };
+    let connection = match connection {
+        Ok(connection) => connection,
+        Err(err) => fail(&format!("Could not open {}: {}", db_path, err)),
+    };
+
+    let result = match (&command[..], params.len()) {
+        ("list", 0) => list(&connection),
+        ("search", 1) => search(&connection, &params[0]),
+        ("delete", n) if n > 0 => delete(&connection, params),
+        ("estimate", _) => estimate(&connection, params),
+        ("merge", n) if n > 0 => merge(&connection, params),
+        ("stats", 0) => stats(&connection),
+        ("validate", 0) => validate(&connection),
+        _ => usage(),
+    };
+
+    match result {
+        Ok(true) => (),
+        Ok(false) => process::exit(1),
+        Err(err) => fail(&format!("Could not read {}: {}", db_path, err)),
+    }
+}
+
+fn usage() -> ! {
+    let _ = write!(io::stderr(), "{}", USAGE);
+    process::exit(2)
+}
+
+fn fail(message: &str) -> ! {
+    let _ = writeln!(io::stderr(), "herbie-lint-db: {}", message);
+    process::exit(2)
+}
+
+/// Parse both sides of a row.
+fn parse_row(row: &Row) -> Result<(LispExpr, LispExpr), String> {
+    let mut parser = Parser::new();
+
+    let cmdin = try!(parser.parse(&row.cmdin).map_err(|err| format!("cmdin: {:?}", err)));
+    let cmdout = try!(parser.parse(&row.cmdout).map_err(|err| format!("cmdout: {:?}", err)));
+
+    Ok((cmdin, cmdout))
+}
+
+fn error_to_string(err: Option<f64>) -> String {
+    err.map_or("?".into(), |err| format!("{:.1}", err))
+}
+
+fn print_row(connection: &sql::Connection, row: &Row) -> Result<(), sql::Error> {
+    let names = try!(db::function_names(connection, row.id));
+
+    print!("#{}", row.id);
+    if !names.is_empty() {
+        print!(" ({})", names.join(", "));
+    }
+    println!(": {} → {} bits of error", error_to_string(row.errin), error_to_string(row.errout));
+
+    match parse_row(row) {
+        Ok((cmdin, cmdout)) => {
+            println!("    in:  {}", cmdin.to_lisp("herbie"));
+            if let Some(rust) = cmdin.to_rust_placeholder("herbie") {
+                println!("         {}", rust);
+            }
+            println!("    out: {}", cmdout.to_lisp("herbie"));
+            if let Some(rust) = cmdout.to_rust_placeholder("herbie") {
+                println!("         {}", rust);
+            }
+        }
+        Err(err) => {
+            println!("    in:  {}", row.cmdin);
+            println!("    out: {}", row.cmdout);
+            println!("    invalid {}", err);
+        }
+    }
+
+    Ok(())
+}
+
+fn list(connection: &sql::Connection) -> Result<bool, sql::Error> {
+    for row in try!(db::read_rows(connection)) {
+        try!(print_row(connection, &row));
+    }
+
+    Ok(true)
+}
+
+fn search(connection: &sql::Connection, name: &str) -> Result<bool, sql::Error> {
+    let mut found = false;
+
+    for row in try!(db::read_rows(connection)) {
+        let uses_fun = match parse_row(&row) {
+            Ok((cmdin, cmdout)) => {
+                cmdin.functions().contains(&name) || cmdout.functions().contains(&name)
+            }
+            Err(..) => false,
+        };
+
+        if uses_fun || try!(db::function_names(connection, row.id)).iter().any(|n| n == name) {
+            try!(print_row(connection, &row));
+            found = true;
+        }
+    }
+
+    Ok(found)
+}
+
+fn delete(connection: &sql::Connection, ids: &[String]) -> Result<bool, sql::Error> {
+    let mut ok = true;
+
+    for id in ids {
+        match id.parse() {
+            Ok(id) => {
+                if try!(db::delete_row(connection, id)) {
+                    println!("Deleted #{}", id);
+                }
+                else {
+                    let _ = writeln!(io::stderr(), "No entry #{}", id);
+                    ok = false;
+                }
+            }
+            Err(..) => {
+                let _ = writeln!(io::stderr(), "Invalid id: {}", id);
+                ok = false;
+            }
+        }
+    }
+
+    Ok(ok)
+}
+
+fn estimate(connection: &sql::Connection, ids: &[String]) -> Result<bool, sql::Error> {
+    let mut ids = match ids.iter().map(|id| id.parse()).collect::<Result<Vec<i64>, _>>() {
+        Ok(ids) => ids,
+        Err(..) => usage(),
+    };
+
+    let estimator = Estimator::new(estimate::DEFAULT_POINTS, 1);
+    let mut ok = true;
+
+    for row in try!(db::read_rows(connection)) {
+        if !ids.is_empty() {
+            match ids.iter().position(|&id| id == row.id) {
+                Some(idx) => ids.remove(idx),
+                None => continue,
+            };
+        }
+
+        print!("#{}: stored {} → {} bits of error",
+               row.id,
+               error_to_string(row.errin),
+               error_to_string(row.errout));
+
+        match parse_row(&row) {
+            Ok((cmdin, cmdout)) => {
+                match estimator.errors(&[&cmdin, &cmdout]) {
+                    Ok(errors) => println!(", measured {:.1} → {:.1}", errors[0], errors[1]),
+                    Err(err) => println!(", cannot be measured: {}", err),
+                }
+            }
+            Err(err) => {
+                println!(", invalid {}", err);
+                ok = false;
+            }
+        }
+    }
+
+    for id in ids {
+        let _ = writeln!(io::stderr(), "No entry #{}", id);
+        ok = false;
+    }
+
+    Ok(ok)
+}
+
+fn merge(connection: &sql::Connection, sources: &[String]) -> Result<bool, sql::Error> {
+    for source in sources {
+        let report = match db::open(source, false).and_then(|from| {
+            db::merge_databases(connection, &from)
+        }) {
+            Ok(report) => report,
+            Err(err) => fail(&format!("Could not merge {}: {}", source, err)),
+        };
+
+        println!("{}: {} added, {} replaced, {} kept, {} conflicts, {} invalid",
+                 source,
+                 report.added,
+                 report.replaced,
+                 report.kept,
+                 report.conflicts.len(),
+                 report.invalid.len());
+
+        for conflict in &report.conflicts {
+            println!("    conflict for #{}: {}", conflict.existing.id, conflict.existing.cmdin);
+            println!("        existing: {} ({} bits)",
+                     conflict.existing.cmdout,
+                     error_to_string(conflict.existing.errout));
+            println!("        {}: {} ({} bits)",
+                     if conflict.replaced { "replaced by" } else { "ignored" },
+                     conflict.new.cmdout,
+                     error_to_string(conflict.new.errout));
+        }
+
+        for row in &report.invalid {
+            println!("    invalid entry #{} ignored", row.id);
+        }
+    }
+
+    Ok(true)
+}
+
+fn stats(connection: &sql::Connection) -> Result<bool, sql::Error> {
+    let rows = try!(db::read_rows(connection));
+
+    let mut invalid = 0;
+    let mut unchanged = 0;
+    let mut improved = 0;
+    let mut improvement = 0.;
+    let mut seeds = HashMap::new();
+    let mut funs = HashMap::new();
+
+    for row in &rows {
+        *seeds.entry(&row.opts[..]).or_insert(0) += 1;
+
+        match parse_row(row) {
+            Ok((cmdin, cmdout)) => {
+                for fun in cmdin.functions() {
+                    *funs.entry(fun.to_owned()).or_insert(0) += 1;
+                }
+
+                if cmdin == cmdout {
+                    unchanged += 1;
+                }
+            }
+            Err(..) => invalid += 1,
+        }
+
+        if let (Some(errin), Some(errout)) = (row.errin, row.errout) {
+            if errin > errout {
+                improved += 1;
+                improvement += errin - errout;
+            }
+        }
+    }
+
+    println!("Entries:             {}", rows.len());
+    println!("Invalid entries:     {}", invalid);
+    println!("Unchanged by Herbie: {}", unchanged);
+    println!("Improved by Herbie:  {}", improved);
+    if improved != 0 {
+        println!("Average improvement: {:.1} bits", improvement / improved as f64);
+    }
+    println!("Seeds:               {}", seeds.len());
+
+    let mut funs = funs.into_iter().collect::<Vec<_>>();
+    funs.sort_by(|&(ref a, na), &(ref b, nb)| (nb, a).cmp(&(na, b)));
+    if !funs.is_empty() {
+        println!("Functions:");
+        for (fun, n) in funs {
+            println!("    {:<12} {}", fun, n);
+        }
+    }
+
+    Ok(true)
+}
+
+fn validate(connection: &sql::Connection) -> Result<bool, sql::Error> {
+    let rows = try!(db::read_rows(connection));
+    let mut ok = true;
+
+    for row in &rows {
+        if let Err(err) = parse_row(row) {
+            println!("#{}: invalid {}", row.id, err);
+            ok = false;
+        }
+    }
+
+    if ok {
+        println!("All {} entries are valid", rows.len());
+    }
+
+    Ok(ok)
+}
@@ -0,0 +1,356 @@
+//! The analysis of an expression, which is the same in the lint pass and in the standalone binary:
+//! the expression is matched with the database or given to Herbie, and the result is checked
+//! against the configuration. The frontends find the expressions, print the rewrites as Rust and
+//! report the outcome.
+
+use conf::Conf;
+use cost::Cost;
+use estimate::{Estimator, Rejection};
+use herbie::{self, InitError, Output, Sub, Verification};
+use ignore::Ignores;
+use lisp::{self, LispExpr};
+use matcher::{self, Bindings, Source};
+use report::{Entry, Status};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use suggestion::{Applicability, Location, Suggestion};
+
+/// A message for the user about the analysed expression.
+#[derive(Debug, PartialEq)]
+pub enum Message {
+    /// A warning, with the level of the lint in the plugin.
+    Warning(String),
+    Note(String),
+}
+
+/// The result of the analysis of an expression.
+#[derive(Debug)]
+pub struct Outcome {
+    /// What happened to the expression, one entry per matching substitution of the database.
+    pub entries: Vec<Entry>,
+    /// The most accurate suggestion, if one is worth reporting.
+    pub suggestion: Option<Suggestion>,
+    pub messages: Vec<Message>,
+}
+
+/// Analyses the expressions of a crate with the databases and the ignore file of a
+/// configuration.
+#[derive(Debug)]
+pub struct Checker {
+    conf: Conf,
+    subs: Vec<Sub>,
+    /// The expressions which are not reported.
+    ignores: Ignores,
+    /// Used to verify the substitutions and to estimate them on the range of their inputs,
+    /// created when first needed.
+    estimator: Option<Estimator>,
+}
+
+impl Checker {
+    /// Create a checker using the databases and the ignore file of `conf`.
+    pub fn new(conf: Conf) -> Result<Checker, InitError> {
+        let subs = try!(herbie::read_subs(&conf));
+        let ignores = try!(herbie::read_ignores(&conf));
+
+        Ok(Checker {
+            conf: conf,
+            subs: subs,
+            ignores: ignores,
+            estimator: None,
+        })
+    }
+
+    pub fn conf(&self) -> &Conf {
+        &self.conf
+    }
+
+    /// Analyse `expr`, which is at `location`. `rust` prints a rewrite of `expr` as Rust code,
+    /// unless it uses a function with no Rust equivalent, and `on_herbie` is called when Herbie
+    /// starts, as it might take a while.
+    pub fn check<S, R, F>(
+        &mut self,
+        source: &S,
+        expr: &S::Expr,
+        location: Location,
+        rust: R,
+        on_herbie: F
+    ) -> Outcome
+    where S: Source,
+          R: Fn(&LispExpr, &Bindings<S::Expr>) -> Option<String>,
+          F: FnOnce() {
+        let expression = source.snippet(expr);
+        let mut outcome = Outcome { entries: Vec::new(), suggestion: None, messages: Vec::new() };
+
+        if self.ignores.contains(&location.file_name, &expression) {
+            outcome.entries.push(Entry::new(&location, expression, Status::Ignored));
+            return outcome;
+        }
+
+        let conf = &self.conf;
+        let mut got_match = false;
+        // several substitutions can match the same expression, only the most accurate one is
+        // reported
+        let mut best: Option<(Entry, Suggestion)> = None;
+
+        for sub in &mut self.subs {
+            let bindings = match matcher::match_expr(source, expr, &sub.cmdin) {
+                Some(bindings) => bindings,
+                None => continue,
+            };
+
+            if conf.verify_db {
+                match sub.verify(herbie::estimator(&mut self.estimator)) {
+                    Some(Rejection::Error(err)) => {
+                        outcome.messages.push(Message::Note(format!(
+                            "Using entry #{} of the Herbie database {} without verifying it: {}",
+                            sub.id,
+                            sub.database,
+                            err
+                        )));
+                    }
+                    Some(rejection) => {
+                        outcome.messages.push(Message::Warning(format!(
+                            "Ignoring entry #{} of the Herbie database {}: {}",
+                            sub.id,
+                            sub.database,
+                            rejection
+                        )));
+                    }
+                    None => (),
+                }
+
+                if sub.verification == Verification::Quarantined {
+                    continue;
+                }
+            }
+
+            got_match = true;
+
+            let mut entry = Entry::new(&location, expression.clone(), Status::Database);
+            let (mut errin, mut errout) = (sub.errin, sub.errout);
+
+            // the database's estimate is over all doubles, re-estimate it if the inputs are known
+            // to be in a smaller range
+            let ranges = match ranges(source, &bindings) {
+                Ok(ranges) => ranges,
+                Err(note) => {
+                    if !outcome.messages.contains(&note) {
+                        outcome.messages.push(note);
+                    }
+                    HashMap::new()
+                }
+            };
+            if !ranges.is_empty() {
+                let estimator = herbie::estimator(&mut self.estimator);
+                // keep the database's estimate if the expressions cannot be evaluated
+                if let Ok(errors) = estimator.errors_in_ranges(&[&sub.cmdin, &sub.cmdout],
+                                                               &ranges) {
+                    errin = errors[0];
+                    errout = errors[1];
+                }
+            }
+
+            entry.errin = Some(errin);
+            entry.errout = Some(errout);
+
+            if errin <= errout {
+                outcome.messages.push(Message::Note(format!(
+                    "Not suggesting a rewriting from the Herbie database: it does not improve the \
+                     accuracy on the known range of the inputs, {:.1} → {:.1} bits of error \
+                     instead of {:.1} → {:.1} on all doubles",
+                    errin,
+                    errout,
+                    sub.errin,
+                    sub.errout
+                )));
+                entry.status = Status::NotOnRange;
+            }
+            else if let Some(suggestion) = suggest(conf, source, &location, &rust, &sub.cmdin,
+                                                   &sub.cmdout, &bindings, errin, errout,
+                                                   &mut entry) {
+                if best.as_ref().map_or(true, |best| errout < best.1.errout) {
+                    best = Some((entry, suggestion));
+                }
+                continue;
+            }
+
+            outcome.entries.push(entry);
+        }
+
+        if let Some((entry, suggestion)) = best {
+            outcome.entries.push(entry);
+            outcome.suggestion = Some(suggestion);
+        }
+        else if !got_match {
+            let entry = Entry::new(&location, expression, Status::Unknown);
+            match try_with_herbie(conf, source, expr, &location, &rust, on_herbie, entry,
+                                  &mut outcome.messages) {
+                Ok((entry, suggestion)) => {
+                    outcome.entries.push(entry);
+                    outcome.suggestion = suggestion;
+                }
+                Err((mut entry, err)) => {
+                    outcome.messages.push(Message::Warning(err.clone().into_owned()));
+                    entry.status = Status::Error;
+                    entry.message = Some(err.into_owned());
+                    outcome.entries.push(entry);
+                }
+            }
+        }
+
+        outcome
+    }
+}
+
+/// Return the known ranges of the identifiers of `bindings`, see `Source::range`. If one of them
+/// is empty, e.g. because of contradictory assertions, no range is used and a note explaining it
+/// is returned instead.
+fn ranges<S: Source>(
+    source: &S,
+    bindings: &Bindings<S::Expr>
+) -> Result<HashMap<u64, (f64, f64)>, Message> {
+    let mut ranges = HashMap::new();
+
+    for &id in &bindings.order {
+        let expr = bindings.idents[&id];
+        if let Some((lo, hi)) = source.range(expr) {
+            if lo > hi {
+                return Err(Message::Note(format!(
+                    "Not using the known ranges of the inputs: the range of `{}`, {} to {}, is \
+                     empty, e.g. because of contradictory assertions",
+                    source.snippet(expr),
+                    lo,
+                    hi
+                )));
+            }
+
+            ranges.insert(id, (lo, hi));
+        }
+    }
+
+    Ok(ranges)
+}
+
+/// Give an expression which is not in the database to Herbie. Return its entry and the suggestion
+/// if it is worth reporting. The messages for the user, e.g. if Herbie timed out, are added to
+/// `messages`.
+fn try_with_herbie<S, R, F>(
+    conf: &Conf,
+    source: &S,
+    expr: &S::Expr,
+    location: &Location,
+    rust: &R,
+    on_herbie: F,
+    mut entry: Entry,
+    messages: &mut Vec<Message>
+) -> Result<(Entry, Option<Suggestion>), (Entry, Cow<'static, str>)>
+where S: Source,
+      R: Fn(&LispExpr, &Bindings<S::Expr>) -> Option<String>,
+      F: FnOnce() {
+    let (lisp_expr, bindings) = match matcher::from_expr(source, expr) {
+        Some(r) => r,
+        // not an error, the expression might for example contain a function unknown to Herbie
+        None => return Ok((entry, None)),
+    };
+
+    if lisp_expr.depth() <= 2 {
+        entry.status = Status::TooShallow;
+        return Ok((entry, None));
+    }
+
+    let ranges = ranges(source, &bindings).unwrap_or_else(|note| {
+        messages.push(note);
+        HashMap::new()
+    });
+    let output = match herbie::call(conf, &lisp_expr, bindings.idents.len(), &ranges, on_herbie) {
+        Ok(output) => output,
+        Err(err) => return Err((entry, err)),
+    };
+
+    let (errin, errout, cmdout) = match output {
+        Output::Done { errin, errout, cmdout } => (errin, errout, cmdout),
+        Output::Disabled => {
+            entry.status = Status::HerbieDisabled;
+            return Ok((entry, None));
+        }
+        Output::TimedOut => {
+            entry.status = Status::Timeout;
+            messages.push(Message::Note("Herbie timed out".into()));
+            return Ok((entry, None));
+        }
+    };
+
+    entry.status = Status::NoImprovement;
+    entry.errin = Some(errin);
+    entry.errout = Some(errout);
+
+    if errin <= errout {
+        return Ok((entry, None));
+    }
+
+    let mut parser = lisp::Parser::new();
+    let cmdout = match parser.parse(&cmdout) {
+        Ok(cmdout) => cmdout,
+        _ => return Err((entry, "Could not understand herbie-inout cmdout".into())),
+    };
+
+    if let Err(err) = herbie::save(conf, &lisp_expr.to_lisp("herbie"), &cmdout, "", errin, errout) {
+        return Err((entry, err.into()));
+    }
+
+    entry.status = Status::Herbie;
+    let suggestion = suggest(conf, source, location, rust, &lisp_expr, &cmdout, &bindings,
+                             errin, errout, &mut entry);
+    Ok((entry, suggestion))
+}
+
+/// Check that replacing `cmdin` by `cmdout` is worth reporting with the configuration, and return
+/// the suggestion if it is. Otherwise, `entry` is updated with the reason.
+fn suggest<S, R>(
+    conf: &Conf,
+    source: &S,
+    location: &Location,
+    rust: &R,
+    cmdin: &LispExpr,
+    cmdout: &LispExpr,
+    bindings: &Bindings<S::Expr>,
+    errin: f64,
+    errout: f64,
+    entry: &mut Entry
+) -> Option<Suggestion>
+where S: Source, R: Fn(&LispExpr, &Bindings<S::Expr>) -> Option<String> {
+    let (costin, costout) = (Cost::of_expr(cmdin), Cost::of_expr(cmdout));
+
+    if errin - errout < conf.min_improvement {
+        entry.status = Status::BelowThreshold;
+        return None;
+    }
+
+    if conf.is_too_costly(costin, costout) {
+        entry.status = Status::TooCostly;
+        return None;
+    }
+
+    let replacement = match rust(cmdout, bindings) {
+        Some(replacement) => replacement,
+        None => {
+            entry.status = Status::NoRustEquivalent;
+            return None;
+        }
+    };
+
+    let (applicability, message) = if bindings.is_exact_rewrite(source, cmdout) {
+        (Applicability::MachineApplicable, "Try this")
+    }
+    else {
+        (
+            Applicability::MaybeIncorrect,
+            "Try this, but check that it does not change how often sub-expressions are evaluated"
+        )
+    };
+
+    entry.suggestion = Some(replacement.clone());
+    Some(Suggestion::new(
+        location.clone(), message, replacement, applicability, errin, errout, costin, costout
+    ))
+}
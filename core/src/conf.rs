@@ -0,0 +1,741 @@
+use cost::Cost;
+use rustc_serialize::Decodable;
+use std;
+use std::borrow::Cow;
+use std::env;
+use std::fs::File;
+use std::io::{Error as IOError, Read};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use toml;
+
+// That's the default seed used by the Haskell plugin
+const DEFAULT_HERBIE_SEED: &'static str = "#(1461197085 2376054483 1553562171 1611329376 \
+                                           2497620867 2308122621)";
+const DEFAULT_DB_PATH: &'static str = "Herbie.db";
+const DEFAULT_IGNORE_PATH: &'static str = "Herbie.ignore";
+const DEFAULT_MIN_IMPROVEMENT: f64 = 0.;
+const DEFAULT_TIMEOUT: u32 = 120;
+
+/// The keys of `UxConf`.
+const KEYS: &'static [&'static str] = &[
+    "databases",
+    "db_path",
+    "fixes_path",
+    "fma",
+    "herbie_seed",
+    "ignore_path",
+    "max_cost_factor",
+    "min_improvement",
+    "report_path",
+    "sarif_path",
+    "timeout",
+    "use_herbie",
+    "verify_db",
+];
+
+/// The keys of `UxDatabase`.
+const DATABASE_KEYS: &'static [&'static str] = &["path", "user", "writable"];
+
+#[derive(Debug, Default, RustcDecodable)]
+pub struct UxConf {
+    /// The databases, searched in order. Cannot be used with `db_path`.
+    pub databases: Option<Vec<UxDatabase>>,
+    /// Path to the database. Defaults to "Herbie.db". Shorthand for a single writable database.
+    pub db_path: Option<String>,
+    /// Path to a file where suggestions are written as JSON, one per line, so that they can be
+    /// applied automatically. If not provided, suggestions are only reported as warnings.
+    pub fixes_path: Option<String>,
+    /// Let Herbie use fused multiply-adds, printed as `mul_add`, in its suggestions. Defaults to
+    /// false.
+    pub fma: Option<bool>,
+    /// The seed use by Herbie. If not provided, a fixed seed will be used. Fixing the seed ensures
+    /// deterministic builds.
+    pub herbie_seed: Option<String>,
+    /// Path to the file listing the expressions which are not reported, where `herbie-lint
+    /// --review` records the rejected suggestions. Defaults to "Herbie.ignore".
+    pub ignore_path: Option<String>,
+    /// Maximum factor by which a suggestion can increase the cost of an expression, as roughly
+    /// estimated from its operations, for it to be reported. If not provided, suggestions are
+    /// reported whatever their cost.
+    pub max_cost_factor: Option<f64>,
+    /// Minimum improvement, in average bits of error, for a suggestion to be reported. Defaults
+    /// to 0, i.e. any improvement is reported.
+    pub min_improvement: Option<f64>,
+    /// Path, without extension, of a report listing all the analysed expressions. The report is
+    /// written as both JSON and HTML. If not provided, no report is written.
+    pub report_path: Option<String>,
+    /// Path to a SARIF 2.1 file with the unstable expressions and their suggestions. If not
+    /// provided, no SARIF file is written.
+    pub sarif_path: Option<String>,
+    /// Maximum time in seconds that Herbie is allowed to play with an expression. If null, allow
+    /// Herbie to run indefinitely. Default is two minutes.
+    pub timeout: Option<u32>,
+    /// Allow the plugin to call Herbie on unknown expressions. Positive results from Herbie will
+    /// be cached in the database.
+    /// If ‘true’, the plugin will fail if it cannot find the executable.
+    /// If ‘false’, the plugin will not try to run Herbie.
+    /// By default, the plugin will call the executable only if it's found, but won't complain
+    /// otherwise.
+    pub use_herbie: Option<bool>,
+    /// Check the suggestions from the database by evaluating them before reporting them. Entries
+    /// which are not equivalent to their expression or not more accurate are ignored. Defaults
+    /// to false.
+    pub verify_db: Option<bool>,
+}
+
+#[derive(Debug, RustcDecodable)]
+pub struct UxDatabase {
+    /// Path to the database. Either `path` or `user` must be provided.
+    pub path: Option<String>,
+    /// Use the per-user cache, `$XDG_CACHE_HOME/herbie-lint/Herbie.db`.
+    pub user: Option<bool>,
+    /// Write the results from Herbie to this database. At most one database can be writable.
+    /// Defaults to false.
+    pub writable: Option<bool>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum UseHerbieConf {
+    Default,
+    No,
+    Yes,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct Database {
+    pub path: String,
+    /// Whether this is the per-user cache. It is skipped if it does not exist yet, and created
+    /// when written.
+    pub user: bool,
+    pub writable: bool,
+}
+
+#[derive(Debug)]
+pub struct Conf {
+    pub databases: Vec<Database>,
+    pub fixes_path: Option<String>,
+    pub fma: bool,
+    pub herbie_seed: Cow<'static, str>,
+    pub ignore_path: String,
+    pub max_cost_factor: Option<f64>,
+    pub min_improvement: f64,
+    pub report_path: Option<String>,
+    pub sarif_path: Option<String>,
+    pub timeout: Option<u32>,
+    pub use_herbie: UseHerbieConf,
+    pub verify_db: bool,
+    /// Warnings about the configuration, such as unknown keys.
+    pub warnings: Vec<String>,
+}
+
+impl Default for Conf {
+    fn default() -> Conf {
+        Conf {
+            databases: vec![Database {
+                path: DEFAULT_DB_PATH.into(),
+                user: false,
+                writable: true,
+            }],
+            fixes_path: None,
+            fma: false,
+            herbie_seed: DEFAULT_HERBIE_SEED.into(),
+            ignore_path: DEFAULT_IGNORE_PATH.into(),
+            max_cost_factor: None,
+            min_improvement: DEFAULT_MIN_IMPROVEMENT,
+            report_path: None,
+            sarif_path: None,
+            timeout: Some(DEFAULT_TIMEOUT),
+            use_herbie: UseHerbieConf::Default,
+            verify_db: false,
+            warnings: vec![],
+        }
+    }
+}
+
+impl Conf {
+    /// Return the database where results from Herbie are written, if any.
+    pub fn writable_database(&self) -> Option<&Database> {
+        self.databases.iter().find(|database| database.writable)
+    }
+
+    /// Whether the cost of a suggestion grows by more than `max_cost_factor`.
+    pub fn is_too_costly(&self, costin: Cost, costout: Cost) -> bool {
+        self.max_cost_factor.map_or(false, |factor| {
+            costout.total() as f64 > costin.total() as f64 * factor
+        })
+    }
+
+    /// Convert the configuration found in `dir`, relative paths are resolved from there.
+    fn from_ux(ux: UxConf, dir: &Path) -> Result<Conf, ConfError> {
+        let databases = match (ux.databases, ux.db_path) {
+            (Some(..), Some(..)) => {
+                return Err(invalid("`databases` and `db_path` cannot be used together"));
+            }
+            (Some(databases), None) => {
+                try!(databases.into_iter()
+                              .map(|database| Database::from_ux(database, dir))
+                              .collect::<Result<Vec<_>, _>>())
+            }
+            (None, db_path) => {
+                vec![Database {
+                    path: resolve(dir, &db_path.unwrap_or_else(|| DEFAULT_DB_PATH.into())),
+                    user: false,
+                    writable: true,
+                }]
+            }
+        };
+
+        if databases.iter().filter(|database| database.writable).count() > 1 {
+            return Err(invalid("at most one database can be writable"));
+        }
+
+        if let Some(ref seed) = ux.herbie_seed {
+            try!(check_seed(seed));
+        }
+
+        if let Some(max_cost_factor) = ux.max_cost_factor {
+            if !(max_cost_factor > 0.) || max_cost_factor.is_infinite() {
+                return Err(invalid("`max_cost_factor` must be a positive number"));
+            }
+        }
+
+        if let Some(min_improvement) = ux.min_improvement {
+            if !(min_improvement >= 0.) || min_improvement.is_infinite() {
+                return Err(invalid("`min_improvement` must be a non-negative number of bits"));
+            }
+        }
+
+        Ok(Conf {
+            databases: databases,
+            fixes_path: ux.fixes_path.map(|path| resolve(dir, &path)),
+            fma: ux.fma.unwrap_or(false),
+            herbie_seed: ux.herbie_seed.map_or(DEFAULT_HERBIE_SEED.into(), Into::into),
+            ignore_path: resolve(dir, &ux.ignore_path.unwrap_or_else(|| {
+                DEFAULT_IGNORE_PATH.into()
+            })),
+            max_cost_factor: ux.max_cost_factor,
+            min_improvement: ux.min_improvement.unwrap_or(DEFAULT_MIN_IMPROVEMENT),
+            report_path: ux.report_path.map(|path| resolve(dir, &path)),
+            sarif_path: ux.sarif_path.map(|path| resolve(dir, &path)),
+            timeout: ux.timeout.map_or(Some(DEFAULT_TIMEOUT), |t| {
+                if t == 0 {
+                    None
+                }
+                else {
+                    Some(t)
+                }
+            }),
+            use_herbie: ux.use_herbie.map_or(UseHerbieConf::Default, |u| {
+                if u {
+                    UseHerbieConf::Yes
+                }
+                else {
+                    UseHerbieConf::No
+                }
+            }),
+            verify_db: ux.verify_db.unwrap_or(false),
+            warnings: vec![],
+        })
+    }
+}
+
+impl Database {
+    fn from_ux(ux: UxDatabase, dir: &Path) -> Result<Database, ConfError> {
+        let user = ux.user.unwrap_or(false);
+
+        let path = match (ux.path, user) {
+            (Some(path), false) => resolve(dir, &path),
+            (None, true) => {
+                let path = try!(user_cache_path().ok_or_else(|| {
+                    invalid("could not locate the user cache, set XDG_CACHE_HOME or HOME")
+                }));
+                path.to_string_lossy().into_owned()
+            }
+            _ => return Err(invalid("each database needs either a `path` or `user = true`")),
+        };
+
+        Ok(Database {
+            path: path,
+            user: user,
+            writable: ux.writable.unwrap_or(false),
+        })
+    }
+}
+
+/// Check that a seed has the format expected by Herbie, that of a Racket pseudo-random generator:
+/// `#(n1 n2 n3 n4 n5 n6)`, where `n1`, `n2` and `n3` are less than 4294967087 and not all 0, and
+/// `n4`, `n5` and `n6` are less than 4294944443 and not all 0.
+fn check_seed(seed: &str) -> Result<(), ConfError> {
+    let error = |reason: &str| {
+        Err(ConfError::Invalid {
+            message: format!("invalid `herbie_seed` `{}`: {}", seed, reason),
+        })
+    };
+
+    let seed = seed.trim();
+    if !seed.starts_with("#(") || !seed.ends_with(')') {
+        return error("expected `#(` followed by six integers and `)`");
+    }
+
+    let mut numbers = vec![];
+    for number in seed[2..seed.len() - 1].split_whitespace() {
+        match number.parse::<u64>() {
+            Ok(number) => numbers.push(number),
+            Err(..) => return error(&format!("`{}` is not a non-negative integer", number)),
+        }
+    }
+
+    if numbers.len() != 6 {
+        return error(&format!("expected six integers, got {}", numbers.len()));
+    }
+
+    if numbers[..3].iter().any(|&n| n >= 4294967087)
+    || numbers[3..].iter().any(|&n| n >= 4294944443) {
+        return error("the first three integers must be less than 4294967087 and the last three \
+                      less than 4294944443");
+    }
+
+    if numbers[..3].iter().all(|&n| n == 0) || numbers[3..].iter().all(|&n| n == 0) {
+        return error("the first three and the last three integers cannot all be 0");
+    }
+
+    Ok(())
+}
+
+/// Resolve a path relative to the directory of the configuration.
+fn resolve(dir: &Path, path: &str) -> String {
+    dir.join(path).to_string_lossy().into_owned()
+}
+
+/// Return the path of the per-user cache, following the XDG base directory specification.
+fn user_cache_path() -> Option<PathBuf> {
+    let cache = match env::var_os("XDG_CACHE_HOME") {
+        Some(ref cache) if cache != "" => PathBuf::from(cache),
+        _ => {
+            match env::var_os("HOME") {
+                Some(ref home) if home != "" => PathBuf::from(home).join(".cache"),
+                _ => return None,
+            }
+        }
+    };
+
+    Some(cache.join("herbie-lint").join("Herbie.db"))
+}
+
+fn invalid(message: &str) -> ConfError {
+    ConfError::Invalid { message: message.into() }
+}
+
+#[derive(Debug)]
+pub enum ConfError {
+    Io {
+        error: IOError,
+        file: String,
+    },
+    /// A TOML syntax error.
+    Parse {
+        file: String,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// A key with a value of the wrong type.
+    Value {
+        file: String,
+        line: Option<usize>,
+        message: String,
+    },
+    Invalid {
+        message: String,
+    },
+}
+
+impl std::fmt::Display for ConfError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        match *self {
+            ConfError::Io { ref error, ref file } => write!(f, "Error reading {}: {}", file, error),
+            ConfError::Parse { ref file, line, column, ref message } => {
+                write!(f, "Syntax error in {} at line {}, column {}: {}",
+                       file, line, column, message)
+            }
+            ConfError::Value { ref file, line: Some(line), ref message } => {
+                write!(f, "Invalid value in {} at line {}: {}", file, line, message)
+            }
+            ConfError::Value { ref file, line: None, ref message } => {
+                write!(f, "Invalid value in {}: {}", file, message)
+            }
+            ConfError::Invalid { ref message } => write!(f, "Invalid configuration: {}", message),
+        }
+    }
+}
+
+/// Read the configuration of the crate being compiled. See `find_conf`.
+pub fn read_conf() -> Result<Conf, ConfError> {
+    let start = match env::var_os("CARGO_MANIFEST_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            try!(env::current_dir().map_err(|err| {
+                ConfError::Io { error: err, file: "the current directory".into() }
+            }))
+        }
+    };
+
+    find_conf(&start)
+}
+
+/// Look for the configuration from the crate directory `start` up to the root of its workspace,
+/// if any. In each directory, a `Herbie.toml` file takes precedence over a
+/// `[package.metadata.herbie]` table in `Cargo.toml`. Relative paths in the configuration are
+/// relative to the directory where it was found, or to `start` if none was found.
+/// `HERBIE_LINT_*` environment variables take precedence over the configuration, see
+/// `find_conf_with_env`.
+pub fn find_conf(start: &Path) -> Result<Conf, ConfError> {
+    find_conf_with_env(start, |name| env::var(name).ok())
+}
+
+/// Same as `find_conf`, but environment variables are looked up with `var`.
+pub fn find_conf_with_env<F>(start: &Path, var: F) -> Result<Conf, ConfError>
+where F: Fn(&str) -> Option<String> {
+    let (mut ux, dir, warnings) = try!(find_ux_conf(start));
+    try!(override_from_env(&mut ux, var));
+
+    let mut conf = try!(Conf::from_ux(ux, dir));
+    conf.warnings = warnings;
+    Ok(conf)
+}
+
+/// Find and decode the configuration, and return the directory where it was found and the
+/// warnings about it.
+fn find_ux_conf(start: &Path) -> Result<(UxConf, &Path, Vec<String>), ConfError> {
+    let root = workspace_root(start).unwrap_or(start);
+
+    let mut dir = start;
+    loop {
+        let herbie_toml = dir.join("Herbie.toml");
+        if herbie_toml.exists() {
+            let source = try!(read_file(&herbie_toml));
+            let file = display_path(start, dir, "Herbie.toml");
+
+            let (ux, warnings) = try!(decode_conf(&source, &file));
+            return Ok((ux, dir, warnings));
+        }
+
+        if let Some((source, manifest)) = read_manifest(dir) {
+            if let Some(herbie) = manifest.lookup("package.metadata.herbie") {
+                let file = format!("[package.metadata.herbie] of {}",
+                                   display_path(start, dir, "Cargo.toml"));
+
+                let (ux, warnings) = try!(decode_value(
+                    &source, Some("package.metadata.herbie"), herbie.clone(), &file
+                ));
+                return Ok((ux, dir, warnings));
+            }
+        }
+
+        if dir == root {
+            break;
+        }
+
+        dir = match dir.parent() {
+            Some(parent) => parent,
+            None => break,
+        };
+    }
+
+    Ok((UxConf::default(), start, vec![]))
+}
+
+/// Decode a configuration file, and return the warnings about it.
+fn decode_conf(source: &str, file: &str) -> Result<(UxConf, Vec<String>), ConfError> {
+    let mut parser = toml::Parser::new(source);
+
+    match parser.parse() {
+        Some(table) => decode_value(source, None, toml::Value::Table(table), file),
+        None => {
+            let error = &parser.errors[0];
+            let (line, column) = parser.to_linecol(error.lo);
+
+            Err(ConfError::Parse {
+                file: file.into(),
+                line: line + 1,
+                column: column + 1,
+                message: error.desc.clone(),
+            })
+        }
+    }
+}
+
+/// Decode the configuration in `value`, which comes from the table `section` of `source`, and
+/// return the warnings about it.
+fn decode_value(
+    source: &str,
+    section: Option<&str>,
+    value: toml::Value,
+    file: &str
+) -> Result<(UxConf, Vec<String>), ConfError> {
+    let mut decoder = toml::Decoder::new(value);
+
+    let ux = try!(UxConf::decode(&mut decoder).map_err(|err| {
+        let key = err.field.clone().unwrap_or(String::new());
+        ConfError::Value {
+            file: file.into(),
+            line: find_key(source, section, &key),
+            message: err.to_string(),
+        }
+    }));
+
+    // the decoder leaves the keys it did not use
+    let mut unknown = vec![];
+    if let Some(ref rest) = decoder.toml {
+        unknown_keys(rest, String::new(), &mut unknown);
+    }
+
+    let warnings = unknown.into_iter().map(|key| {
+        let mut warning = format!("unknown key `{}` in {}", key, file);
+
+        if let Some(line) = find_key(source, section, &key) {
+            warning.push_str(&format!(" at line {}", line));
+        }
+
+        if let Some(suggestion) = similar_key(&key) {
+            warning.push_str(&format!(", did you mean `{}`?", suggestion));
+        }
+
+        warning
+    }).collect();
+
+    Ok((ux, warnings))
+}
+
+/// Collect the paths of the keys left in a decoded value, e.g. `databases.writeable`.
+fn unknown_keys(value: &toml::Value, path: String, keys: &mut Vec<String>) {
+    match *value {
+        toml::Value::Table(ref table) => {
+            for (key, value) in table {
+                let path = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                unknown_keys(value, path, keys);
+            }
+        }
+        toml::Value::Array(ref array) => {
+            for value in array {
+                unknown_keys(value, path.clone(), keys);
+            }
+        }
+        _ => {
+            if !keys.contains(&path) {
+                keys.push(path);
+            }
+        }
+    }
+}
+
+/// Return the line, starting at 1, where a key is defined, if it can be found. The key can be a
+/// path such as `databases.path`, only its last component is looked for.
+fn find_key(source: &str, section: Option<&str>, key: &str) -> Option<usize> {
+    let key = key.rsplit('.').next().unwrap_or(key);
+    if key.is_empty() {
+        return None;
+    }
+
+    let mut in_section = section.is_none();
+
+    for (i, line) in source.lines().enumerate() {
+        let line = line.trim();
+
+        if line.starts_with('[') {
+            if let Some(section) = section {
+                in_section = line == format!("[{}]", section);
+            }
+        }
+        else if in_section && line.starts_with(key)
+             && line[key.len()..].trim_left().starts_with('=') {
+            return Some(i + 1);
+        }
+    }
+
+    None
+}
+
+/// Return the known key most similar to an unknown key, if any is similar enough.
+fn similar_key(key: &str) -> Option<&'static str> {
+    let (known, key): (&[&'static str], _) = match key.find('.') {
+        Some(dot) if &key[..dot] == "databases" => (DATABASE_KEYS, &key[dot + 1..]),
+        Some(..) => return None,
+        None => (KEYS, key),
+    };
+
+    known.iter()
+         .map(|&known| (lev_distance(key, known), known))
+         .filter(|&(distance, known)| distance <= std::cmp::max(1, known.len() / 3))
+         .min_by_key(|&(distance, _)| distance)
+         .map(|(_, known)| known)
+}
+
+/// Return the Levenshtein distance between two strings.
+fn lev_distance(lhs: &str, rhs: &str) -> usize {
+    let rhs: Vec<char> = rhs.chars().collect();
+    let mut distances: Vec<usize> = (0..rhs.len() + 1).collect();
+
+    for (i, lc) in lhs.chars().enumerate() {
+        let mut prev = distances[0];
+        distances[0] = i + 1;
+
+        for (j, &rc) in rhs.iter().enumerate() {
+            let substitution = prev + if lc == rc { 0 } else { 1 };
+            prev = distances[j + 1];
+            distances[j + 1] = std::cmp::min(substitution, std::cmp::min(prev, distances[j]) + 1);
+        }
+    }
+
+    distances[rhs.len()]
+}
+
+/// Override the configuration with the `HERBIE_LINT_*` environment variables. An empty variable
+/// resets the option to its default. Relative paths are resolved like those of the configuration,
+/// from the directory where it was found.
+fn override_from_env<F>(ux: &mut UxConf, var: F) -> Result<(), ConfError>
+where F: Fn(&str) -> Option<String> {
+    fn parse<T: FromStr>(name: &str, value: &str, expected: &str) -> Result<T, ConfError> {
+        value.parse().map_err(|_| {
+            ConfError::Invalid {
+                message: format!("{}: expected {}, got `{}`", name, expected, value),
+            }
+        })
+    }
+
+    fn boolean(name: &str, value: Option<String>) -> Result<Option<bool>, ConfError> {
+        match value.as_ref().map(|value| &value[..]) {
+            Some("1") | Some("true") | Some("yes") => Ok(Some(true)),
+            Some("0") | Some("false") | Some("no") => Ok(Some(false)),
+            Some(value) => {
+                Err(ConfError::Invalid {
+                    message: format!(
+                        "{}: expected 1, true, yes, 0, false or no, got `{}`",
+                        name,
+                        value
+                    ),
+                })
+            }
+            None => Ok(None),
+        }
+    }
+
+    // `Some(None)` if the variable is set but empty
+    let lookup = |name: &str| {
+        var(name).map(|value| if value.is_empty() { None } else { Some(value) })
+    };
+
+    if let Some(db) = lookup("HERBIE_LINT_DB") {
+        ux.databases = None;
+        ux.db_path = db;
+    }
+
+    if let Some(fixes_path) = lookup("HERBIE_LINT_FIXES_PATH") {
+        ux.fixes_path = fixes_path;
+    }
+
+    if let Some(fma) = lookup("HERBIE_LINT_FMA") {
+        ux.fma = try!(boolean("HERBIE_LINT_FMA", fma));
+    }
+
+    if let Some(herbie_seed) = lookup("HERBIE_LINT_HERBIE_SEED") {
+        ux.herbie_seed = herbie_seed;
+    }
+
+    if let Some(ignore_path) = lookup("HERBIE_LINT_IGNORE_PATH") {
+        ux.ignore_path = ignore_path;
+    }
+
+    if let Some(max_cost_factor) = lookup("HERBIE_LINT_MAX_COST_FACTOR") {
+        ux.max_cost_factor = match max_cost_factor {
+            Some(value) => Some(try!(parse("HERBIE_LINT_MAX_COST_FACTOR", &value, "a number"))),
+            None => None,
+        };
+    }
+
+    if let Some(min_improvement) = lookup("HERBIE_LINT_MIN_IMPROVEMENT") {
+        ux.min_improvement = match min_improvement {
+            Some(value) => Some(try!(parse("HERBIE_LINT_MIN_IMPROVEMENT", &value, "a number"))),
+            None => None,
+        };
+    }
+
+    if let Some(report_path) = lookup("HERBIE_LINT_REPORT_PATH") {
+        ux.report_path = report_path;
+    }
+
+    if let Some(sarif_path) = lookup("HERBIE_LINT_SARIF_PATH") {
+        ux.sarif_path = sarif_path;
+    }
+
+    if let Some(timeout) = lookup("HERBIE_LINT_TIMEOUT") {
+        ux.timeout = match timeout {
+            Some(value) => {
+                Some(try!(parse("HERBIE_LINT_TIMEOUT", &value, "a number of seconds")))
+            }
+            None => None,
+        };
+    }
+
+    if let Some(use_herbie) = lookup("HERBIE_LINT_USE_HERBIE") {
+        ux.use_herbie = try!(boolean("HERBIE_LINT_USE_HERBIE", use_herbie));
+    }
+
+    if let Some(verify_db) = lookup("HERBIE_LINT_VERIFY_DB") {
+        ux.verify_db = try!(boolean("HERBIE_LINT_VERIFY_DB", verify_db));
+    }
+
+    Ok(())
+}
+
+/// Return the path of a file for error messages, only naming it if it is in the crate directory.
+fn display_path(start: &Path, dir: &Path, name: &str) -> String {
+    if dir == start {
+        name.into()
+    }
+    else {
+        dir.join(name).display().to_string()
+    }
+}
+
+/// Return the closest ancestor of `dir`, including itself, with a `Cargo.toml` declaring a
+/// workspace.
+fn workspace_root(dir: &Path) -> Option<&Path> {
+    let mut dir = Some(dir);
+
+    while let Some(current) = dir {
+        let manifest = read_manifest(current);
+        if manifest.map_or(false, |(_, manifest)| manifest.lookup("workspace").is_some()) {
+            return Some(current);
+        }
+
+        dir = current.parent();
+    }
+
+    None
+}
+
+/// Read the `Cargo.toml` of a directory, if it has a valid one, and return its source and its
+/// value. Invalid manifests are reported by Cargo itself.
+fn read_manifest(dir: &Path) -> Option<(String, toml::Value)> {
+    let manifest = dir.join("Cargo.toml");
+
+    if !manifest.exists() {
+        return None;
+    }
+
+    let source = if let Ok(source) = read_file(&manifest) { source } else { return None };
+    let table = toml::Parser::new(&source).parse();
+    table.map(|table| (source, toml::Value::Table(table)))
+}
+
+fn read_file(path: &Path) -> Result<String, ConfError> {
+    let mut buffer = String::new();
+
+    try!(File::open(path).and_then(|mut file| file.read_to_string(&mut buffer)).map_err(|err| {
+        ConfError::Io { error: err, file: path.display().to_string() }
+    }));
+
+    Ok(buffer)
+}
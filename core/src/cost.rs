@@ -0,0 +1,108 @@
+//! A rough estimation of the cost of evaluating expressions, to compare an expression with its
+//! rewriting.
+
+use lisp::{BinOp, LispExpr};
+use std;
+
+/// The cost of a division relative to an addition.
+const DIVISION_COST: usize = 4;
+/// The cost of a square root relative to an addition.
+const SQUARE_ROOT_COST: usize = 4;
+/// The cost of a call to a function such as `exp` or `hypot` relative to an addition.
+const CALL_COST: usize = 20;
+
+/// The number of operations of each kind in an expression.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, RustcEncodable)]
+pub struct Cost {
+    /// Additions, subtractions, negations and absolute values.
+    pub additions: usize,
+    pub multiplications: usize,
+    /// Divisions and remainders.
+    pub divisions: usize,
+    pub square_roots: usize,
+    /// Calls to other functions, such as `exp`, `sin` or `hypot`.
+    pub calls: usize,
+}
+
+impl Cost {
+    pub fn of_expr(expr: &LispExpr) -> Cost {
+        fn add_cost(expr: &LispExpr, cost: &mut Cost) {
+            match *expr {
+                LispExpr::Binary(op, ref lhs, ref rhs) => {
+                    match op {
+                        BinOp::Add | BinOp::Sub => cost.additions += 1,
+                        BinOp::Mul => cost.multiplications += 1,
+                        _ => cost.divisions += 1,
+                    }
+                    add_cost(lhs, cost);
+                    add_cost(rhs, cost);
+                }
+                LispExpr::Fun(ref name, ref args) => {
+                    match &name[..] {
+                        "abs" => cost.additions += 1,
+                        // `mul_add` is usually a single instruction, but no cheaper than its
+                        // operations
+                        "fma" => {
+                            cost.multiplications += 1;
+                            cost.additions += 1;
+                        }
+                        "sqrt" => cost.square_roots += 1,
+                        _ => cost.calls += 1,
+                    }
+                    for arg in args {
+                        add_cost(arg, cost);
+                    }
+                }
+                LispExpr::Ident(..) | LispExpr::Lit(..) => (),
+                LispExpr::Unary(_, ref expr) => {
+                    cost.additions += 1;
+                    add_cost(expr, cost);
+                }
+            }
+        }
+
+        let mut cost = Cost::default();
+        add_cost(expr, &mut cost);
+        cost
+    }
+
+    /// Return the weighted number of operations, in multiples of the cost of an addition.
+    pub fn total(&self) -> usize {
+        self.additions
+        + self.multiplications
+        + self.divisions * DIVISION_COST
+        + self.square_roots * SQUARE_ROOT_COST
+        + self.calls * CALL_COST
+    }
+}
+
+impl std::fmt::Display for Cost {
+    /// List the operations, e.g. “1 addition, 2 multiplications, 1 square root”.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        let counts = [
+            (self.additions, "addition", "additions"),
+            (self.multiplications, "multiplication", "multiplications"),
+            (self.divisions, "division", "divisions"),
+            (self.square_roots, "square root", "square roots"),
+            (self.calls, "function call", "function calls"),
+        ];
+
+        let mut first = true;
+        for &(count, singular, plural) in &counts {
+            if count != 0 {
+                try!(write!(f,
+                            "{}{} {}",
+                            if first { "" } else { ", " },
+                            count,
+                            if count == 1 { singular } else { plural }));
+                first = false;
+            }
+        }
+
+        if first {
+            try!(write!(f, "no operation"));
+        }
+
+        Ok(())
+    }
+}
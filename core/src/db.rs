@@ -0,0 +1,315 @@
+//! Access to the database of Herbie results, shared with the Haskell plugin.
+
+use lisp::{LispExpr, Parser};
+use rusqlite as sql;
+use std::collections::HashMap;
+
+/// A row of the `HerbieResults` table.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Row {
+    pub id: i64,
+    /// The original expression, in Herbie's Lisp syntax.
+    pub cmdin: String,
+    /// The improved expression, in Herbie's Lisp syntax.
+    pub cmdout: String,
+    /// The options Herbie was run with, i.e. its seed.
+    pub opts: String,
+    /// Average bits of error of `cmdin`, if known.
+    pub errin: Option<f64>,
+    /// Average bits of error of `cmdout`, if known.
+    pub errout: Option<f64>,
+}
+
+/// Open the database at `path`.
+pub fn open(path: &str, writable: bool) -> Result<sql::Connection, sql::Error> {
+    let flags = if writable {
+        sql::SQLITE_OPEN_READ_WRITE
+    }
+    else {
+        sql::SQLITE_OPEN_READ_ONLY
+    };
+
+    sql::Connection::open_with_flags(path, flags)
+}
+
+/// Open the database at `path` for writing, creating it and its tables if necessary.
+pub fn create(path: &str) -> Result<sql::Connection, sql::Error> {
+    let connection = try!(sql::Connection::open(path));
+
+    try!(connection.execute_batch(
+        "CREATE TABLE IF NOT EXISTS HerbieResults (
+             id INTEGER PRIMARY KEY,
+             cmdin  TEXT        NOT NULL,
+             cmdout TEXT        NOT NULL,
+             opts   TEXT        NOT NULL,
+             errin  DOUBLE      ,
+             errout DOUBLE      ,
+             hash   INTEGER     ,
+             UNIQUE (cmdin, opts)
+         );
+         CREATE INDEX IF NOT EXISTS HerbieResultsIndex ON HerbieResults(cmdin);
+         CREATE INDEX IF NOT EXISTS HerbieResultsHash ON HerbieResults(hash);
+         CREATE TABLE IF NOT EXISTS DbgInfo (
+             id INTEGER PRIMARY KEY,
+             resid INTEGER NOT NULL,
+             dbgComments TEXT,
+             modName TEXT,
+             functionName TEXT,
+             functionType TEXT
+         );"
+    ));
+
+    Ok(connection)
+}
+
+/// Read all the rows of the `HerbieResults` table, ordered by id.
+pub fn read_rows(connection: &sql::Connection) -> Result<Vec<Row>, sql::Error> {
+    query_rows(connection, "", &[])
+}
+
+/// Read the rows of the `HerbieResults` table matching a `WHERE` clause, ordered by id.
+fn query_rows(
+    connection: &sql::Connection,
+    filter: &str,
+    params: &[&sql::types::ToSql]
+) -> Result<Vec<Row>, sql::Error> {
+    let mut query = try!(connection.prepare(&format!(
+        "SELECT id, cmdin, cmdout, opts, errin, errout FROM HerbieResults {} ORDER BY id", filter
+    )));
+
+    let rows = try!(query.query_map(params, |row| {
+        Row {
+            id: row.get(0),
+            cmdin: row.get(1),
+            cmdout: row.get(2),
+            opts: row.get(3),
+            errin: row.get_checked(4).ok(),
+            errout: row.get_checked(5).ok(),
+        }
+    }));
+
+    rows.collect()
+}
+
+/// Return the names of the functions a row was recorded for, as found in the `DbgInfo` table.
+/// Rows added by this plugin have no such information.
+pub fn function_names(connection: &sql::Connection, id: i64) -> Result<Vec<String>, sql::Error> {
+    let mut query = try!(connection.prepare(
+        "SELECT DISTINCT functionName FROM DbgInfo WHERE resid = $1 AND functionName IS NOT NULL"
+    ));
+
+    let names = try!(query.query_map(&[&id], |row| row.get(0)));
+
+    names.collect()
+}
+
+/// Delete a row and its debug information. Return whether the row existed.
+pub fn delete_row(connection: &sql::Connection, id: i64) -> Result<bool, sql::Error> {
+    let deleted = try!(connection.execute("DELETE FROM HerbieResults WHERE id = $1", &[&id]));
+    try!(connection.execute("DELETE FROM DbgInfo WHERE resid = $1", &[&id]));
+
+    Ok(deleted != 0)
+}
+
+/// Two entries with the same canonical `cmdin` but different results.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Conflict {
+    /// The entry that was already in the database.
+    pub existing: Row,
+    /// The entry that was merged into the database.
+    pub new: Row,
+    /// Whether the new entry replaced the existing one, because it has a lower `errout`.
+    pub replaced: bool,
+}
+
+/// The result of `merge`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MergeReport {
+    /// Number of entries added to the database.
+    pub added: usize,
+    /// Number of entries which replaced an existing entry with a higher `errout`.
+    pub replaced: usize,
+    /// Number of entries which were already in the database with a lower or equal `errout`.
+    pub kept: usize,
+    /// The entries with a different `cmdout` than the existing entry for the same `cmdin`.
+    pub conflicts: Vec<Conflict>,
+    /// The entries which could not be parsed, and were ignored.
+    pub invalid: Vec<Row>,
+}
+
+/// A parsed row.
+struct Parsed {
+    /// `cmdin` with its variables renamed by order of first occurrence, as it is stored.
+    cmdin: LispExpr,
+    /// `cmdout` with the same renaming as `cmdin`.
+    cmdout: LispExpr,
+    /// The canonical form of `cmdin`, see `LispExpr::canonicalize`.
+    canonical_cmdin: LispExpr,
+    /// `cmdout` simplified and renamed like the canonical form of `cmdin`.
+    canonical_cmdout: LispExpr,
+    /// The canonical hash of `cmdin`, as stored in the `hash` column.
+    hash: i64,
+}
+
+/// Complete a renaming of the identifiers of `cmdin` with the identifiers only used in `cmdout`.
+fn complete_renaming(renaming: &mut HashMap<u64, u64>, cmdout: &LispExpr) {
+    for id in cmdout.identifiers() {
+        let next = renaming.len() as u64;
+        renaming.entry(id).or_insert(next);
+    }
+}
+
+fn parse(row: &Row) -> Option<Parsed> {
+    let mut parser = Parser::new();
+
+    let cmdin = if let Ok(cmdin) = parser.parse(&row.cmdin) { cmdin } else { return None };
+    let cmdout = if let Ok(cmdout) = parser.parse(&row.cmdout) { cmdout } else { return None };
+
+    let mut renaming = cmdin.identifiers().into_iter().zip(0..).collect();
+    complete_renaming(&mut renaming, &cmdout);
+    let (cmdin, cmdout) = (cmdin.rename(&renaming), cmdout.rename(&renaming));
+
+    let (canonical_cmdin, mut renaming) = cmdin.canonicalize();
+    complete_renaming(&mut renaming, &cmdout);
+    let canonical_cmdout = cmdout.rename(&renaming).simplify();
+
+    Some(Parsed {
+        hash: cmdin.canonical_hash() as i64,
+        cmdin: cmdin,
+        cmdout: cmdout,
+        canonical_cmdin: canonical_cmdin,
+        canonical_cmdout: canonical_cmdout,
+    })
+}
+
+/// Add the `hash` column, the canonical hash of `cmdin` used to find duplicates, to databases
+/// created by the Haskell plugin or by older versions, and fill it for the rows that lack it.
+fn update_hashes(connection: &sql::Connection) -> Result<(), sql::Error> {
+    let has_hash = {
+        let mut query = try!(connection.prepare("PRAGMA table_info(HerbieResults)"));
+        let columns = try!(query.query_map(&[], |row| {
+            let name: String = row.get(1);
+            name
+        }));
+        let columns: Vec<_> = try!(columns.collect());
+        columns.iter().any(|column| column == "hash")
+    };
+
+    if !has_hash {
+        try!(connection.execute_batch(
+            "ALTER TABLE HerbieResults ADD COLUMN hash INTEGER;
+             CREATE INDEX IF NOT EXISTS HerbieResultsHash ON HerbieResults(hash);"
+        ));
+    }
+
+    for row in try!(query_rows(connection, "WHERE hash IS NULL", &[])) {
+        if let Some(parsed) = parse(&row) {
+            try!(connection.execute("UPDATE HerbieResults SET hash = $1 WHERE id = $2",
+                                    &[&parsed.hash, &row.id]));
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether `errout` is better than `than`. An unknown error is worse than any known error.
+fn is_better(errout: Option<f64>, than: Option<f64>) -> bool {
+    match (errout, than) {
+        (Some(errout), Some(than)) => errout < than,
+        (Some(..), None) => true,
+        (None, _) => false,
+    }
+}
+
+/// Merge rows into a database. Rows are deduplicated by the canonical form of their `cmdin`, see
+/// `LispExpr::canonicalize`, and only the one with the lowest `errout` is kept for each `cmdin`.
+/// The `id` of the merged rows is ignored.
+pub fn merge(connection: &sql::Connection, rows: &[Row]) -> Result<MergeReport, sql::Error> {
+    try!(update_hashes(connection));
+
+    let mut report = MergeReport::default();
+
+    try!(connection.execute_batch("BEGIN"));
+
+    let result = merge_impl(connection, rows, &mut report);
+
+    try!(connection.execute_batch(if result.is_ok() { "COMMIT" } else { "ROLLBACK" }));
+
+    result.map(|()| report)
+}
+
+fn merge_impl(
+    connection: &sql::Connection,
+    rows: &[Row],
+    report: &mut MergeReport
+) -> Result<(), sql::Error> {
+    for row in rows {
+        let parsed = if let Some(parsed) = parse(row) {
+            parsed
+        }
+        else {
+            report.invalid.push(row.clone());
+            continue;
+        };
+
+        let new = Row {
+            id: 0,
+            cmdin: parsed.cmdin.to_lisp("herbie"),
+            cmdout: parsed.cmdout.to_lisp("herbie"),
+            opts: row.opts.clone(),
+            errin: row.errin,
+            errout: row.errout,
+        };
+
+        // the hash can collide, so check the canonical forms
+        let existing = try!(query_rows(connection, "WHERE hash = $1", &[&parsed.hash]))
+            .into_iter()
+            .filter_map(|row| parse(&row).map(|existing| (row, existing)))
+            .find(|&(_, ref existing)| existing.canonical_cmdin == parsed.canonical_cmdin);
+
+        if let Some((existing, existing_parsed)) = existing {
+            let replaced = is_better(new.errout, existing.errout);
+
+            if existing_parsed.canonical_cmdout != parsed.canonical_cmdout {
+                report.conflicts.push(Conflict {
+                    existing: existing.clone(),
+                    new: row.clone(),
+                    replaced: replaced,
+                });
+            }
+
+            if replaced {
+                try!(connection.execute(
+                    "UPDATE HerbieResults SET cmdin = $1, cmdout = $2, opts = $3, errin = $4,
+                                              errout = $5
+                     WHERE id = $6",
+                    &[&new.cmdin, &new.cmdout, &new.opts, &new.errin, &new.errout, &existing.id]
+                ));
+                report.replaced += 1;
+            }
+            else {
+                report.kept += 1;
+            }
+
+            continue;
+        }
+
+        try!(connection.execute(
+            "INSERT INTO HerbieResults (cmdin, cmdout, opts, errin, errout, hash)
+             VALUES ($1, $2, $3, $4, $5, $6)",
+            &[&new.cmdin, &new.cmdout, &new.opts, &new.errin, &new.errout, &parsed.hash]
+        ));
+        report.added += 1;
+    }
+
+    Ok(())
+}
+
+/// Merge all the rows of the database `from` into `into`. See `merge`.
+pub fn merge_databases(
+    into: &sql::Connection,
+    from: &sql::Connection
+) -> Result<MergeReport, sql::Error> {
+    let rows = try!(read_rows(from));
+    merge(into, &rows)
+}
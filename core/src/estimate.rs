@@ -0,0 +1,428 @@
+//! Estimation of the error of expressions, without Herbie.
+//!
+//! As Herbie does, expressions are evaluated on random inputs both with `f64` and with arbitrary
+//! precision, whose precision is increased until the result is stable, and their error is the
+//! average number of bits of error of the `f64` results over the inputs with a finite exact
+//! result.
+
+use bigfloat::{BigFloat, Context, f64_from_bits, f64_to_bits};
+use lisp::{BinOp, LispExpr, UnOp};
+use std::collections::HashMap;
+use std;
+
+/// The number of inputs Herbie samples by default.
+pub const DEFAULT_POINTS: usize = 256;
+
+/// The precisions at which the exact results are computed, until two agree.
+const PRECISIONS: &'static [usize] = &[128, 256, 512, 1024, 2048, 4096];
+
+/// The number of inputs tried for each requested input, as some inputs are invalid.
+const MAX_TRIES: usize = 8;
+
+/// The average bits of difference between the exact results of an expression and its rewriting
+/// above which they are not considered equivalent.
+const MAX_DIFFERENCE: f64 = 1.;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum EstimateError {
+    /// The expression uses an operator or a function that cannot be evaluated.
+    Unsupported(String),
+    /// No sampled input had a finite result.
+    NoValidPoint,
+}
+
+impl std::fmt::Display for EstimateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        match *self {
+            EstimateError::Unsupported(ref what) => write!(f, "`{}` is not supported", what),
+            EstimateError::NoValidPoint => write!(f, "no sampled input has a finite result"),
+        }
+    }
+}
+
+/// The reason a rewriting is rejected by `Estimator::verify`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Rejection {
+    /// The rewriting does not compute the same values, they differ by that many bits on average.
+    NotEquivalent(f64),
+    /// The rewriting is not more accurate, with these average bits of error before and after.
+    NotMoreAccurate(f64, f64),
+    /// The expressions cannot be evaluated.
+    Error(EstimateError),
+}
+
+impl std::fmt::Display for Rejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        match *self {
+            Rejection::NotEquivalent(difference) => {
+                write!(f, "its rewriting differs from the expression by {:.1} bits on average",
+                       difference)
+            }
+            Rejection::NotMoreAccurate(errin, errout) => {
+                write!(f,
+                       "its rewriting is not more accurate: {:.1} → {:.1} bits of error measured",
+                       errin,
+                       errout)
+            }
+            Rejection::Error(ref err) => write!(f, "it cannot be verified: {}", err),
+        }
+    }
+}
+
+/// Return the number of bits of error of `approx`, as Herbie computes it: the base 2 logarithm
+/// of the number of doubles between `approx` and `exact`, plus one.
+pub fn bits_of_error(approx: f64, exact: f64) -> f64 {
+    if approx.is_nan() || exact.is_nan() {
+        return if approx.is_nan() && exact.is_nan() { 0. } else { 64. };
+    }
+
+    let (approx, exact) = (ordinal(approx), ordinal(exact));
+    let ulps = if (approx >= 0) == (exact >= 0) {
+        (approx - exact).abs() as f64
+    }
+    else {
+        (approx as f64).abs() + (exact as f64).abs()
+    };
+
+    (1. + ulps).log2()
+}
+
+/// Return the position of a double among the doubles, which are ordered like their
+/// representations, with negative numbers reversed.
+fn ordinal(x: f64) -> i64 {
+    let bits = f64_to_bits(x.abs()) as i64;
+    if x < 0. { -bits } else { bits }
+}
+
+/// The inverse of `ordinal`.
+fn from_ordinal(ordinal: i64) -> f64 {
+    if ordinal < 0 {
+        -f64_from_bits(ordinal.wrapping_neg() as u64)
+    }
+    else {
+        f64_from_bits(ordinal as u64)
+    }
+}
+
+/// An arithmetic in which expressions can be evaluated.
+trait Arith {
+    type Value: Clone;
+
+    fn binary(&self, op: BinOp, lhs: &Self::Value, rhs: &Self::Value) -> Option<Self::Value>;
+    fn neg(&self, x: &Self::Value) -> Self::Value;
+    fn lit(&self, f: f64) -> Self::Value;
+    fn fun(&self, name: &str, args: &[Self::Value]) -> Option<Self::Value>;
+}
+
+/// The arithmetic of the user's code.
+#[derive(Debug)]
+struct F64;
+
+impl Arith for F64 {
+    type Value = f64;
+
+    fn binary(&self, op: BinOp, lhs: &f64, rhs: &f64) -> Option<f64> {
+        match op {
+            BinOp::Add => Some(lhs + rhs),
+            BinOp::Sub => Some(lhs - rhs),
+            BinOp::Mul => Some(lhs * rhs),
+            BinOp::Div => Some(lhs / rhs),
+            BinOp::Rem => None,
+        }
+    }
+
+    fn neg(&self, x: &f64) -> f64 {
+        -x
+    }
+
+    fn lit(&self, f: f64) -> f64 {
+        f
+    }
+
+    fn fun(&self, name: &str, args: &[f64]) -> Option<f64> {
+        let x = args[0];
+
+        Some(match name {
+            "abs" => x.abs(),
+            "acos" => x.acos(),
+            "asin" => x.asin(),
+            "atan" => x.atan(),
+            "atan2" => x.atan2(args[1]),
+            "cos" => x.cos(),
+            "cosh" => x.cosh(),
+            "exp" => x.exp(),
+            "expm1" => x.exp_m1(),
+            "expt" => x.powf(args[1]),
+            "fma" => x.mul_add(args[1], args[2]),
+            "hypot" => x.hypot(args[1]),
+            "log" => x.ln(),
+            "log1p" => x.ln_1p(),
+            "sin" => x.sin(),
+            "sinh" => x.sinh(),
+            "sqrt" => x.sqrt(),
+            "tan" => x.tan(),
+            "tanh" => x.tanh(),
+            _ => return None,
+        })
+    }
+}
+
+/// The reference arithmetic.
+impl Arith for Context {
+    type Value = BigFloat;
+
+    fn binary(&self, op: BinOp, lhs: &BigFloat, rhs: &BigFloat) -> Option<BigFloat> {
+        match op {
+            BinOp::Add => Some(self.add(lhs, rhs)),
+            BinOp::Sub => Some(self.sub(lhs, rhs)),
+            BinOp::Mul => Some(self.mul(lhs, rhs)),
+            BinOp::Div => Some(self.div(lhs, rhs)),
+            BinOp::Rem => None,
+        }
+    }
+
+    fn neg(&self, x: &BigFloat) -> BigFloat {
+        x.neg()
+    }
+
+    fn lit(&self, f: f64) -> BigFloat {
+        self.from_f64(f)
+    }
+
+    fn fun(&self, name: &str, args: &[BigFloat]) -> Option<BigFloat> {
+        let x = &args[0];
+
+        Some(match name {
+            "abs" => x.abs(),
+            "acos" => self.acos(x),
+            "asin" => self.asin(x),
+            "atan" => self.atan(x),
+            "atan2" => self.atan2(x, &args[1]),
+            "cos" => self.cos(x),
+            "cosh" => self.cosh(x),
+            "exp" => self.exp(x),
+            "expm1" => self.exp_m1(x),
+            "expt" => self.powf(x, &args[1]),
+            "fma" => self.add(&self.mul(x, &args[1]), &args[2]),
+            "hypot" => self.hypot(x, &args[1]),
+            "log" => self.ln(x),
+            "log1p" => self.ln_1p(x),
+            "sin" => self.sin(x),
+            "sinh" => self.sinh(x),
+            "sqrt" => self.sqrt(x),
+            "tan" => self.tan(x),
+            "tanh" => self.tanh(x),
+            _ => return None,
+        })
+    }
+}
+
+/// Evaluate an expression, the value of the identifier `i` being `point[i]`.
+fn eval<A: Arith>(arith: &A, expr: &LispExpr, point: &[A::Value])
+-> Result<A::Value, EstimateError> {
+    match *expr {
+        LispExpr::Binary(op, ref lhs, ref rhs) => {
+            let lhs = try!(eval(arith, lhs, point));
+            let rhs = try!(eval(arith, rhs, point));
+            arith.binary(op, &lhs, &rhs)
+                 .ok_or_else(|| EstimateError::Unsupported(op.as_str().into()))
+        }
+        LispExpr::Fun(ref name, ref args) => {
+            let args = try!(args.iter()
+                                .map(|arg| eval(arith, arg, point))
+                                .collect::<Result<Vec<_>, _>>());
+            arith.fun(name, &args).ok_or_else(|| EstimateError::Unsupported(name.clone()))
+        }
+        LispExpr::Ident(id) => Ok(point[id as usize].clone()),
+        LispExpr::Lit(f) => Ok(arith.lit(f)),
+        LispExpr::Unary(UnOp::Neg, ref expr) => Ok(arith.neg(&try!(eval(arith, expr, point)))),
+    }
+}
+
+/// A xorshift* pseudo-random generator, so that estimations are reproducible.
+#[derive(Debug)]
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Return a random finite double, uniformly distributed among the representations as Herbie
+    /// samples them.
+    fn next_f64(&mut self) -> f64 {
+        loop {
+            let x = f64_from_bits(self.next());
+            if x.is_finite() {
+                return x;
+            }
+        }
+    }
+
+    /// Return a random finite double between `lo` and `hi` inclusive, distributed as by
+    /// `next_f64`. The range must not be empty.
+    fn next_f64_in(&mut self, lo: f64, hi: f64) -> f64 {
+        debug_assert!(lo <= hi, "Empty range {}..{}", lo, hi);
+        let lo = ordinal(lo.max(-std::f64::MAX));
+        let hi = ordinal(hi.min(std::f64::MAX));
+        // the number of doubles in the range can only overflow for the whole range
+        let count = (hi as u64).wrapping_sub(lo as u64).wrapping_add(1);
+
+        let offset = if count == 0 { self.next() } else { self.next() % count };
+        from_ordinal(lo.wrapping_add(offset as i64))
+    }
+}
+
+/// Estimates the error of expressions on random inputs.
+#[derive(Debug)]
+pub struct Estimator {
+    points: usize,
+    seed: u64,
+    contexts: Vec<Context>,
+}
+
+impl Estimator {
+    /// Create an estimator which uses `points` inputs, generated from `seed`.
+    pub fn new(points: usize, seed: u64) -> Estimator {
+        Estimator {
+            points: points,
+            // xorshift generators must not start at 0
+            seed: if seed == 0 { 0x9e37_79b9_7f4a_7c15 } else { seed },
+            contexts: PRECISIONS.iter().map(|&bits| Context::new(bits)).collect(),
+        }
+    }
+
+    /// Return the exact result of an expression rounded to a double, or `None` if it does not
+    /// stabilize at the highest precision.
+    fn eval_exact(&self, expr: &LispExpr, point: &[f64]) -> Result<Option<f64>, EstimateError> {
+        // a cancellation loses as many bits as the exponents of the inputs, e.g. in
+        // `(- (exp x) 1)`, and the results would agree at too low precisions
+        let exponent = point.iter()
+                            .map(|&x| if x == 0. { 0 } else { x.abs().log2().abs() as usize })
+                            .max()
+                            .unwrap_or(0);
+        let first = PRECISIONS.iter()
+                              .position(|&bits| bits >= exponent + PRECISIONS[0])
+                              .unwrap_or(PRECISIONS.len());
+        let first = std::cmp::min(first, PRECISIONS.len() - 2);
+
+        let mut previous: Option<f64> = None;
+
+        for context in &self.contexts[first..] {
+            let point = point.iter().map(|&x| context.from_f64(x)).collect::<Vec<_>>();
+            let result = try!(eval(context, expr, &point)).to_f64();
+
+            if let Some(previous) = previous {
+                if previous == result || (previous.is_nan() && result.is_nan()) {
+                    return Ok(Some(result));
+                }
+            }
+
+            previous = Some(result);
+        }
+
+        Ok(None)
+    }
+
+    /// Return the average bits of error of an expression.
+    pub fn error(&self, expr: &LispExpr) -> Result<f64, EstimateError> {
+        self.errors(&[expr]).map(|errors| errors[0])
+    }
+
+    /// Return the average bits of error of expressions on the same inputs, e.g. an expression
+    /// and its rewriting by Herbie. An input is only used if the exact result of all the
+    /// expressions is known, and that of the first one is finite.
+    pub fn errors(&self, exprs: &[&LispExpr]) -> Result<Vec<f64>, EstimateError> {
+        self.errors_in_ranges(exprs, &HashMap::new())
+    }
+
+    /// Return the average bits of error of expressions, as `errors`, on inputs where the
+    /// identifiers in `ranges` are between the given bounds, inclusive.
+    pub fn errors_in_ranges(&self, exprs: &[&LispExpr], ranges: &HashMap<u64, (f64, f64)>)
+    -> Result<Vec<f64>, EstimateError> {
+        let mut sums = vec![0.; exprs.len()];
+
+        let valid = try!(self.sample(exprs, ranges, |results| {
+            for (sum, &(approx, exact)) in sums.iter_mut().zip(results) {
+                *sum += bits_of_error(approx, exact);
+            }
+        }));
+
+        Ok(sums.into_iter().map(|sum| sum / valid as f64).collect())
+    }
+
+    /// Check that `cmdout` is a valid rewriting of `cmdin`: both must compute the same values
+    /// and `cmdout` must be more accurate. Return the average bits of error of both expressions.
+    pub fn verify(&self, cmdin: &LispExpr, cmdout: &LispExpr) -> Result<(f64, f64), Rejection> {
+        let (mut errin, mut errout, mut difference) = (0., 0., 0.);
+
+        let valid = try!(self.sample(&[cmdin, cmdout], &HashMap::new(), |results| {
+            let ((approx_in, exact_in), (approx_out, exact_out)) = (results[0], results[1]);
+            errin += bits_of_error(approx_in, exact_in);
+            errout += bits_of_error(approx_out, exact_out);
+            difference += bits_of_error(exact_out, exact_in);
+        }).map_err(Rejection::Error)) as f64;
+
+        let (errin, errout, difference) = (errin / valid, errout / valid, difference / valid);
+
+        if difference > MAX_DIFFERENCE {
+            Err(Rejection::NotEquivalent(difference))
+        }
+        else if errout >= errin {
+            Err(Rejection::NotMoreAccurate(errin, errout))
+        }
+        else {
+            Ok((errin, errout))
+        }
+    }
+
+    /// Call `f` with the `f64` and the exact results of the expressions on each valid input, and
+    /// return the number of valid inputs. The inputs are sampled in `ranges` when given.
+    fn sample<F>(&self, exprs: &[&LispExpr], ranges: &HashMap<u64, (f64, f64)>, mut f: F)
+    -> Result<usize, EstimateError>
+    where F: FnMut(&[(f64, f64)]) {
+        let nb_vars = exprs.iter()
+                           .flat_map(|expr| expr.identifiers())
+                           .max()
+                           .map_or(0, |id| id as usize + 1);
+
+        let mut rng = Rng(self.seed);
+        let mut valid = 0;
+
+        'points: for _ in 0..self.points * MAX_TRIES {
+            if valid == self.points {
+                break;
+            }
+
+            let point = (0..nb_vars as u64).map(|id| {
+                match ranges.get(&id) {
+                    Some(&(lo, hi)) => rng.next_f64_in(lo, hi),
+                    None => rng.next_f64(),
+                }
+            }).collect::<Vec<_>>();
+
+            let mut results = Vec::with_capacity(exprs.len());
+            for (i, expr) in exprs.iter().enumerate() {
+                let exact = match try!(self.eval_exact(expr, &point)) {
+                    Some(exact) if i != 0 || exact.is_finite() => exact,
+                    _ => continue 'points,
+                };
+                let approx = try!(eval(&F64, expr, &point));
+
+                results.push((approx, exact));
+            }
+
+            f(&results);
+            valid += 1;
+        }
+
+        if valid == 0 {
+            return Err(EstimateError::NoValidPoint);
+        }
+
+        Ok(valid)
+    }
+}
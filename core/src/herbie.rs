@@ -0,0 +1,369 @@
+//! The substitutions of the databases, and calls to Herbie with the saving of its results. These
+//! are shared by the lint pass and other frontends.
+
+use conf::{self, Conf};
+use db;
+use estimate::{self, Estimator, Rejection};
+use ignore::Ignores;
+use lisp::{self, LispExpr};
+use rusqlite as sql;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Error as IOError, Read, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+use std;
+use wait_timeout::ChildExt;
+
+/// A possible substitution from the database.
+#[derive(Debug)]
+pub struct Sub {
+    /// The id of the row in the database.
+    pub id: i64,
+    /// The path of the database.
+    pub database: String,
+    pub cmdin: LispExpr,
+    pub cmdout: LispExpr,
+    /// Average bits of error of `cmdin`, as estimated by Herbie.
+    pub errin: f64,
+    /// Average bits of error of `cmdout`, as estimated by Herbie.
+    pub errout: f64,
+    pub verification: Verification,
+}
+
+/// Whether a substitution was checked with `Estimator::verify`, which is only done once it
+/// matches an expression.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Verification {
+    Unchecked,
+    Verified,
+    /// The substitution could not be checked, e.g. because it uses `%`, and is used as is.
+    Unverifiable,
+    /// The substitution is wrong and is ignored.
+    Quarantined,
+}
+
+impl Sub {
+    /// Parse a row of the database, if it is an improvement.
+    pub fn from_row(row: &db::Row, database: &str) -> Option<Sub> {
+        let errin = row.errin.unwrap_or(0.);
+        let errout = row.errout.unwrap_or(0.);
+
+        if row.cmdin == row.cmdout || errin <= errout {
+            return None;
+        }
+
+        let mut parser = lisp::Parser::new();
+        match parser.parse(&row.cmdin) {
+            Ok(cmdin) => {
+                match parser.parse(&row.cmdout) {
+                    Ok(cmdout) => {
+                        Some(Sub {
+                            id: row.id,
+                            database: database.into(),
+                            cmdin: cmdin,
+                            cmdout: cmdout,
+                            errin: errin,
+                            errout: errout,
+                            verification: Verification::Unchecked,
+                        })
+                    }
+                    Err(..) => None,
+                }
+            }
+            Err(..) => None,
+        }
+    }
+
+    /// Verify the substitution with `estimator` if it has not been verified yet. Return why it is
+    /// wrong when it gets quarantined, or why it cannot be verified, in which case it is kept.
+    pub fn verify(&mut self, estimator: &Estimator) -> Option<Rejection> {
+        if self.verification != Verification::Unchecked {
+            return None;
+        }
+
+        match estimator.verify(&self.cmdin, &self.cmdout) {
+            Ok(..) => {
+                self.verification = Verification::Verified;
+                None
+            }
+            Err(rejection) => {
+                self.verification = match rejection {
+                    Rejection::Error(..) => Verification::Unverifiable,
+                    _ => Verification::Quarantined,
+                };
+                Some(rejection)
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum InitError {
+    Conf {
+        error: conf::ConfError,
+    },
+    SQL {
+        error: sql::Error,
+    },
+    Ignore {
+        error: IOError,
+    },
+}
+
+impl From<conf::ConfError> for InitError {
+    fn from(err: conf::ConfError) -> InitError {
+        InitError::Conf { error: err }
+    }
+}
+
+impl From<sql::Error> for InitError {
+    fn from(err: sql::Error) -> InitError {
+        InitError::SQL { error: err }
+    }
+}
+
+impl std::fmt::Display for InitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        match *self {
+            InitError::Conf { ref error } => write!(f, "Configuration error: {}", error),
+            InitError::SQL { ref error } => write!(f, "Got SQL error: {}", error),
+            InitError::Ignore { ref error } => {
+                write!(f, "Could not read the ignore file: {}", error)
+            }
+        }
+    }
+}
+
+/// Read the ignore file of `conf`.
+pub fn read_ignores(conf: &Conf) -> Result<Ignores, InitError> {
+    Ignores::read(&conf.ignore_path).map_err(|err| InitError::Ignore { error: err })
+}
+
+/// Read the substitutions of the databases of `conf`. An expression found in a database is
+/// ignored in the following ones, and the best result is kept within a database.
+pub fn read_subs(conf: &Conf) -> Result<Vec<Sub>, InitError> {
+    let mut subs: Vec<Sub> = Vec::new();
+    // the canonical `cmdin`s by canonical hash, with the index of their substitution and the
+    // database it comes from; different expressions can have the same hash
+    let mut known: HashMap<u64, Vec<(LispExpr, usize, usize)>> = HashMap::new();
+
+    for (layer, database) in conf.databases.iter().enumerate() {
+        // the user cache is only created when Herbie's results are first saved
+        if database.user && !Path::new(&database.path).exists() {
+            continue;
+        }
+
+        let connection = try!(db::open(&database.path, false));
+
+        for row in try!(db::read_rows(&connection)) {
+            let sub = match Sub::from_row(&row, &database.path) {
+                Some(sub) => sub,
+                None => continue,
+            };
+
+            let canonical = sub.cmdin.canonicalize().0;
+            let same_hash = known.entry(sub.cmdin.canonical_hash()).or_insert_with(Vec::new);
+
+            match same_hash.iter().find(|&&(ref cmdin, _, _)| *cmdin == canonical) {
+                Some(&(_, idx, sub_layer)) => {
+                    // earlier databases take precedence
+                    if sub_layer == layer && sub.errout < subs[idx].errout {
+                        subs[idx] = sub;
+                    }
+                }
+                None => {
+                    same_hash.push((canonical, subs.len(), layer));
+                    subs.push(sub);
+                }
+            }
+        }
+    }
+
+    Ok(subs)
+}
+
+/// The result of a call to Herbie.
+#[derive(Debug)]
+pub enum Output {
+    /// Herbie is disabled, or it could not be found and is not required.
+    Disabled,
+    TimedOut,
+    /// Herbie's result, which is not necessarily an improvement.
+    Done {
+        /// Average bits of error of the expression.
+        errin: f64,
+        /// Average bits of error of `cmdout`.
+        errout: f64,
+        /// Herbie's expression, as Lisp.
+        cmdout: String,
+    },
+}
+
+/// Call `herbie-inout` on `lisp_expr`, whose identifiers are numbered from 0 to `idents`, with
+/// the known `ranges` of its identifiers as precondition. `on_start` is called once Herbie is
+/// running, e.g. to tell the user it might take a while.
+pub fn call<F: FnOnce()>(
+    conf: &Conf,
+    lisp_expr: &LispExpr,
+    idents: usize,
+    ranges: &HashMap<u64, (f64, f64)>,
+    on_start: F
+) -> Result<Output, Cow<'static, str>> {
+    if conf.use_herbie == conf::UseHerbieConf::No {
+        return Ok(Output::Disabled);
+    }
+
+    let seed: &str = &conf.herbie_seed;
+    let mut command = Command::new("herbie-inout");
+    let command = command
+        .arg("--seed").arg(seed)
+        .arg("-o").arg("rules:numerics")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+    ;
+
+    if conf.fma {
+        command.arg("-o").arg("rules:fma");
+    }
+
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(err) => {
+            return if conf.use_herbie == conf::UseHerbieConf::Yes {
+                // TODO: wiki
+                Err(format!("Could not call Herbie: {}", err).into())
+            }
+            else {
+                Ok(Output::Disabled)
+            };
+        }
+    };
+
+    on_start();
+
+    let params = (0..idents).map(|id| format!("herbie{}", id)).collect::<Vec<_>>().join(" ");
+    let cmdin = lisp_expr.to_lisp("herbie");
+    let lisp_expr = match lisp::precondition(ranges, "herbie") {
+        Some(pre) => format!("(lambda ({}) #:pre {} {})\n", params, pre, cmdin),
+        None => format!("(lambda ({}) {})\n", params, cmdin),
+    };
+    let lisp_expr = lisp_expr.as_bytes();
+    child.stdin
+        .as_mut().expect("Herbie-inout's stdin not captured")
+        .write(lisp_expr).expect("Could not write on herbie-inout's stdin")
+    ;
+
+    match conf.timeout {
+        Some(timeout) => {
+            match child.wait_timeout(Duration::from_secs(timeout as u64)) {
+                Ok(Some(status)) if status.success() => (),
+                Ok(Some(status)) => {
+                    return Err(format!("herbie-inout did not return successfully: status={}", status).into());
+                }
+                Ok(None) => return Ok(Output::TimedOut),
+                Err(err) => {
+                    return Err(format!("herbie-inout did not return successfully: {}", err).into());
+                }
+            }
+        }
+        None => {
+            match child.wait() {
+                Ok(status) if status.success() => (),
+                Ok(status) => {
+                    return Err(format!("herbie-inout did not return successfully: status={}", status).into());
+                }
+                Err(err) => {
+                    return Err(format!("herbie-inout did not return successfully: {}", err).into());
+                }
+            }
+        }
+    }
+
+    let mut stdout = if let Some(output) = child.stdout {
+        output
+    }
+    else {
+        return Err("cannot capture herbie-inout output".into());
+    };
+
+    let mut output = String::new();
+    if let Err(err) = stdout.read_to_string(&mut output) {
+        return Err(format!("cannot read output: {}", err).into());
+    }
+
+    let mut output = output.lines();
+
+    let parse_error = |s: Option<&str>| -> Option<f64> {
+        match s {
+            Some(s) => {
+                match s.split(' ').last().map(str::parse::<f64>) {
+                    Some(Ok(f)) => Some(f),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    };
+
+    match (parse_error(output.next()), parse_error(output.next()), output.next()) {
+        (Some(errin), Some(errout), Some(cmdout)) => {
+            Ok(Output::Done { errin: errin, errout: errout, cmdout: cmdout.into() })
+        }
+        _ => Err("Could not parse herbie-inout output".into()),
+    }
+}
+
+/// Save a result of Herbie to the writable database of `conf`, if any.
+pub fn save(
+    conf: &Conf,
+    cmdin: &str, cmdout: &LispExpr,
+    seed: &str,
+    errin: f64, errout: f64
+) -> Result<(), String> {
+    // results are only saved to the writable database
+    let database = if let Some(database) = conf.writable_database() {
+        database
+    }
+    else {
+        return Ok(());
+    };
+
+    let connection = if database.user {
+        if let Some(dir) = Path::new(&database.path).parent() {
+            try!(fs::create_dir_all(dir).map_err(|err| {
+                format!("Could not create the user cache {}: {}", dir.display(), err)
+            }));
+        }
+
+        db::create(&database.path)
+    }
+    else {
+        db::open(&database.path, true)
+    };
+
+    let row = db::Row {
+        id: 0,
+        cmdin: cmdin.into(),
+        cmdout: cmdout.to_lisp("herbie"),
+        opts: seed.into(),
+        errin: Some(errin),
+        errout: Some(errout),
+    };
+
+    connection.and_then(|connection| db::merge(&connection, &[row]))
+              .map(|_| ())
+              .map_err(|err| format!("Could not save database, got SQL error {}", err))
+}
+
+/// Return the estimator, which is only created when first needed.
+pub fn estimator(estimator: &mut Option<Estimator>) -> &Estimator {
+    if estimator.is_none() {
+        *estimator = Some(Estimator::new(estimate::DEFAULT_POINTS, 1));
+    }
+
+    estimator.as_ref().expect("The estimator was just created")
+}
@@ -0,0 +1,103 @@
+//! The project's ignore file, where the suggestions rejected with `herbie-lint --review` are
+//! recorded so that their expressions are not reported again.
+//!
+//! Each line of the file is an ignored expression, as `file: expression`, where the file is
+//! relative to the directory of the ignore file if it is in it, and as reported otherwise. File
+//! names are compared as given, whatever the current directory. Whitespace in expressions is not
+//! significant. Empty lines and lines starting with `#` are ignored.
+
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::{Error as IOError, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+
+const HEADER: &'static str = "\
+# Expressions that herbie-lint does not report, as `file: expression`.
+";
+
+#[derive(Debug)]
+pub struct Ignores {
+    path: PathBuf,
+    /// The directory file names are relative to.
+    dir: PathBuf,
+    /// The file and the normalized code of each ignored expression.
+    entries: HashSet<(String, String)>,
+}
+
+impl Ignores {
+    /// Read the ignore file at `path`. A missing file has no entry, and is created when an
+    /// expression is first ignored.
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Ignores, IOError> {
+        let path = path.as_ref().to_path_buf();
+        let dir = path.parent().map_or_else(PathBuf::new, Path::to_path_buf);
+        let mut ignores = Ignores { path: path, dir: dir, entries: HashSet::new() };
+
+        let mut content = String::new();
+        match File::open(&ignores.path) {
+            Ok(mut file) => try!(file.read_to_string(&mut content)),
+            Err(ref err) if err.kind() == ErrorKind::NotFound => return Ok(ignores),
+            Err(err) => return Err(err),
+        };
+
+        for (idx, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            match line.find(": ") {
+                Some(colon) => {
+                    let entry = (line[..colon].into(), normalize(&line[colon + 2..]));
+                    ignores.entries.insert(entry);
+                }
+                None => {
+                    return Err(IOError::new(ErrorKind::InvalidData, format!(
+                        "{}: line {}: expected `file: expression`",
+                        ignores.path.display(),
+                        idx + 1
+                    )));
+                }
+            }
+        }
+
+        Ok(ignores)
+    }
+
+    /// Whether the expression whose code is `expr` in the file `file_name`, as reported in the
+    /// suggestions, is ignored.
+    pub fn contains(&self, file_name: &str, expr: &str) -> bool {
+        self.entries.contains(&(self.relative(file_name), normalize(expr)))
+    }
+
+    /// Ignore an expression, and record it in the ignore file.
+    pub fn add(&mut self, file_name: &str, expr: &str) -> Result<(), IOError> {
+        let entry = (self.relative(file_name), normalize(expr));
+        if self.entries.contains(&entry) {
+            return Ok(());
+        }
+
+        let is_new = !self.path.exists();
+        let mut file = try!(OpenOptions::new().append(true).create(true).open(&self.path));
+        if is_new {
+            try!(file.write_all(HEADER.as_bytes()));
+        }
+        try!(writeln!(file, "{}: {}", entry.0, entry.1));
+
+        self.entries.insert(entry);
+        Ok(())
+    }
+
+    /// Return a file name relative to the directory of the ignore file, if it is in it, e.g.
+    /// `src/lib.rs` for `/project/src/lib.rs` and `/project/Herbie.ignore`.
+    fn relative(&self, file_name: &str) -> String {
+        match Path::new(file_name).strip_prefix(&self.dir) {
+            Ok(path) => path.to_string_lossy().into_owned(),
+            Err(..) => file_name.into(),
+        }
+    }
+}
+
+/// Replace each sequence of whitespace in some code with a single space.
+fn normalize(expr: &str) -> String {
+    expr.split_whitespace().collect::<Vec<_>>().join(" ")
+}
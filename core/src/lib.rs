@@ -0,0 +1,37 @@
+//! The parts of *herbie-lint* which do not depend on rustc: Herbie's expressions with their
+//! parser and printers, the matcher, the database, the configuration, the estimation of the error
+//! of expressions, the calls to Herbie, the analysis of an expression built on them, the
+//! suggestions and the report. They can be used from build scripts, tests or other tools.
+
+#![cfg_attr(feature="clippy", feature(plugin))]
+#![cfg_attr(feature="clippy", plugin(clippy))]
+
+#![deny(
+    missing_copy_implementations,
+    missing_debug_implementations,
+    trivial_casts,
+    trivial_numeric_casts,
+    unsafe_code,
+    unused_import_braces,
+    unused_qualifications,
+)]
+
+extern crate rusqlite;
+extern crate rustc_serialize;
+extern crate toml;
+extern crate wait_timeout;
+
+pub mod bigfloat;
+pub mod check;
+pub mod conf;
+pub mod cost;
+pub mod db;
+pub mod estimate;
+pub mod herbie;
+pub mod ignore;
+pub mod lisp;
+pub mod matcher;
+pub mod range;
+pub mod report;
+pub mod sarif;
+pub mod suggestion;
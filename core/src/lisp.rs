@@ -0,0 +1,983 @@
+#![cfg_attr(feature="clippy", allow(float_cmp))]
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std;
+
+/// The binary operators of Herbie's language.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl BinOp {
+    /// Return the operator as written in both Lisp and Rust, e.g. `+`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+            BinOp::Rem => "%",
+        }
+    }
+
+    /// Whether the operands can be swapped without changing the value of the operation.
+    pub fn is_commutative(self) -> bool {
+        self == BinOp::Add || self == BinOp::Mul
+    }
+}
+
+/// The unary operators of Herbie's language.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum UnOp {
+    Neg,
+}
+
+impl UnOp {
+    /// Return the operator as written in both Lisp and Rust, e.g. `-`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            UnOp::Neg => "-",
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub enum LispExpr {
+    Binary(BinOp, Box<LispExpr>, Box<LispExpr>),
+    Fun(String, Vec<LispExpr>),
+    Ident(u64),
+    Lit(f64),
+    Unary(UnOp, Box<LispExpr>),
+}
+
+impl std::fmt::Debug for LispExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        f.pad(&self.to_lisp("$"))
+    }
+}
+
+/// List `(herbie_name, rust_name, arity)`, the arity including the receiver of the method.
+/// Warning: *MUST* be alphabetized on Herbie name.
+/// Herbie also supports the following:
+///   * `cot` (cotangent),
+///   * `expt` (Rust has `powi` vs. `powf`),
+///   * `mod`,
+///   * `sqr` (square),
+const KNOWN_FUNS : &'static [(&'static str, &'static str, usize)] = &[
+    ("abs",   "abs",     1),
+    ("acos",  "acos",    1),
+    ("asin",  "asin",    1),
+    ("atan",  "atan",    1),
+    ("atan2", "atan2",   2),
+    ("cos",   "cos",     1),
+    ("cosh",  "cosh",    1),
+    ("exp",   "exp",     1),
+    ("expm1", "exp_m1",  1),
+    ("expt",  "powf",    2),
+    ("fma",   "mul_add", 3),
+    ("hypot", "hypot",   2),
+    ("log",   "ln",      1),
+    ("log1p", "ln_1p",   1),
+    ("sin",   "sin",     1),
+    ("sinh",  "sinh",    1),
+    ("sqrt",  "sqrt",    1),
+    ("tan",   "tan",     1),
+    ("tanh",  "tanh",    1),
+];
+
+/// Return the name of the Rust method corresponding to a Herbie function.
+pub fn rust_name(herbie_name: &str) -> Option<&'static str> {
+    KNOWN_FUNS .iter()
+               .find(|&&(name, _, _)| herbie_name == name)
+               .map(|&(_, rust_name, _)| rust_name)
+}
+
+/// Return the name of the Herbie function corresponding to a Rust method, called with
+/// `nb_params` arguments including its receiver.
+pub fn herbie_name(rust_name: &str, nb_params: usize) -> Option<&'static str> {
+    KNOWN_FUNS.iter()
+              .find(|&&(_, name, params)| rust_name == name && nb_params == params)
+              .map(|t| t.0)
+}
+
+/// Precedence of a Rust expression, used to print as few parentheses as possible.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Prec {
+    /// Anything that binds less tightly than `+`. Block-like expressions are also put there so
+    /// that a suggestion starting with one stays valid in statement position.
+    Min,
+    Add,
+    Mul,
+    Cast,
+    Unary,
+    /// Method calls, field accesses, paths, literals, etc.
+    Max,
+}
+
+impl Prec {
+    pub fn of_binop(op: BinOp) -> Prec {
+        match op {
+            BinOp::Add | BinOp::Sub => Prec::Add,
+            BinOp::Mul | BinOp::Div | BinOp::Rem => Prec::Mul,
+        }
+    }
+
+    /// Precedence of a snippet of the user's code which had precedence `self`.
+    pub fn of_snippet(self, snippet: &str) -> Prec {
+        if is_parenthesized(snippet) {
+            Prec::Max
+        }
+        else {
+            self
+        }
+    }
+}
+
+/// Check whether a snippet is entirely enclosed in a pair of parentheses, e.g. `(a + b)` but not
+/// `(a + b) * (c + d)`.
+pub fn is_parenthesized(snippet: &str) -> bool {
+    if !snippet.starts_with('(') || !snippet.ends_with(')') {
+        return false;
+    }
+
+    let mut depth = 0;
+    for (i, c) in snippet.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return i == snippet.len() - 1;
+                }
+            }
+            _ => (),
+        }
+    }
+
+    false
+}
+
+/// Maximum width of a line, as in rustfmt.
+const MAX_WIDTH: usize = 100;
+
+/// Format a float with the shortest representation that round-trips, using the exponent notation
+/// for very large or very small numbers, e.g. `1`, `0.1` or `1e-300`.
+fn float_to_string(f: f64) -> String {
+    if f != 0. && (f.abs() >= 1e16 || f.abs() < 1e-5) {
+        format!("{:e}", f)
+    }
+    else {
+        format!("{}", f)
+    }
+}
+
+/// Format a float as a Racket number.
+fn float_to_lisp(f: f64) -> String {
+    if f.is_nan() {
+        "+nan.0".into()
+    }
+    else if f.is_infinite() {
+        if f > 0. { "+inf.0".into() } else { "-inf.0".into() }
+    }
+    else {
+        float_to_string(f)
+    }
+}
+
+/// Format a float as a valid Rust `f64` literal, e.g. `1.0` rather than `1` which would be an
+/// integer.
+fn float_to_rust(f: f64) -> String {
+    if f.is_nan() {
+        "std::f64::NAN".into()
+    }
+    else if f.is_infinite() {
+        if f > 0. { "std::f64::INFINITY".into() } else { "std::f64::NEG_INFINITY".into() }
+    }
+    else {
+        let mut buf = float_to_string(f);
+        if !buf.contains('.') && !buf.contains('e') {
+            buf.push_str(".0");
+        }
+        buf
+    }
+}
+
+/// Return a Herbie precondition constraining identifiers with a known range, if any, e.g.
+/// `(and (<= 0 herbie0 255) (<= -128 herbie1))`.
+pub fn precondition(ranges: &HashMap<u64, (f64, f64)>, placeholder: &str) -> Option<String> {
+    let mut ranges : Vec<_> = ranges.iter().map(|(&id, &(lo, hi))| (id, lo, hi)).collect();
+    ranges.sort_by_key(|&(id, _, _)| id);
+
+    let mut conditions = Vec::new();
+    for (id, lo, hi) in ranges {
+        match (lo.is_finite(), hi.is_finite()) {
+            (true, true) => conditions.push(format!("(<= {} {}{} {})", lo, placeholder, id, hi)),
+            (true, false) => conditions.push(format!("(<= {} {}{})", lo, placeholder, id)),
+            (false, true) => conditions.push(format!("(<= {}{} {})", placeholder, id, hi)),
+            (false, false) => (),
+        }
+    }
+
+    if conditions.is_empty() {
+        None
+    }
+    else {
+        Some(format!("(and {})", conditions.join(" ")))
+    }
+}
+
+impl LispExpr {
+    pub fn to_lisp(&self, placeholder: &str) -> String {
+        match *self {
+            LispExpr::Binary(op, ref lhs, ref rhs) => {
+                format!(
+                    "({} {} {})",
+                    op.as_str(),
+                    lhs.to_lisp(placeholder),
+                    rhs.to_lisp(placeholder)
+                )
+            }
+            LispExpr::Fun(ref name, ref params) => {
+                let mut buf = String::new();
+                buf.push('(');
+                buf.push_str(name);
+
+                for p in params {
+                    buf.push(' ');
+                    buf.push_str(&p.to_lisp(placeholder));
+                }
+
+                buf.push(')');
+                buf
+            }
+            LispExpr::Lit(f) => float_to_lisp(f),
+            LispExpr::Unary(op, ref expr) => {
+                format!("({} {})", op.as_str(), expr.to_lisp(placeholder))
+            }
+            LispExpr::Ident(id) => format!("{}{}", placeholder, id),
+        }
+    }
+
+    pub fn depth(&self) -> u64 {
+        match *self {
+            LispExpr::Binary(_, ref lhs, ref rhs) => 1 + std::cmp::max(lhs.depth(), rhs.depth()),
+            LispExpr::Fun(_, ref params) => 1 + params.iter().map(Self::depth).max().unwrap_or(0),
+            LispExpr::Lit(_) | LispExpr::Ident(_) => 0,
+            LispExpr::Unary(_, ref expr) => expr.depth(),
+        }
+    }
+
+    /// Convert the expression to Rust code written at `position`, with only the necessary
+    /// parentheses, in a context requiring the precedence `prec`, or a strictly higher one if
+    /// `strict`. Return `None` if it uses a function with no Rust equivalent.
+    pub fn to_rust<N: RustNames>(
+        &self,
+        names: &N,
+        position: Position,
+        (prec, strict): (Prec, bool)
+    ) -> Option<String> {
+        if !self.has_rust_functions() {
+            return None;
+        }
+
+        let (rust, rust_prec) = self.to_rust_layout(names, position.indent, position.column);
+        if rust_prec < prec || (strict && rust_prec == prec) {
+            let (rust, _) = self.to_rust_layout(names, position.indent, position.column + 1);
+            Some(format!("({})", rust))
+        }
+        else {
+            Some(rust)
+        }
+    }
+
+    /// Whether all the functions of the expression have a Rust equivalent, see `rust_name`.
+    pub fn has_rust_functions(&self) -> bool {
+        self.functions().iter().all(|name| rust_name(name).is_some())
+    }
+
+    /// Return the number of occurrences of an identifier in the expression.
+    pub fn count_ident(&self, id: u64) -> usize {
+        match *self {
+            LispExpr::Binary(_, ref lhs, ref rhs) => lhs.count_ident(id) + rhs.count_ident(id),
+            LispExpr::Fun(_, ref params) => params.iter().fold(0, |acc, p| acc + p.count_ident(id)),
+            LispExpr::Ident(i) if i == id => 1,
+            LispExpr::Ident(_) | LispExpr::Lit(_) => 0,
+            LispExpr::Unary(_, ref expr) => expr.count_ident(id),
+        }
+    }
+
+    /// Convert the expression to an assignment to `lhs`, written at `position`. If possible, use
+    /// the compound assignment form, e.g. `a += b` rather than `a = a + b`. Return `None` if it
+    /// uses a function with no Rust equivalent.
+    pub fn to_rust_assign<N: RustNames>(
+        &self,
+        names: &N,
+        lhs: &str,
+        position: Position
+    ) -> Option<String> {
+        if !self.has_rust_functions() {
+            return None;
+        }
+
+        if let LispExpr::Binary(op, ref op_lhs, ref op_rhs) = *self {
+            if to_rust_impl(op_lhs, names).0 == lhs {
+                let prefix = format!("{} {}= ", lhs, op.as_str());
+                let column = position.column + prefix.len();
+                let (rhs, _) = op_rhs.to_rust_layout(names, position.indent, column);
+                return Some(prefix + &rhs);
+            }
+        }
+
+        let prefix = format!("{} = ", lhs);
+        let (rhs, _) = self.to_rust_layout(names, position.indent, position.column + prefix.len());
+        Some(prefix + &rhs)
+    }
+
+    /// Convert the expression to Rust code, without a context. Identifiers are printed as
+    /// `placeholder` followed by their number, as in `to_lisp`. Return `None` if it uses a
+    /// function with no Rust equivalent.
+    pub fn to_rust_placeholder(&self, placeholder: &str) -> Option<String> {
+        if self.has_rust_functions() {
+            Some(to_rust_impl(self, &Placeholder(placeholder)).0)
+        }
+        else {
+            None
+        }
+    }
+
+    /// Simplify the expression without changing its value: the operands of `+` and `*` are sorted
+    /// and double negations are removed. Identifiers are not renamed. Operands are sorted by
+    /// shape, then by how they repeat identifiers, e.g. `(* herbie1 herbie1)` comes before
+    /// `(* herbie0 herbie1)`, and only then by the numbers of their identifiers.
+    pub fn simplify(&self) -> LispExpr {
+        match *self {
+            LispExpr::Binary(op, ref lhs, ref rhs) => {
+                let (lhs, rhs) = (lhs.simplify(), rhs.simplify());
+                if op.is_commutative() && cmp_operands(&rhs, &lhs) == Ordering::Less {
+                    LispExpr::Binary(op, Box::new(rhs), Box::new(lhs))
+                }
+                else {
+                    LispExpr::Binary(op, Box::new(lhs), Box::new(rhs))
+                }
+            }
+            LispExpr::Fun(ref name, ref params) => {
+                LispExpr::Fun(name.clone(), params.iter().map(Self::simplify).collect())
+            }
+            LispExpr::Ident(..) | LispExpr::Lit(..) => self.clone(),
+            LispExpr::Unary(UnOp::Neg, ref expr) => {
+                match expr.simplify() {
+                    LispExpr::Lit(f) => LispExpr::Lit(-f),
+                    LispExpr::Unary(UnOp::Neg, expr) => *expr,
+                    expr => LispExpr::Unary(UnOp::Neg, Box::new(expr)),
+                }
+            }
+        }
+    }
+
+    /// Return the canonical form of the expression, and the renaming of identifiers applied to
+    /// get it. The canonical form is simplified, see `simplify`, and its identifiers are numbered
+    /// by order of first occurrence, so that e.g. `(+ herbie1 (sqr herbie0))` and
+    /// `(+ (* herbie3 herbie3) herbie2)` have the same canonical form.
+    pub fn canonicalize(&self) -> (LispExpr, HashMap<u64, u64>) {
+        let expr = self.simplify();
+        let mut renaming = expr.identifiers().into_iter().zip(0..).collect::<HashMap<_, _>>();
+        let mut expr = expr.rename(&renaming);
+
+        // renumbering can change the order of operands which only differ by their identifiers
+        for _ in 0..MAX_CANONICALIZE_STEPS {
+            let next = expr.simplify();
+            let step = next.identifiers().into_iter().zip(0..).collect::<HashMap<_, _>>();
+            let next = next.rename(&step);
+            if next == expr {
+                break;
+            }
+
+            for (_, id) in renaming.iter_mut() {
+                *id = step[&*id];
+            }
+            expr = next;
+        }
+
+        (expr, renaming)
+    }
+
+    /// Return a hash of the canonical form of the expression. Unlike `std::hash::Hash`, it does
+    /// not depend on the version of the compiler or on the platform, so it can be stored.
+    pub fn canonical_hash(&self) -> u64 {
+        // 64-bit FNV-1a
+        self.canonicalize().0.to_lisp("herbie").bytes().fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        })
+    }
+
+    /// Return the identifiers of the expression, in order of first occurrence.
+    pub fn identifiers(&self) -> Vec<u64> {
+        fn identifiers_impl(expr: &LispExpr, ids: &mut Vec<u64>) {
+            match *expr {
+                LispExpr::Binary(_, ref lhs, ref rhs) => {
+                    identifiers_impl(lhs, ids);
+                    identifiers_impl(rhs, ids);
+                }
+                LispExpr::Fun(_, ref params) => {
+                    for param in params {
+                        identifiers_impl(param, ids);
+                    }
+                }
+                LispExpr::Ident(id) => {
+                    if !ids.contains(&id) {
+                        ids.push(id);
+                    }
+                }
+                LispExpr::Lit(_) => (),
+                LispExpr::Unary(_, ref expr) => identifiers_impl(expr, ids),
+            }
+        }
+
+        let mut ids = vec![];
+        identifiers_impl(self, &mut ids);
+        ids
+    }
+
+    /// Rename the identifiers of the expression. Identifiers not in `renaming` are kept.
+    pub fn rename(&self, renaming: &HashMap<u64, u64>) -> LispExpr {
+        match *self {
+            LispExpr::Binary(op, ref lhs, ref rhs) => {
+                LispExpr::Binary(op, Box::new(lhs.rename(renaming)), Box::new(rhs.rename(renaming)))
+            }
+            LispExpr::Fun(ref name, ref params) => {
+                LispExpr::Fun(name.clone(), params.iter().map(|p| p.rename(renaming)).collect())
+            }
+            LispExpr::Ident(id) => LispExpr::Ident(*renaming.get(&id).unwrap_or(&id)),
+            LispExpr::Lit(f) => LispExpr::Lit(f),
+            LispExpr::Unary(op, ref expr) => LispExpr::Unary(op, Box::new(expr.rename(renaming))),
+        }
+    }
+
+    /// Return the names of the functions used in the expression, in order of first use.
+    pub fn functions(&self) -> Vec<&str> {
+        fn functions_impl<'a>(expr: &'a LispExpr, funs: &mut Vec<&'a str>) {
+            match *expr {
+                LispExpr::Binary(_, ref lhs, ref rhs) => {
+                    functions_impl(lhs, funs);
+                    functions_impl(rhs, funs);
+                }
+                LispExpr::Fun(ref name, ref params) => {
+                    if !funs.contains(&&name[..]) {
+                        funs.push(name);
+                    }
+                    for param in params {
+                        functions_impl(param, funs);
+                    }
+                }
+                LispExpr::Ident(_) | LispExpr::Lit(_) => (),
+                LispExpr::Unary(_, ref expr) => functions_impl(expr, funs),
+            }
+        }
+
+        let mut funs = vec![];
+        functions_impl(self, &mut funs);
+        funs
+    }
+
+    /// Convert the expression to Rust code starting at the given column of a line with the given
+    /// indentation. If it does not fit on one line, the top-level chain of binary operators is
+    /// broken before each operator, as rustfmt would.
+    fn to_rust_layout<N: RustNames>(
+        &self,
+        names: &N,
+        indent: usize,
+        column: usize
+    ) -> (String, Prec) {
+        let one_line = to_rust_impl(self, names);
+        if column + one_line.0.len() <= MAX_WIDTH {
+            return one_line;
+        }
+
+        let prec = if let LispExpr::Binary(op, _, _) = *self {
+            Prec::of_binop(op)
+        }
+        else {
+            return one_line;
+        };
+
+        // collect the operands of `a op b op c…` where all the `op`s have the same precedence
+        let mut chain = vec![];
+        let mut first = self;
+        while let LispExpr::Binary(op, ref lhs, ref rhs) = *first {
+            if Prec::of_binop(op) != prec {
+                break;
+            }
+            chain.push((op, rhs));
+            first = &**lhs;
+        }
+
+        let mut buf = parenthesize(to_rust_impl(first, names), prec);
+        for &(op, rhs) in chain.iter().rev() {
+            buf.push('\n');
+            for _ in 0..indent + 4 {
+                buf.push(' ');
+            }
+            buf.push_str(op.as_str());
+            buf.push(' ');
+            buf.push_str(&parenthesize_strict(to_rust_impl(rhs, names), prec));
+        }
+
+        (buf, prec)
+    }
+}
+
+/// The number of times `canonicalize` sorts and renumbers an expression at most.
+const MAX_CANONICALIZE_STEPS: usize = 4;
+
+fn then(ord: Ordering, next: Ordering) -> Ordering {
+    if ord == Ordering::Equal { next } else { ord }
+}
+
+/// Order the operands of a commutative operator, see `simplify`.
+fn cmp_operands(lhs: &LispExpr, rhs: &LispExpr) -> Ordering {
+    fn pattern(expr: &LispExpr) -> LispExpr {
+        let renaming = expr.identifiers().into_iter().zip(0..).collect();
+        expr.rename(&renaming)
+    }
+
+    let ord = then(cmp_shape(lhs, rhs, false), cmp_shape(&pattern(lhs), &pattern(rhs), true));
+    then(ord, cmp_shape(lhs, rhs, true))
+}
+
+/// Order expressions by their shape, ignoring the numbering of their identifiers unless `idents`
+/// is set.
+fn cmp_shape(lhs: &LispExpr, rhs: &LispExpr, idents: bool) -> Ordering {
+    fn rank(expr: &LispExpr) -> u8 {
+        match *expr {
+            LispExpr::Ident(..) => 0,
+            LispExpr::Lit(..) => 1,
+            LispExpr::Unary(..) => 2,
+            LispExpr::Fun(..) => 3,
+            LispExpr::Binary(..) => 4,
+        }
+    }
+
+    match (lhs, rhs) {
+        (&LispExpr::Binary(lop, ref l1, ref l2), &LispExpr::Binary(rop, ref r1, ref r2)) => {
+            let ord = then(lop.as_str().cmp(rop.as_str()), cmp_shape(l1, r1, idents));
+            then(ord, cmp_shape(l2, r2, idents))
+        }
+        (&LispExpr::Fun(ref lname, ref lp), &LispExpr::Fun(ref rname, ref rp)) => {
+            lp.iter().zip(rp).fold(then(lname.cmp(rname), lp.len().cmp(&rp.len())), |ord, (l, r)| {
+                then(ord, cmp_shape(l, r, idents))
+            })
+        }
+        (&LispExpr::Ident(l), &LispExpr::Ident(r)) if idents => l.cmp(&r),
+        (&LispExpr::Lit(l), &LispExpr::Lit(r)) => l.partial_cmp(&r).unwrap_or(Ordering::Equal),
+        (&LispExpr::Unary(lop, ref l), &LispExpr::Unary(rop, ref r)) => {
+            then(lop.as_str().cmp(rop.as_str()), cmp_shape(l, r, idents))
+        }
+        _ => rank(lhs).cmp(&rank(rhs)),
+    }
+}
+
+/// Where Rust code is written.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Position {
+    /// The indentation of the line.
+    pub indent: usize,
+    /// The column the code starts at.
+    pub column: usize,
+}
+
+/// How identifiers and literals are printed as Rust code.
+pub trait RustNames {
+    /// Return the Rust code for an identifier, and its precedence.
+    fn ident(&self, id: u64) -> (String, Prec);
+
+    /// Return the user's spelling of a literal, if they wrote it.
+    fn lit(&self, f: f64) -> Option<String>;
+}
+
+/// Identifiers are printed as `placeholder` followed by their number, as in `to_lisp`.
+struct Placeholder<'a>(&'a str);
+
+impl<'a> RustNames for Placeholder<'a> {
+    fn ident(&self, id: u64) -> (String, Prec) {
+        (format!("{}{}", self.0, id), Prec::Max)
+    }
+
+    fn lit(&self, _: f64) -> Option<String> {
+        None
+    }
+}
+
+/// Convert an expression to Rust code on one line, and return its precedence. Its functions must
+/// have a Rust equivalent.
+fn to_rust_impl<N: RustNames>(expr: &LispExpr, names: &N) -> (String, Prec) {
+    match *expr {
+        LispExpr::Binary(op, ref lhs, ref rhs) => {
+            let prec = Prec::of_binop(op);
+            // binary operators are left-associative, and floating point operations are not
+            // associative, so the right-hand side needs parentheses at equal precedence
+            let lhs = parenthesize(to_rust_impl(lhs, names), prec);
+            let rhs = parenthesize_strict(to_rust_impl(rhs, names), prec);
+            (format!("{} {} {}", lhs, op.as_str(), rhs), prec)
+        }
+        LispExpr::Fun(ref name, ref params) => {
+            let receiver = if let LispExpr::Lit(f) = params[0] {
+                lit_to_rust(f, names, true)
+            }
+            else {
+                to_rust_impl(&params[0], names)
+            };
+
+            let mut buf = parenthesize(receiver, Prec::Max);
+            buf.push('.');
+            buf.push_str(rust_name(name).expect("Functions without a Rust name are checked first"));
+            buf.push('(');
+
+            for (i, p) in params.iter().skip(1).enumerate() {
+                if i != 0 {
+                    buf.push_str(", ");
+                }
+                buf.push_str(&to_rust_impl(p, names).0);
+            }
+
+            buf.push(')');
+            (buf, Prec::Max)
+        }
+        LispExpr::Lit(f) => lit_to_rust(f, names, false),
+        LispExpr::Unary(op, ref expr) => {
+            // `- -a` would be printed `--a`
+            let expr = parenthesize_strict(to_rust_impl(expr, names), Prec::Unary);
+            (format!("{}{}", op.as_str(), expr), Prec::Unary)
+        }
+        LispExpr::Ident(id) => names.ident(id),
+    }
+}
+
+/// Convert a literal to Rust code, keeping the user's spelling if they wrote it. The receiver of a
+/// method call needs a suffix for the type of the literal to be known.
+fn lit_to_rust<N: RustNames>(f: f64, names: &N, suffixed: bool) -> (String, Prec) {
+    let prec = if f.is_sign_negative() { Prec::Unary } else { Prec::Max };
+
+    if let Some(lit) = names.lit(f) {
+        if !suffixed || lit.ends_with("f64") {
+            return (lit, prec);
+        }
+    }
+
+    let mut buf = float_to_rust(f);
+    if suffixed && f.is_finite() {
+        buf.push_str("f64");
+    }
+    (buf, prec)
+}
+
+/// Add parentheses around an expression if its precedence is lower than `prec`.
+fn parenthesize((expr, expr_prec): (String, Prec), prec: Prec) -> String {
+    if expr_prec < prec {
+        format!("({})", expr)
+    }
+    else {
+        expr
+    }
+}
+
+/// Add parentheses around an expression if its precedence is not higher than `prec`.
+fn parenthesize_strict((expr, expr_prec): (String, Prec), prec: Prec) -> String {
+    if expr_prec <= prec {
+        format!("({})", expr)
+    }
+    else {
+        expr
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParseError {
+    Arity,
+    Expected(char),
+    Ident,
+    Float,
+    Unexpected(char),
+    EOE,
+}
+
+#[derive(Debug, Default)]
+pub struct Parser {
+    ids: Vec<String>,
+    stack: Vec<char>,
+}
+
+impl Parser {
+    pub fn new() -> Parser {
+        Parser::default()
+    }
+
+    pub fn parse(&mut self, s: &str) -> Result<LispExpr, ParseError> {
+        let mut it = s.chars();
+
+        match self.parse_impl(&mut it) {
+            Ok(result) => {
+                if it.next().is_some() {
+                    Err(ParseError::EOE)
+                }
+                else  {
+                    Ok(result)
+                }
+            }
+            err @ Err(..) => err,
+        }
+    }
+
+    fn parse_impl<It: Iterator<Item = char>>(&mut self, it: &mut It)
+    -> Result<LispExpr, ParseError> {
+        match self.get_char(it, true) {
+            Some('(') => {
+                match self.get_char(it, true) {
+                    Some('+') => self.parse_op(it, BinOp::Add),
+                    Some('-') => self.parse_op(it, BinOp::Sub),
+                    Some('*') => self.parse_op(it, BinOp::Mul),
+                    Some('/') => self.parse_op(it, BinOp::Div),
+                    Some('%') => self.parse_op(it, BinOp::Rem),
+                    Some('\u{3bb}') => self.parse_lambda(it),
+                    Some(c) => {
+                        self.put_back(c);
+                        self.parse_fun(it)
+                    }
+                    None => Err(ParseError::EOE),
+                }
+            }
+            Some(c) if c.is_digit(10) => {
+                self.put_back(c);
+                self.parse_float(it)
+            }
+            Some('-') => {
+                match self.get_char(it, false) {
+                    Some(c) if c.is_digit(10) => {
+                        self.put_back(c);
+                        self.put_back('-');
+                        self.parse_float(it)
+                    }
+                    Some(c) => {
+                        self.put_back(c);
+                        self.put_back('-');
+                        Err(ParseError::Unexpected('-'))
+                    }
+                    None => {
+                        self.put_back('-');
+                        Err(ParseError::Unexpected('-'))
+                    }
+                }
+            }
+            Some(c) if c.is_alphanumeric() => {
+                self.put_back(c);
+                self.parse_ident(it)
+            }
+            Some(c) => {
+                self.put_back(c);
+                Err(ParseError::Unexpected(c))
+            }
+            None => Err(ParseError::EOE),
+        }
+    }
+
+    fn expect<It: Iterator<Item = char>>(
+        &mut self,
+        it: &mut It,
+        c: char,
+        skip_whitespace: bool
+    ) -> Result<(), ParseError> {
+        if self.get_char(it, skip_whitespace) == Some(c) {
+            Ok(())
+        }
+        else {
+            Err(ParseError::Expected(c))
+        }
+    }
+
+    fn parse_float<It: Iterator<Item = char>>(&mut self, it: &mut It)
+    -> Result<LispExpr, ParseError> {
+        let mut buf = String::new();
+        loop {
+            let c = self.get_char(it, false);
+            if let Some(c) = c {
+                if c.is_digit(10) || ['.', 'e', '+', '-'].contains(&c) {
+                    buf.push(c);
+                    continue;
+                }
+
+                self.put_back(c);
+            }
+
+            break;
+        }
+
+        let num = match buf.parse::<f64>() {
+            Ok(f) => f,
+            Err(..) => return Err(ParseError::Float),
+        };
+
+        // Herbie uses exact rationals such as `1/3`
+        match self.get_char(it, false) {
+            Some('/') => (),
+            Some(c) => {
+                self.put_back(c);
+                return Ok(LispExpr::Lit(num));
+            }
+            None => return Ok(LispExpr::Lit(num)),
+        }
+
+        let mut buf = String::new();
+        loop {
+            let c = self.get_char(it, false);
+            if let Some(c) = c {
+                if c.is_digit(10) {
+                    buf.push(c);
+                    continue;
+                }
+
+                self.put_back(c);
+            }
+
+            break;
+        }
+
+        match buf.parse::<f64>() {
+            Ok(den) if den != 0. => Ok(LispExpr::Lit(num / den)),
+            _ => Err(ParseError::Float),
+        }
+    }
+
+    fn parse_ident<It: Iterator<Item = char>>(&mut self, it: &mut It)
+    -> Result<LispExpr, ParseError> {
+        // TODO: Herbie also supports ‘pi’ and ‘e’ as native constants.
+        let mut buf = String::new();
+        loop {
+            let c = self.get_char(it, false);
+            if let Some(c) = c {
+                if c.is_alphanumeric() {
+                    buf.push(c);
+                    continue;
+                }
+                else {
+                    self.put_back(c);
+                }
+            }
+
+            break;
+        }
+
+        if let Some(id) = self.ids.iter().position(|e| e == &buf) {
+            Ok(LispExpr::Ident(id as u64))
+        }
+        else {
+            self.ids.push(buf);
+            Ok(LispExpr::Ident(self.ids.len() as u64 - 1))
+        }
+    }
+
+    fn parse_lambda<It: Iterator<Item = char>>(&mut self, it: &mut It)
+    -> Result<LispExpr, ParseError> {
+        loop {
+            match it.next() {
+                Some(')') | None => break,
+                _ => continue,
+            }
+        }
+
+        let r = self.parse_impl(it);
+        try!(self.expect(it, ')', true));
+        r
+    }
+
+    fn parse_fun<It: Iterator<Item = char>>(&mut self, it: &mut It)
+    -> Result<LispExpr, ParseError> {
+        let mut buf = String::new();
+        loop {
+            let c = self.get_char(it, false);
+            if let Some(c) = c {
+                if c.is_alphanumeric() {
+                    buf.push(c);
+                    continue;
+                }
+                else {
+                    self.put_back(c);
+                }
+            }
+
+            break;
+        }
+
+        if !buf.is_empty() {
+            let mut params = vec![];
+
+            while let Ok(param) = self.parse_impl(it) {
+                params.push(param);
+            }
+
+            try!(self.expect(it, ')', true));
+            if let Ok(idx) = KNOWN_FUNS.binary_search_by(|p| p.0.cmp(&buf)) {
+                return if KNOWN_FUNS[idx].2 == params.len() {
+                    Ok(LispExpr::Fun(buf, params))
+                }
+                else {
+                    Err(ParseError::Arity)
+                };
+            }
+            else if buf == "sqr" && params.len() == 1 {
+                let param = params.remove(0);
+                return Ok(LispExpr::Binary(BinOp::Mul, Box::new(param.clone()), Box::new(param)));
+            }
+        }
+
+        Err(ParseError::Ident)
+    }
+
+    fn parse_op<It: Iterator<Item = char>>(&mut self, it: &mut It, op: BinOp)
+    -> Result<LispExpr, ParseError> {
+        // TODO: Herbie seems to also support the following for the repip of a float: (/ 42) and
+        // rust has a function recip for that
+        let lhs = try!(self.parse_impl(it));
+        let r = if let Ok(rhs) = self.parse_impl(it) {
+            Ok(LispExpr::Binary(op, Box::new(lhs), Box::new(rhs)))
+        }
+        else if op == BinOp::Sub {
+            Ok(LispExpr::Unary(UnOp::Neg, Box::new(lhs)))
+        }
+        else {
+            return Err(ParseError::Arity);
+        };
+        try!(self.expect(it, ')', true));
+        r
+    }
+
+    fn get_char<It: Iterator<Item = char>>(
+        &mut self,
+        it: &mut It,
+        skip_whitespace: bool
+    ) -> Option<char> {
+        loop {
+            match self.stack.pop() {
+                Some(e) if skip_whitespace && e.is_whitespace() => continue,
+                Some(e) => return Some(e),
+                None => break,
+            }
+        }
+
+        loop {
+            match it.next() {
+                Some(e) if skip_whitespace && e.is_whitespace() => continue,
+                Some(e) => return Some(e),
+                None => return None,
+            }
+        }
+    }
+
+    fn put_back(&mut self, c: char) {
+        self.stack.push(c);
+    }
+}
@@ -0,0 +1,317 @@
+//! Matching of the expressions of a source language, such as Rust's HIR in the lint pass, with
+//! the patterns of the database, and conversion of these expressions to `LispExpr`.
+
+use lisp::{BinOp, LispExpr, Position, Prec, RustNames, UnOp};
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+
+/// What the matcher needs to know about an expression of the source language.
+#[derive(Debug)]
+pub enum Shape<'a, E: 'a, K> {
+    /// A binary operation, e.g. `a + b`. Compound assignments such as `a += b` also have this
+    /// shape.
+    Binary(BinOp, &'a E, &'a E),
+    /// A call to a method known to Herbie, by its Herbie name, the receiver being the first
+    /// argument, e.g. `a.hypot(b)`.
+    Fun(&'static str, Vec<&'a E>),
+    Unary(UnOp, &'a E),
+    /// A float literal, whose spelling is kept in suggestions.
+    Lit(f64),
+    /// A constant which is not a float literal, e.g. `2 as f64`.
+    Const(f64),
+    /// A value which is the same wherever an expression with the same key appears, e.g. a
+    /// variable or a field.
+    Var(K),
+    /// An expression with the same value as another one, e.g. a cast from `f64` to `f64`.
+    Transparent(&'a E),
+    /// Any other expression, which is treated as an opaque value.
+    Opaque,
+    /// An expression Herbie cannot be given, e.g. a call to an unknown function. It can still be
+    /// matched by an identifier of a pattern, as an opaque value.
+    Unknown,
+}
+
+/// A source language.
+pub trait Source {
+    type Expr;
+    /// Identifies variables, see `Shape::Var`.
+    type Key: PartialEq;
+
+    fn shape<'a>(&self, expr: &'a Self::Expr) -> Shape<'a, Self::Expr, Self::Key>;
+
+    /// Return the code of an expression, as written by the user.
+    fn snippet(&self, expr: &Self::Expr) -> String;
+
+    /// Return the precedence of an expression, regardless of any parentheses around it.
+    fn prec(&self, expr: &Self::Expr) -> Prec;
+
+    /// Return the range of the values of an expression bound to an identifier, if it is known,
+    /// e.g. from an assertion or from the type of an integer casted to `f64`.
+    fn range(&self, _expr: &Self::Expr) -> Option<(f64, f64)> {
+        None
+    }
+}
+
+/// The sub-expressions of the source bound to the identifiers of a `LispExpr`.
+#[derive(Debug)]
+pub struct Bindings<'a, E: 'a> {
+    pub idents: HashMap<u64, &'a E>,
+    /// The identifiers of `idents`, in the order their sub-expressions appear in the original
+    /// expression, which is the order they are evaluated in.
+    pub order: Vec<u64>,
+    /// Float literals written by the user, to keep their spelling in suggestions.
+    pub lits: Vec<(f64, &'a E)>,
+}
+
+impl<'a, E> Bindings<'a, E> {
+    fn new() -> Bindings<'a, E> {
+        Bindings {
+            idents: HashMap::new(),
+            order: Vec::new(),
+            lits: Vec::new(),
+        }
+    }
+
+    /// Check whether replacing the original expression with `rewrite` evaluates each opaque
+    /// sub-expression of the original exactly once and in the same order, in which case the
+    /// rewrite cannot change side effects.
+    pub fn is_exact_rewrite<S>(&self, source: &S, rewrite: &LispExpr) -> bool
+    where S: Source<Expr = E> {
+        let opaque = self.order.iter().cloned().filter(|id| {
+            match shape(source, self.idents[id]) {
+                Shape::Lit(..) | Shape::Const(..) | Shape::Var(..) => false,
+                _ => true,
+            }
+        }).collect::<Vec<_>>();
+
+        if opaque.iter().any(|&id| rewrite.count_ident(id) != 1) {
+            return false;
+        }
+
+        // e.g. `f() + g()` rewritten as `g() + f()` calls the functions in another order
+        let rewritten = rewrite.identifiers().into_iter().filter(|id| opaque.contains(id));
+        rewritten.eq(opaque.iter().cloned())
+    }
+}
+
+/// Return the shape of an expression, looking through transparent expressions.
+fn shape<'a, S: Source>(source: &S, expr: &'a S::Expr) -> Shape<'a, S::Expr, S::Key> {
+    match source.shape(expr) {
+        Shape::Transparent(inner) => shape(source, inner),
+        shape => shape,
+    }
+}
+
+/// Look through transparent expressions.
+fn skip_transparent<'a, S: Source>(source: &S, expr: &'a S::Expr) -> &'a S::Expr {
+    match source.shape(expr) {
+        Shape::Transparent(inner) => skip_transparent(source, inner),
+        _ => expr,
+    }
+}
+
+/// Check whether two bound expressions are known to have the same value.
+fn same_value<S: Source>(source: &S, lhs: &S::Expr, rhs: &S::Expr) -> bool {
+    match (shape(source, lhs), shape(source, rhs)) {
+        (Shape::Var(lkey), Shape::Var(rkey)) => lkey == rkey,
+        (Shape::Lit(l), Shape::Lit(r)) | (Shape::Const(l), Shape::Const(r)) => l == r,
+        _ => false,
+    }
+}
+
+/// Match an expression with a pattern from the database, and return the sub-expressions bound
+/// to the identifiers of the pattern. The operands of `+` and `*` can be in either order.
+pub fn match_expr<'a, S: Source>(
+    source: &S,
+    expr: &'a S::Expr,
+    pattern: &LispExpr
+) -> Option<Bindings<'a, S::Expr>> {
+    #[cfg_attr(feature="clippy", allow(similar_names))]
+    fn match_expr_impl<'a, S: Source>(
+        source: &S,
+        lhs: &'a S::Expr,
+        rhs: &LispExpr,
+        bindings: &mut Bindings<'a, S::Expr>
+    ) -> bool {
+        let lhs = skip_transparent(source, lhs);
+
+        match (source.shape(lhs), rhs) {
+            (Shape::Binary(lop, lp1, lp2), &LispExpr::Binary(rop, ref rp1, ref rp2)) => {
+                if lop != rop {
+                    return false;
+                }
+
+                let saved_idents = bindings.idents.clone();
+                let (saved_order, saved_lits) = (bindings.order.len(), bindings.lits.len());
+                if match_expr_impl(source, lp1, rp1, bindings)
+                && match_expr_impl(source, lp2, rp2, bindings) {
+                    return true;
+                }
+
+                // the database might have the operands in the other order, see `canonicalize`
+                if !rop.is_commutative() {
+                    return false;
+                }
+
+                bindings.idents = saved_idents;
+                bindings.order.truncate(saved_order);
+                bindings.lits.truncate(saved_lits);
+                match_expr_impl(source, lp1, rp2, bindings)
+                && match_expr_impl(source, lp2, rp1, bindings)
+            }
+            (Shape::Fun(lfun, ref lp), &LispExpr::Fun(ref rfun, ref rp)) => {
+                lfun == rfun
+                && lp.len() == rp.len()
+                && lp.iter().zip(rp).all(|(lp, rp)| match_expr_impl(source, lp, rp, bindings))
+            }
+            (Shape::Unary(lop, lp), &LispExpr::Unary(rop, ref rp)) => {
+                lop == rop && match_expr_impl(source, lp, rp, bindings)
+            }
+            (Shape::Lit(l), &LispExpr::Lit(r)) => {
+                if l == r {
+                    bindings.lits.push((r, lhs));
+                    true
+                }
+                else {
+                    false
+                }
+            }
+            (Shape::Const(l), &LispExpr::Lit(r)) => l == r,
+            (_, &LispExpr::Ident(rid)) => {
+                match bindings.idents.entry(rid) {
+                    Entry::Occupied(entry) => same_value(source, entry.get(), lhs),
+                    Entry::Vacant(vacant) => {
+                        vacant.insert(lhs);
+                        bindings.order.push(rid);
+                        true
+                    }
+                }
+            }
+            _ => false,
+        }
+    }
+
+    let mut bindings = Bindings::new();
+    if match_expr_impl(source, expr, pattern, &mut bindings) {
+        Some(bindings)
+    }
+    else {
+        None
+    }
+}
+
+/// Convert an expression to a `LispExpr`, whose identifiers are numbered from 0 and bound to the
+/// variables and opaque sub-expressions of the original expression. Return `None` if Herbie
+/// cannot be given the expression, e.g. if it uses an unknown function.
+pub fn from_expr<'a, S: Source>(
+    source: &S,
+    expr: &'a S::Expr
+) -> Option<(LispExpr, Bindings<'a, S::Expr>)> {
+    fn new_ident<'a, E>(expr: &'a E, bindings: &mut Bindings<'a, E>) -> LispExpr {
+        let id = bindings.idents.len() as u64;
+        bindings.idents.insert(id, expr);
+        bindings.order.push(id);
+        LispExpr::Ident(id)
+    }
+
+    fn from_expr_impl<'a, S: Source>(
+        source: &S,
+        expr: &'a S::Expr,
+        bindings: &mut Bindings<'a, S::Expr>
+    ) -> Option<LispExpr> {
+        let expr = skip_transparent(source, expr);
+
+        match source.shape(expr) {
+            Shape::Binary(op, lhs, rhs) => {
+                if let Some(lhs) = from_expr_impl(source, lhs, bindings) {
+                    if let Some(rhs) = from_expr_impl(source, rhs, bindings) {
+                        return Some(LispExpr::Binary(op, Box::new(lhs), Box::new(rhs)));
+                    }
+                }
+
+                None
+            }
+            Shape::Fun(name, params) => {
+                let mut lisp_params = Vec::new();
+                for param in params {
+                    if let Some(lisp_expr) = from_expr_impl(source, param, bindings) {
+                        lisp_params.push(lisp_expr);
+                    }
+                    else {
+                        return None;
+                    }
+                }
+                Some(LispExpr::Fun(name.into(), lisp_params))
+            }
+            Shape::Unary(op, inner) => {
+                from_expr_impl(source, inner, bindings).map(|inner| {
+                    LispExpr::Unary(op, Box::new(inner))
+                })
+            }
+            Shape::Lit(f) => {
+                bindings.lits.push((f, expr));
+                Some(LispExpr::Lit(f))
+            }
+            Shape::Const(f) => Some(LispExpr::Lit(f)),
+            Shape::Var(..) => {
+                let known = bindings.idents.iter().find(|&(_, &bound)| {
+                    same_value(source, bound, expr)
+                }).map(|(&id, _)| id);
+
+                Some(known.map_or_else(|| new_ident(expr, bindings), LispExpr::Ident))
+            }
+            Shape::Opaque => Some(new_ident(expr, bindings)),
+            Shape::Transparent(..) | Shape::Unknown => None,
+        }
+    }
+
+    let mut bindings = Bindings::new();
+    from_expr_impl(source, expr, &mut bindings).map(|lisp_expr| (lisp_expr, bindings))
+}
+
+/// Convert `rewrite` to Rust code written at `position`, in a context requiring the precedence
+/// `prec`, see `LispExpr::to_rust`. Its identifiers and literals are printed as in the source.
+/// Return `None` if it uses a function with no Rust equivalent.
+pub fn to_rust<S: Source>(
+    source: &S,
+    rewrite: &LispExpr,
+    bindings: &Bindings<S::Expr>,
+    position: Position,
+    prec: (Prec, bool)
+) -> Option<String> {
+    rewrite.to_rust(&SourceNames { source: source, bindings: bindings }, position, prec)
+}
+
+/// Convert `rewrite` to an assignment to `lhs` written at `position`, see
+/// `LispExpr::to_rust_assign`.
+pub fn to_rust_assign<S: Source>(
+    source: &S,
+    rewrite: &LispExpr,
+    bindings: &Bindings<S::Expr>,
+    lhs: &S::Expr,
+    position: Position
+) -> Option<String> {
+    let names = SourceNames { source: source, bindings: bindings };
+    rewrite.to_rust_assign(&names, &source.snippet(lhs), position)
+}
+
+/// Identifiers and literals are printed as they appear in the user's code.
+struct SourceNames<'s, 'a: 's, S: Source + 's> where S::Expr: 'a {
+    source: &'s S,
+    bindings: &'s Bindings<'a, S::Expr>,
+}
+
+impl<'s, 'a: 's, S: Source + 's> RustNames for SourceNames<'s, 'a, S> where S::Expr: 'a {
+    fn ident(&self, id: u64) -> (String, Prec) {
+        let expr = *self.bindings.idents.get(&id).expect("Got an unbinded id!");
+        let snippet = self.source.snippet(expr);
+        let prec = self.source.prec(expr).of_snippet(&snippet);
+        (snippet, prec)
+    }
+
+    fn lit(&self, f: f64) -> Option<String> {
+        self.bindings.lits.iter()
+                          .find(|&&(lit, _)| lit == f)
+                          .map(|&(_, expr)| self.source.snippet(expr))
+    }
+}
@@ -0,0 +1,32 @@
+//! Ranges of the values of variables, as given by `#[herbie_range(x = "0..1")]` attributes and
+//! assertions. The frontends find which variables they apply to.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std;
+
+/// Parse a range such as `0..1`, `..1e3` or `-1..`, an omitted bound being infinite.
+pub fn parse_range(range: &str) -> Option<(f64, f64)> {
+    let dots = match range.find("..") {
+        Some(dots) => dots,
+        None => return None,
+    };
+
+    let bound = |s: &str, infinity: f64| {
+        let s = s.trim();
+        if s.is_empty() { Some(infinity) } else { s.parse::<f64>().ok() }
+    };
+
+    match (bound(&range[..dots], std::f64::NEG_INFINITY),
+           bound(&range[dots + 2..], std::f64::INFINITY)) {
+        (Some(lo), Some(hi)) if lo <= hi => Some((lo, hi)),
+        _ => None,
+    }
+}
+
+/// Restrict the range of `var`.
+pub fn intersect<K: Eq + Hash>(ranges: &mut HashMap<K, (f64, f64)>, var: K, (lo, hi): (f64, f64)) {
+    let range = ranges.entry(var).or_insert((std::f64::NEG_INFINITY, std::f64::INFINITY));
+    range.0 = range.0.max(lo);
+    range.1 = range.1.min(hi);
+}
@@ -0,0 +1,212 @@
+//! The report of all the analysed expressions, written as JSON and HTML.
+
+use rustc_serialize::json;
+use std::fs::File;
+use std::io::{Error as IOError, Write};
+use suggestion::Location;
+
+/// What happened to an expression.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, RustcEncodable)]
+pub enum Status {
+    /// A substitution from the database matched and was reported.
+    Database,
+    /// Herbie found a better expression, which was reported.
+    Herbie,
+    /// A better expression was found, but the improvement was below `min_improvement`.
+    BelowThreshold,
+    /// A substitution from the database matched, but it does not improve the accuracy on the
+    /// known range of the inputs.
+    NotOnRange,
+    /// A better expression was found, but it is more costly than allowed by `max_cost_factor`.
+    TooCostly,
+    /// A better expression was found, but it uses a function with no Rust equivalent.
+    NoRustEquivalent,
+    /// Herbie could not improve the expression.
+    NoImprovement,
+    /// The expression is in the ignore file.
+    Ignored,
+    /// The expression contains something unknown to Herbie, e.g. a function call.
+    Unknown,
+    /// The expression is too simple to be worth calling Herbie.
+    TooShallow,
+    /// Herbie was not called because it is disabled or could not be found.
+    HerbieDisabled,
+    /// Herbie timed out.
+    Timeout,
+    /// Calling Herbie failed.
+    Error,
+}
+
+impl Status {
+    fn description(self) -> &'static str {
+        match self {
+            Status::Database => "matched the database",
+            Status::Herbie => "improved by Herbie",
+            Status::BelowThreshold => "improvement below threshold",
+            Status::NotOnRange => "no improvement on the inputs' range",
+            Status::TooCostly => "suggestion too costly",
+            Status::NoRustEquivalent => "suggestion not expressible in Rust",
+            Status::NoImprovement => "not improved by Herbie",
+            Status::Ignored => "skipped: in the ignore file",
+            Status::Unknown => "skipped: unknown to Herbie",
+            Status::TooShallow => "skipped: too shallow",
+            Status::HerbieDisabled => "skipped: Herbie not available",
+            Status::Timeout => "skipped: Herbie timed out",
+            Status::Error => "error",
+        }
+    }
+
+    fn all() -> &'static [Status] {
+        const ALL: &'static [Status] = &[
+            Status::Database,
+            Status::Herbie,
+            Status::BelowThreshold,
+            Status::NotOnRange,
+            Status::TooCostly,
+            Status::NoRustEquivalent,
+            Status::NoImprovement,
+            Status::Ignored,
+            Status::Unknown,
+            Status::TooShallow,
+            Status::HerbieDisabled,
+            Status::Timeout,
+            Status::Error,
+        ];
+        ALL
+    }
+}
+
+/// An analysed expression.
+#[derive(Debug, RustcEncodable)]
+pub struct Entry {
+    pub file_name: String,
+    /// 1-based.
+    pub line: usize,
+    /// 1-based.
+    pub column: usize,
+    pub expression: String,
+    pub status: Status,
+    pub errin: Option<f64>,
+    pub errout: Option<f64>,
+    pub suggestion: Option<String>,
+    /// Details about an error.
+    pub message: Option<String>,
+}
+
+impl Entry {
+    /// Create the entry of the expression whose code is `expression`, at `location`.
+    pub fn new(location: &Location, expression: String, status: Status) -> Entry {
+        Entry {
+            file_name: location.file_name.clone(),
+            line: location.line_start,
+            column: location.column_start,
+            expression: expression,
+            status: status,
+            errin: None,
+            errout: None,
+            suggestion: None,
+            message: None,
+        }
+    }
+}
+
+/// Write the report to `<path>.json` and `<path>.html`.
+pub fn write(path: &str, entries: &[Entry]) -> Result<(), IOError> {
+    try!(write_json(&format!("{}.json", path), entries));
+    write_html(&format!("{}.html", path), entries)
+}
+
+fn write_json(path: &str, entries: &[Entry]) -> Result<(), IOError> {
+    let mut file = try!(File::create(path));
+    let report = json::as_pretty_json(&entries);
+    writeln!(file, "{}", report)
+}
+
+fn write_html(path: &str, entries: &[Entry]) -> Result<(), IOError> {
+    fn fmt_err(err: Option<f64>) -> String {
+        err.map_or(String::new(), |err| format!("{:.1}", err))
+    }
+
+    let mut file = try!(File::create(path));
+
+    try!(file.write_all(HTML_HEADER.as_bytes()));
+
+    try!(writeln!(file, "<h2>Summary</h2>\n<table>"));
+    for &status in Status::all() {
+        let count = entries.iter().filter(|e| e.status == status).count();
+        if count != 0 {
+            try!(writeln!(
+                file,
+                "<tr class=\"{:?}\"><td>{}</td><td>{}</td></tr>",
+                status, status.description(), count
+            ));
+        }
+    }
+    try!(writeln!(file, "</table>"));
+
+    try!(writeln!(file, "<h2>Expressions</h2>\n<table>"));
+    try!(writeln!(
+        file,
+        "<tr><th>Location</th><th>Expression</th><th>Status</th>\
+         <th>Error before</th><th>Error after</th><th>Suggestion</th></tr>"
+    ));
+    for entry in entries {
+        let status = match entry.message {
+            Some(ref message) => format!("{}: {}", entry.status.description(), message),
+            None => entry.status.description().into(),
+        };
+
+        try!(writeln!(
+            file,
+            "<tr class=\"{:?}\"><td>{}:{}:{}</td><td><code>{}</code></td><td>{}</td>\
+             <td>{}</td><td>{}</td><td><code>{}</code></td></tr>",
+            entry.status,
+            escape(&entry.file_name), entry.line, entry.column,
+            escape(&entry.expression),
+            escape(&status),
+            fmt_err(entry.errin),
+            fmt_err(entry.errout),
+            entry.suggestion.as_ref().map_or(String::new(), |s| escape(s))
+        ));
+    }
+    try!(writeln!(file, "</table>"));
+
+    file.write_all(HTML_FOOTER.as_bytes())
+}
+
+fn escape(s: &str) -> String {
+    let mut buf = String::with_capacity(s.len());
+
+    for c in s.chars() {
+        match c {
+            '&' => buf.push_str("&amp;"),
+            '<' => buf.push_str("&lt;"),
+            '>' => buf.push_str("&gt;"),
+            '"' => buf.push_str("&quot;"),
+            c => buf.push(c),
+        }
+    }
+
+    buf
+}
+
+const HTML_HEADER: &'static str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Herbie-Lint report</title>
+<style>
+body { font-family: sans-serif; }
+table { border-collapse: collapse; }
+td, th { border: 1px solid #ccc; padding: 0.2em 0.5em; text-align: left; }
+code { white-space: pre; }
+.Database, .Herbie { background: #fdd; }
+.BelowThreshold, .NotOnRange, .TooCostly, .NoImprovement { background: #dfd; }
+.Timeout, .Error { background: #ffd; }
+</style>
+</head>
+<body>
+<h1>Herbie-Lint report</h1>
+"#;
+
+const HTML_FOOTER: &'static str = "</body>\n</html>\n";
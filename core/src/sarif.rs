@@ -0,0 +1,107 @@
+use rustc_serialize::json::{Json, ToJson};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{Error as IOError, Write};
+use suggestion::Suggestion;
+
+const SARIF_SCHEMA: &'static str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_VERSION: &'static str = "2.1.0";
+
+/// Build a JSON object from a list of `(key, value)`.
+fn object(fields: Vec<(&str, Json)>) -> Json {
+    let mut object = BTreeMap::new();
+
+    for (key, value) in fields {
+        object.insert(key.to_owned(), value);
+    }
+
+    Json::Object(object)
+}
+
+fn text(text: &str) -> Json {
+    object(vec![("text", text.to_json())])
+}
+
+fn artifact_location(suggestion: &Suggestion) -> Json {
+    object(vec![("uri", suggestion.file_name.to_json())])
+}
+
+fn region(suggestion: &Suggestion) -> Json {
+    object(vec![
+        ("startLine", suggestion.line_start.to_json()),
+        ("startColumn", suggestion.column_start.to_json()),
+        ("endLine", suggestion.line_end.to_json()),
+        ("endColumn", suggestion.column_end.to_json()),
+        ("byteOffset", suggestion.byte_start.to_json()),
+        ("byteLength", (suggestion.byte_end - suggestion.byte_start).to_json()),
+    ])
+}
+
+fn result(suggestion: &Suggestion) -> Json {
+    let location = object(vec![
+        ("physicalLocation", object(vec![
+            ("artifactLocation", artifact_location(suggestion)),
+            ("region", region(suggestion)),
+        ])),
+    ]);
+
+    let fix = object(vec![
+        ("description", text(&suggestion.message)),
+        ("artifactChanges", Json::Array(vec![object(vec![
+            ("artifactLocation", artifact_location(suggestion)),
+            ("replacements", Json::Array(vec![object(vec![
+                ("deletedRegion", region(suggestion)),
+                ("insertedContent", text(&suggestion.suggested_replacement)),
+            ])])),
+        ])])),
+    ]);
+
+    let properties = object(vec![
+        ("errin", suggestion.errin.to_json()),
+        ("errout", suggestion.errout.to_json()),
+        ("costin", suggestion.costin.total().to_json()),
+        ("costout", suggestion.costout.total().to_json()),
+        ("applicability", format!("{:?}", suggestion.applicability).to_json()),
+    ]);
+
+    object(vec![
+        ("ruleId", "herbie".to_json()),
+        ("level", "warning".to_json()),
+        ("message", text(&suggestion.lint_message())),
+        ("locations", Json::Array(vec![location])),
+        ("fixes", Json::Array(vec![fix])),
+        ("properties", properties),
+    ])
+}
+
+/// Build a SARIF log with one result per suggestion.
+pub fn to_sarif(suggestions: &[Suggestion]) -> Json {
+    let rule = object(vec![
+        ("id", "herbie".to_json()),
+        ("shortDescription", text("checks for numerical instability")),
+    ]);
+
+    let driver = object(vec![
+        ("name", "herbie-lint".to_json()),
+        ("version", env!("CARGO_PKG_VERSION").to_json()),
+        ("informationUri", "https://github.com/mcarton/rust-herbie-lint".to_json()),
+        ("rules", Json::Array(vec![rule])),
+    ]);
+
+    let run = object(vec![
+        ("tool", object(vec![("driver", driver)])),
+        ("results", Json::Array(suggestions.iter().map(result).collect())),
+    ]);
+
+    object(vec![
+        ("$schema", SARIF_SCHEMA.to_json()),
+        ("version", SARIF_VERSION.to_json()),
+        ("runs", Json::Array(vec![run])),
+    ])
+}
+
+/// Write the suggestions to `path` as a SARIF file.
+pub fn write(path: &str, suggestions: &[Suggestion]) -> Result<(), IOError> {
+    let mut file = try!(File::create(path));
+    writeln!(file, "{}", to_sarif(suggestions).pretty())
+}
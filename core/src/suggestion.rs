@@ -0,0 +1,239 @@
+use cost::Cost;
+use rustc_serialize::json;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Error as IOError, Read, Write};
+
+/// How confident we are that a suggestion can be applied without a human looking at it. The names
+/// are the ones used by rustfix.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, RustcEncodable)]
+pub enum Applicability {
+    /// The suggestion is an exact rewrite of the original expression.
+    MachineApplicable,
+    /// The suggestion might evaluate some sub-expressions of the original expression more or less
+    /// often, which matters if they have side effects.
+    MaybeIncorrect,
+}
+
+/// Where an expression is in the source code.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Location {
+    pub file_name: String,
+    /// Offset from the start of the file.
+    pub byte_start: usize,
+    /// Offset from the start of the file.
+    pub byte_end: usize,
+    /// 1-based.
+    pub line_start: usize,
+    /// 1-based.
+    pub line_end: usize,
+    /// 1-based.
+    pub column_start: usize,
+    /// 1-based.
+    pub column_end: usize,
+}
+
+/// A suggestion to replace an expression, which is written to the fixes file as a rustc JSON
+/// diagnostic, see `write_json`.
+#[derive(Debug)]
+pub struct Suggestion {
+    pub message: String,
+    pub file_name: String,
+    pub byte_start: usize,
+    pub byte_end: usize,
+    /// 1-based.
+    pub line_start: usize,
+    /// 1-based.
+    pub line_end: usize,
+    /// 1-based.
+    pub column_start: usize,
+    /// 1-based.
+    pub column_end: usize,
+    pub suggested_replacement: String,
+    pub applicability: Applicability,
+    /// Average bits of error of the original expression.
+    pub errin: f64,
+    /// Average bits of error of the suggestion.
+    pub errout: f64,
+    /// Operations of the original expression.
+    pub costin: Cost,
+    /// Operations of the suggestion.
+    pub costout: Cost,
+}
+
+impl Suggestion {
+    pub fn new(
+        location: Location,
+        message: &str,
+        replacement: String,
+        applicability: Applicability,
+        errin: f64,
+        errout: f64,
+        costin: Cost,
+        costout: Cost
+    ) -> Suggestion {
+        Suggestion {
+            message: message.into(),
+            file_name: location.file_name,
+            byte_start: location.byte_start,
+            byte_end: location.byte_end,
+            line_start: location.line_start,
+            line_end: location.line_end,
+            column_start: location.column_start,
+            column_end: location.column_end,
+            suggested_replacement: replacement,
+            applicability: applicability,
+            errin: errin,
+            errout: errout,
+            costin: costin,
+            costout: costout,
+        }
+    }
+
+    /// Return the message of the warning the suggestion is attached to.
+    pub fn lint_message(&self) -> String {
+        format!(
+            "Numerically unstable expression: {:.1} → {:.1} bits of error, cost {} → {} \
+             ({} → {})",
+            self.errin, self.errout,
+            self.costin.total(), self.costout.total(),
+            self.costin, self.costout
+        )
+    }
+}
+
+/// A diagnostic in the format of rustc's `--error-format=json`, which rustfix reads.
+#[derive(Debug, RustcEncodable)]
+struct Diagnostic {
+    message: String,
+    code: Option<DiagnosticCode>,
+    /// `warning` or `help`.
+    level: &'static str,
+    spans: Vec<DiagnosticSpan>,
+    children: Vec<Diagnostic>,
+    rendered: Option<String>,
+}
+
+#[derive(Debug, RustcEncodable)]
+struct DiagnosticCode {
+    code: &'static str,
+    explanation: Option<String>,
+}
+
+#[derive(Debug, RustcEncodable)]
+struct DiagnosticSpan {
+    file_name: String,
+    byte_start: usize,
+    byte_end: usize,
+    line_start: usize,
+    line_end: usize,
+    column_start: usize,
+    column_end: usize,
+    is_primary: bool,
+    /// The source lines of the span, which rustfix uses to check the span.
+    text: Vec<DiagnosticSpanLine>,
+    label: Option<String>,
+    suggested_replacement: Option<String>,
+    suggestion_applicability: Option<Applicability>,
+    expansion: Option<String>,
+}
+
+#[derive(Debug, RustcEncodable)]
+struct DiagnosticSpanLine {
+    text: String,
+    /// 1-based, in characters.
+    highlight_start: usize,
+    /// 1-based, in characters, exclusive.
+    highlight_end: usize,
+}
+
+impl Diagnostic {
+    /// Create the diagnostic of a suggestion. `source` is the content of its file, if it could be
+    /// read.
+    fn new(suggestion: &Suggestion, source: Option<&str>) -> Diagnostic {
+        let span = |replacement: Option<String>, applicability: Option<Applicability>| {
+            DiagnosticSpan {
+                file_name: suggestion.file_name.clone(),
+                byte_start: suggestion.byte_start,
+                byte_end: suggestion.byte_end,
+                line_start: suggestion.line_start,
+                line_end: suggestion.line_end,
+                column_start: suggestion.column_start,
+                column_end: suggestion.column_end,
+                is_primary: true,
+                text: source.map_or_else(Vec::new, |source| span_lines(suggestion, source)),
+                label: None,
+                suggested_replacement: replacement,
+                suggestion_applicability: applicability,
+                expansion: None,
+            }
+        };
+
+        let help = Diagnostic {
+            message: suggestion.message.clone(),
+            code: None,
+            level: "help",
+            spans: vec![span(
+                Some(suggestion.suggested_replacement.clone()), Some(suggestion.applicability)
+            )],
+            children: Vec::new(),
+            rendered: None,
+        };
+
+        Diagnostic {
+            message: suggestion.lint_message(),
+            code: Some(DiagnosticCode { code: "herbie", explanation: None }),
+            level: "warning",
+            spans: vec![span(None, None)],
+            children: vec![help],
+            rendered: None,
+        }
+    }
+}
+
+/// Return the lines of `source` covered by a suggestion, with the highlighted columns.
+fn span_lines(suggestion: &Suggestion, source: &str) -> Vec<DiagnosticSpanLine> {
+    let lines = source.lines().skip(suggestion.line_start - 1);
+    lines.take(suggestion.line_end + 1 - suggestion.line_start).enumerate().map(|(i, line)| {
+        let line_number = suggestion.line_start + i;
+        DiagnosticSpanLine {
+            text: line.into(),
+            highlight_start: if line_number == suggestion.line_start {
+                suggestion.column_start
+            }
+            else {
+                1
+            },
+            highlight_end: if line_number == suggestion.line_end {
+                suggestion.column_end
+            }
+            else {
+                line.chars().count() + 1
+            },
+        }
+    }).collect()
+}
+
+/// Write suggestions to `path` as rustc JSON diagnostics, one per line, so that they can be
+/// applied by rustfix: a `herbie` warning on the expression, with a `help` child whose span has
+/// the replacement and its applicability.
+pub fn write_json(path: &str, suggestions: &[Suggestion]) -> Result<(), IOError> {
+    let mut file = try!(File::create(path));
+    let mut sources = HashMap::new();
+
+    for suggestion in suggestions {
+        let source = sources.entry(suggestion.file_name.clone()).or_insert_with(|| {
+            let mut source = String::new();
+            File::open(&suggestion.file_name)
+                .and_then(|mut file| file.read_to_string(&mut source))
+                .ok()
+                .map(|_| source)
+        });
+
+        let diagnostic = Diagnostic::new(suggestion, source.as_ref().map(|source| &source[..]));
+        let line = json::encode(&diagnostic).expect("Diagnostics can always be encoded");
+        try!(writeln!(file, "{}", line));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,232 @@
+extern crate herbie_lint_core;
+
+use herbie_lint_core::conf::{Conf, UseHerbieConf, find_conf, find_conf_with_env};
+use std::collections::HashMap;
+use std::env::temp_dir;
+use std::fs::{File, create_dir_all, remove_dir_all};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Create a directory tree with the given files in a temporary directory.
+fn tree(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let root = temp_dir().join(name);
+    let _ = remove_dir_all(&root);
+
+    for &(path, content) in files {
+        let path = root.join(path);
+        create_dir_all(path.parent().unwrap()).unwrap();
+        File::create(path).unwrap().write_all(content.as_bytes()).unwrap();
+    }
+
+    root
+}
+
+fn db_path(conf: &Conf) -> PathBuf {
+    PathBuf::from(&conf.databases[0].path)
+}
+
+const PACKAGE: &'static str = "[package]\nname = \"foo\"\nversion = \"0.1.0\"\n";
+
+#[test]
+fn test_find_conf_in_workspace() {
+    let root = tree("herbie-lint-test-workspace", &[
+        ("Cargo.toml", "[workspace]\nmembers = [\"foo\", \"bar\"]\n"),
+        ("Herbie.toml", "db_path = \"db/Herbie.db\"\nmin_improvement = 2.0\n"),
+        ("foo/Cargo.toml", PACKAGE),
+        ("bar/Cargo.toml", "[package.metadata.herbie]\nmin_improvement = 3.0\n"),
+        ("baz/Cargo.toml", PACKAGE),
+        ("baz/Herbie.toml", "min_improvement = 4.0\n"),
+    ]);
+
+    let conf = find_conf(&root.join("foo")).unwrap();
+    assert_eq!(db_path(&conf), root.join("db/Herbie.db"));
+    assert_eq!(conf.min_improvement, 2.);
+
+    let conf = find_conf(&root.join("bar")).unwrap();
+    assert_eq!(db_path(&conf), root.join("bar/Herbie.db"));
+    assert_eq!(conf.min_improvement, 3.);
+
+    let conf = find_conf(&root.join("baz")).unwrap();
+    assert_eq!(db_path(&conf), root.join("baz/Herbie.db"));
+    assert_eq!(conf.min_improvement, 4.);
+
+    remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn test_find_conf_outside_workspace() {
+    // without a workspace, the configuration of the parent directory is not used
+    let root = tree("herbie-lint-test-no-workspace", &[
+        ("Herbie.toml", "min_improvement = 2.0\n"),
+        ("foo/Cargo.toml", PACKAGE),
+    ]);
+
+    let conf = find_conf(&root.join("foo")).unwrap();
+    assert_eq!(db_path(&conf), root.join("foo/Herbie.db"));
+    assert_eq!(conf.min_improvement, 0.);
+
+    remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn test_find_conf_errors() {
+    let root = tree("herbie-lint-test-conf-errors", &[
+        ("foo/Cargo.toml", "[package.metadata.herbie]\nmin_improvement = \"a lot\"\n"),
+        ("bar/Herbie.toml", "timeout = 60\nfoobar"),
+        ("baz/Herbie.toml", "herbie_seed = \"#(1 2 3 0 0 0)\"\n"),
+        ("qux/Herbie.toml", "min_improvement = -1.0\n"),
+        ("quux/Herbie.toml", "max_cost_factor = 0.0\n"),
+    ]);
+
+    assert_eq!(
+        find_conf(&root.join("foo")).unwrap_err().to_string(),
+        "Invalid value in [package.metadata.herbie] of Cargo.toml at line 2: expected a value of \
+         type `float`, but found a value of type `string` for the key `min_improvement`"
+    );
+    assert!(
+        find_conf(&root.join("bar")).unwrap_err().to_string()
+            .starts_with("Syntax error in Herbie.toml at line 2, column ")
+    );
+    assert_eq!(
+        find_conf(&root.join("baz")).unwrap_err().to_string(),
+        "Invalid configuration: invalid `herbie_seed` `#(1 2 3 0 0 0)`: the first three and the \
+         last three integers cannot all be 0"
+    );
+    assert_eq!(
+        find_conf(&root.join("qux")).unwrap_err().to_string(),
+        "Invalid configuration: `min_improvement` must be a non-negative number of bits"
+    );
+    assert_eq!(
+        find_conf(&root.join("quux")).unwrap_err().to_string(),
+        "Invalid configuration: `max_cost_factor` must be a positive number"
+    );
+
+    remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn test_unknown_keys() {
+    let root = tree("herbie-lint-test-unknown-keys", &[
+        ("Herbie.toml", "use_herbe = false\n\
+                         timeout = 60\n\
+                         \n\
+                         [[databases]]\n\
+                         path = \"Herbie.db\"\n\
+                         writeable = true\n\
+                         \n\
+                         [frobnicate]\n\
+                         level = 3\n"),
+    ]);
+
+    let conf = find_conf(&root).unwrap();
+    assert_eq!(conf.timeout, Some(60));
+    assert_eq!(conf.warnings, vec![
+        "unknown key `databases.writeable` in Herbie.toml at line 6, did you mean `writable`?",
+        "unknown key `frobnicate.level` in Herbie.toml at line 9",
+        "unknown key `use_herbe` in Herbie.toml at line 1, did you mean `use_herbie`?",
+    ]);
+
+    remove_dir_all(root).unwrap();
+}
+
+/// Read the configuration of `dir` with the given environment.
+fn conf_with_env(dir: &PathBuf, vars: &[(&str, &str)]) -> Result<Conf, String> {
+    let vars: HashMap<_, _> = vars.iter().cloned().collect();
+    find_conf_with_env(dir, |name| vars.get(name).map(|&value| value.to_owned()))
+        .map_err(|err| err.to_string())
+}
+
+#[test]
+fn test_env_overrides() {
+    let root = tree("herbie-lint-test-env", &[
+        ("Herbie.toml", "db_path = \"db/Herbie.db\"\n\
+                         fixes_path = \"fixes.json\"\n\
+                         timeout = 60\n\
+                         use_herbie = true\n"),
+    ]);
+
+    // the configuration file is used without environment variables
+    let conf = conf_with_env(&root, &[]).unwrap();
+    assert_eq!(db_path(&conf), root.join("db/Herbie.db"));
+    assert_eq!(conf.fixes_path, Some(root.join("fixes.json").to_string_lossy().into_owned()));
+    assert_eq!(conf.ignore_path, root.join("Herbie.ignore").to_string_lossy());
+    assert_eq!(conf.timeout, Some(60));
+    assert_eq!(conf.use_herbie, UseHerbieConf::Yes);
+
+    // environment variables take precedence, relative paths are relative to the configuration
+    let conf = conf_with_env(&root, &[
+        ("HERBIE_LINT_DB", "other.db"),
+        ("HERBIE_LINT_FMA", "1"),
+        ("HERBIE_LINT_HERBIE_SEED", "#(1 2 3 4 5 6)"),
+        ("HERBIE_LINT_IGNORE_PATH", "/tmp/Herbie.ignore"),
+        ("HERBIE_LINT_MAX_COST_FACTOR", "2"),
+        ("HERBIE_LINT_MIN_IMPROVEMENT", "1.5"),
+        ("HERBIE_LINT_REPORT_PATH", "/tmp/report"),
+        ("HERBIE_LINT_SARIF_PATH", "herbie.sarif"),
+        ("HERBIE_LINT_TIMEOUT", "30"),
+        ("HERBIE_LINT_USE_HERBIE", "0"),
+        ("HERBIE_LINT_VERIFY_DB", "1"),
+    ]).unwrap();
+    assert_eq!(db_path(&conf), root.join("other.db"));
+    assert!(conf.databases[0].writable);
+    assert!(conf.fma);
+    assert_eq!(conf.herbie_seed, "#(1 2 3 4 5 6)");
+    assert_eq!(conf.ignore_path, "/tmp/Herbie.ignore");
+    assert_eq!(conf.max_cost_factor, Some(2.));
+    assert_eq!(conf.min_improvement, 1.5);
+    assert_eq!(conf.report_path, Some("/tmp/report".into()));
+    assert_eq!(
+        conf.sarif_path,
+        Some(root.join("herbie.sarif").to_string_lossy().into_owned())
+    );
+    assert_eq!(conf.timeout, Some(30));
+    assert_eq!(conf.use_herbie, UseHerbieConf::No);
+    assert!(conf.verify_db);
+
+    // empty variables reset the options to their defaults
+    let conf = conf_with_env(&root, &[
+        ("HERBIE_LINT_DB", ""),
+        ("HERBIE_LINT_FIXES_PATH", ""),
+        ("HERBIE_LINT_TIMEOUT", ""),
+        ("HERBIE_LINT_USE_HERBIE", ""),
+    ]).unwrap();
+    assert_eq!(db_path(&conf), root.join("Herbie.db"));
+    assert_eq!(conf.fixes_path, None);
+    assert_eq!(conf.timeout, Some(120));
+    assert_eq!(conf.use_herbie, UseHerbieConf::Default);
+
+    assert_eq!(
+        conf_with_env(&root, &[("HERBIE_LINT_TIMEOUT", "soon")]).unwrap_err(),
+        "Invalid configuration: HERBIE_LINT_TIMEOUT: expected a number of seconds, got `soon`"
+    );
+    assert_eq!(
+        conf_with_env(&root, &[("HERBIE_LINT_USE_HERBIE", "maybe")]).unwrap_err(),
+        "Invalid configuration: HERBIE_LINT_USE_HERBIE: expected 1, true, yes, 0, false or no, \
+         got `maybe`"
+    );
+
+    remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn test_env_paths() {
+    let root = tree("herbie-lint-test-env-paths", &[
+        ("Cargo.toml", "[workspace]\nmembers = [\"foo\"]\n"),
+        ("Herbie.toml", "timeout = 60\n"),
+        ("foo/Cargo.toml", PACKAGE),
+    ]);
+
+    // relative paths are relative to the configuration found for the crate, not to the directory
+    // cargo runs in
+    let conf = conf_with_env(&root.join("foo"), &[("HERBIE_LINT_DB", "other.db")]).unwrap();
+    assert_eq!(db_path(&conf), root.join("other.db"));
+
+    remove_dir_all(root).unwrap();
+
+    // or to the crate itself without configuration
+    let root = tree("herbie-lint-test-env-paths-crate", &[("Cargo.toml", PACKAGE)]);
+    let conf = conf_with_env(&root, &[("HERBIE_LINT_SARIF_PATH", "herbie.sarif")]).unwrap();
+    assert_eq!(conf.sarif_path, Some(root.join("herbie.sarif").to_string_lossy().into_owned()));
+
+    remove_dir_all(root).unwrap();
+}
@@ -0,0 +1,32 @@
+extern crate herbie_lint_core;
+
+use herbie_lint_core::cost::Cost;
+use herbie_lint_core::lisp::Parser;
+
+fn cost(s: &str) -> Cost {
+    Cost::of_expr(&Parser::new().parse(s).unwrap())
+}
+
+#[test]
+fn test_cost() {
+    let naive = cost("(sqrt (+ (* a a) (* b b)))");
+    assert_eq!(
+        naive,
+        Cost { additions: 1, multiplications: 2, square_roots: 1, ..Cost::default() }
+    );
+    assert_eq!(naive.total(), 7);
+    assert_eq!(naive.to_string(), "1 addition, 2 multiplications, 1 square root");
+
+    let hypot = cost("(hypot a b)");
+    assert_eq!(hypot.total(), 20);
+    assert_eq!(hypot.to_string(), "1 function call");
+
+    let mixed = cost("(- (/ (abs a) b) (- (exp a)))");
+    assert_eq!(mixed, Cost { additions: 3, divisions: 1, calls: 1, ..Cost::default() });
+    assert_eq!(mixed.to_string(), "3 additions, 1 division, 1 function call");
+
+    let fma = cost("(fma a b (sqrt c))");
+    assert_eq!(fma, Cost { additions: 1, multiplications: 1, square_roots: 1, ..Cost::default() });
+
+    assert_eq!(Cost::default().to_string(), "no operation");
+}
@@ -0,0 +1,89 @@
+extern crate herbie_lint_core;
+
+use herbie_lint_core::db;
+
+const DB_PATH: &'static str = "../tests/compile-fail/general/Herbie.db";
+
+#[test]
+fn test_read_rows() {
+    let connection = db::open(DB_PATH, false).unwrap();
+    let rows = db::read_rows(&connection).unwrap();
+
+    assert_eq!(rows.len(), 119);
+
+    assert_eq!(rows[0].id, 1);
+    assert_eq!(rows[0].cmdin, "(* (* herbie0 herbie0) herbie1)");
+    assert_eq!(rows[0].errin, None);
+    assert_eq!(rows[0].errout, None);
+
+    assert_eq!(rows[1].id, 2);
+    assert_eq!(rows[1].cmdin, "(sqrt (+ (* herbie0 herbie0) (* herbie1 herbie1)))");
+    assert_eq!(rows[1].cmdout, "(hypot herbie0 herbie1)");
+    assert_eq!(rows[1].errin, Some(29.88639024012792));
+    assert_eq!(rows[1].errout, Some(0.015625));
+}
+
+#[test]
+fn test_function_names() {
+    let connection = db::open(DB_PATH, false).unwrap();
+
+    assert_eq!(db::function_names(&connection, 1).unwrap(), vec!["test4"]);
+    assert!(db::function_names(&connection, 0).unwrap().is_empty());
+}
+
+fn row(cmdin: &str, cmdout: &str, errin: f64, errout: f64) -> db::Row {
+    db::Row {
+        id: 0,
+        cmdin: cmdin.into(),
+        cmdout: cmdout.into(),
+        opts: "".into(),
+        errin: Some(errin),
+        errout: Some(errout),
+    }
+}
+
+#[test]
+fn test_merge() {
+    let path = std::env::temp_dir().join("herbie-lint-test-merge.db");
+    let _ = std::fs::remove_file(&path);
+    let connection = db::create(path.to_str().unwrap()).unwrap();
+
+    let report = db::merge(&connection, &[
+        row("(- (sqrt (+ herbie0 1)) (sqrt herbie0))",
+            "(/ 1 (+ (sqrt (+ herbie0 1)) (sqrt herbie0)))", 29.5, 0.2),
+        row("(sqrt (+ (* herbie0 herbie0) (* herbie1 herbie1)))",
+            "(hypot herbie0 herbie1)", 29.9, 0.1),
+    ]).unwrap();
+    assert_eq!(report, db::MergeReport { added: 2, .. Default::default() });
+
+    let report = db::merge(&connection, &[
+        // same expression, with other variables
+        row("(- (sqrt (+ herbie3 1)) (sqrt herbie3))",
+            "(/ 1 (+ (sqrt (+ herbie3 1)) (sqrt herbie3)))", 29.5, 0.2),
+        // better result
+        row("(sqrt (+ (* herbie1 herbie1) (* herbie0 herbie0)))",
+            "(hypot herbie1 herbie0)", 29.9, 0.),
+        // worse, conflicting result
+        row("(- (sqrt (+ herbie0 1)) (sqrt herbie0))", "(sqrt herbie0)", 29.5, 10.),
+        row("(+ herbie0", "herbie0", 1., 0.),
+        // same expression, with the operands of `+` and `*` swapped
+        row("(- (sqrt (+ 1 herbie4)) (sqrt herbie4))",
+            "(/ 1 (+ (sqrt herbie4) (sqrt (+ 1 herbie4))))", 29.5, 0.2),
+    ]).unwrap();
+    assert_eq!(report.added, 0);
+    assert_eq!(report.replaced, 1);
+    assert_eq!(report.kept, 3);
+    assert_eq!(report.conflicts.len(), 1);
+    assert!(!report.conflicts[0].replaced);
+    assert_eq!(report.conflicts[0].new.cmdout, "(sqrt herbie0)");
+    assert_eq!(report.invalid.len(), 1);
+
+    let rows = db::read_rows(&connection).unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].cmdout, "(/ 1 (+ (sqrt (+ herbie0 1)) (sqrt herbie0)))");
+    assert_eq!(rows[1].cmdin, "(sqrt (+ (* herbie0 herbie0) (* herbie1 herbie1)))");
+    assert_eq!(rows[1].cmdout, "(hypot herbie0 herbie1)");
+    assert_eq!(rows[1].errout, Some(0.));
+
+    let _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,146 @@
+extern crate herbie_lint_core;
+
+use herbie_lint_core::bigfloat::{Context, f64_from_bits, f64_to_bits};
+use herbie_lint_core::estimate::{
+    DEFAULT_POINTS, EstimateError, Estimator, Rejection, bits_of_error
+};
+use herbie_lint_core::lisp::{LispExpr, Parser};
+use std::collections::HashMap;
+
+fn parse(s: &str) -> LispExpr {
+    Parser::new().parse(s).unwrap()
+}
+
+#[test]
+fn test_f64_bits() {
+    for &x in &[0., -0., 1., -2.5, 1e300, 2.2250738585072014e-308, 5e-324, std::f64::MAX] {
+        assert_eq!(f64_from_bits(f64_to_bits(x)), x);
+    }
+
+    assert_eq!(f64_to_bits(1.), 0x3ff0_0000_0000_0000);
+    assert_eq!(f64_to_bits(-2.), 0xc000_0000_0000_0000);
+    assert_eq!(f64_to_bits(5e-324), 1);
+    assert_eq!(f64_from_bits(0x7ff0_0000_0000_0000), std::f64::INFINITY);
+    assert!(f64_from_bits(0x7ff8_0000_0000_0000).is_nan());
+}
+
+#[test]
+fn test_bigfloat() {
+    let context = Context::new(256);
+
+    assert_eq!(context.pi().to_f64(), std::f64::consts::PI);
+
+    for &x in &[1., -2.5, 0.1, 1e300, 5e-324, 2.2250738585072014e-308] {
+        assert_eq!(context.from_f64(x).to_f64(), x);
+    }
+
+    let two = context.from_f64(2.);
+    assert_eq!(context.sqrt(&context.from_f64(4.)).to_f64(), 2.);
+    assert_eq!(context.mul(&context.sqrt(&two), &context.sqrt(&two)).to_f64(), 2.);
+    assert_eq!(context.exp(&context.ln(&context.from_f64(3.))).to_f64(), 3.);
+    assert_eq!(context.powf(&context.from_f64(-2.), &context.from_f64(3.)).to_f64(), -8.);
+    assert!(context.powf(&context.from_f64(-2.), &context.from_f64(0.5)).is_nan());
+    assert!(context.sqrt(&context.from_f64(-1.)).is_nan());
+    assert_eq!(context.div(&context.one(), &context.from_f64(0.)).to_f64(), std::f64::INFINITY);
+
+    // the result is exact with enough precision
+    let x = context.add(&context.from_f64(1e30), &context.one());
+    assert_eq!(context.sub(&x, &context.from_f64(1e30)).to_f64(), 1.);
+
+    // large arguments of trigonometric functions are reduced exactly
+    assert_eq!(context.sin(&context.from_f64(1e300)).to_f64(), -0.8178819121159085);
+}
+
+#[test]
+fn test_bits_of_error() {
+    assert_eq!(bits_of_error(1., 1.), 0.);
+    assert_eq!(bits_of_error(0., -0.), 0.);
+    assert_eq!(bits_of_error(1., f64_from_bits(f64_to_bits(1.) + 1)), 1.);
+    assert_eq!(bits_of_error(5e-324, -5e-324), 3f64.log2());
+    assert_eq!(bits_of_error(std::f64::NAN, 1.), 64.);
+    assert_eq!(bits_of_error(std::f64::NAN, std::f64::NAN), 0.);
+}
+
+#[test]
+fn test_estimate() {
+    let estimator = Estimator::new(DEFAULT_POINTS, 1);
+
+    assert_eq!(estimator.error(&parse("(+ a b)")), Ok(0.));
+    assert_eq!(estimator.error(&parse("(* a 2)")), Ok(0.));
+
+    let errors = estimator.errors(&[
+        &parse("(sqrt (+ (* a a) (* b b)))"),
+        &parse("(hypot a b)"),
+    ]).unwrap();
+    assert!(errors[0] > 20., "{:?}", errors);
+    assert!(errors[1] < 0.1, "{:?}", errors);
+
+    let errors = estimator.errors(&[&parse("(- (exp a) 1)"), &parse("(expm1 a)")]).unwrap();
+    assert!(errors[0] > 5., "{:?}", errors);
+    assert!(errors[1] < 0.1, "{:?}", errors);
+
+    // `mul_add` rounds once
+    let errors = estimator.errors(&[&parse("(+ (* a b) c)"), &parse("(fma a b c)")]).unwrap();
+    assert!(errors[0] > errors[1], "{:?}", errors);
+    assert_eq!(errors[1], 0.);
+
+    assert_eq!(
+        estimator.error(&LispExpr::Fun("cot".into(), vec![LispExpr::Ident(0)])),
+        Err(EstimateError::Unsupported("cot".into()))
+    );
+}
+
+#[test]
+fn test_estimate_in_ranges() {
+    let estimator = Estimator::new(DEFAULT_POINTS, 1);
+
+    let exp_m1 = parse("(- (exp a) 1)");
+    // better on average, but not for large arguments
+    let halves = parse("(* (expm1 (/ a 2)) (+ (exp (/ a 2)) 1))");
+
+    let mut ranges = HashMap::new();
+    ranges.insert(0, (1., 2.));
+    let errors = estimator.errors_in_ranges(&[&exp_m1, &halves], &ranges).unwrap();
+    assert!(errors[0] < errors[1], "{:?}", errors);
+
+    ranges.insert(0, (-1e-10, 1e-10));
+    let errors = estimator.errors_in_ranges(&[&exp_m1, &halves], &ranges).unwrap();
+    assert!(errors[0] > 30., "{:?}", errors);
+    assert!(errors[1] < 1., "{:?}", errors);
+
+    // infinite bounds are the largest doubles
+    ranges.insert(0, (std::f64::NEG_INFINITY, std::f64::INFINITY));
+    let errors = estimator.errors_in_ranges(&[&exp_m1, &halves], &ranges).unwrap();
+    assert!(errors[0] > 30., "{:?}", errors);
+    assert!(errors[1] < 1., "{:?}", errors);
+}
+
+#[test]
+fn test_verify() {
+    let estimator = Estimator::new(DEFAULT_POINTS, 1);
+
+    let (errin, errout) = estimator.verify(&parse("(- (exp a) 1)"), &parse("(expm1 a)")).unwrap();
+    assert!(errin > 30., "{}", errin);
+    assert!(errout < 0.1, "{}", errout);
+    assert!(estimator.verify(&parse("(log (+ 1 a))"), &parse("(log1p a)")).is_ok());
+
+    match estimator.verify(&parse("(- (exp a) 1)"), &parse("(expm1 (* 2 a))")) {
+        Err(Rejection::NotEquivalent(difference)) => assert!(difference > 10., "{}", difference),
+        result => panic!("{:?}", result),
+    }
+
+    // both expressions must be parsed together for their identifiers to be numbered the same
+    let mut parser = Parser::new();
+    let lhs = parser.parse("(+ (* a a) b)").unwrap();
+    let rhs = parser.parse("(+ b (* a a))").unwrap();
+    match estimator.verify(&lhs, &rhs) {
+        Err(Rejection::NotMoreAccurate(errin, errout)) => assert_eq!(errin, errout),
+        result => panic!("{:?}", result),
+    }
+
+    assert_eq!(
+        estimator.verify(&LispExpr::Fun("cot".into(), vec![LispExpr::Ident(0)]),
+                         &parse("(/ 1 (tan a))")),
+        Err(Rejection::Error(EstimateError::Unsupported("cot".into())))
+    );
+}
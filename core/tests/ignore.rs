@@ -0,0 +1,40 @@
+extern crate herbie_lint_core;
+
+use herbie_lint_core::ignore::Ignores;
+use std::env::temp_dir;
+use std::fs::{File, create_dir_all, remove_dir_all};
+use std::io::{Read, Write};
+
+#[test]
+fn test_ignores() {
+    let root = temp_dir().join("herbie-lint-test-ignore");
+    let _ = remove_dir_all(&root);
+    create_dir_all(&root).unwrap();
+    let path = root.join("Herbie.ignore");
+    let lib = root.join("src/lib.rs");
+    let lib = lib.to_str().unwrap();
+
+    // a missing file has no entry, and is created with the first one
+    let mut ignores = Ignores::read(&path).unwrap();
+    assert!(!ignores.contains(lib, "(a/b + c) * b"));
+    ignores.add(lib, "(a/b +\n     c) * b").unwrap();
+    assert!(ignores.contains(lib, "(a/b + c)  *  b"));
+    assert!(!ignores.contains(lib, "(a/b + c)*b"));
+    assert!(!ignores.contains(root.join("src/main.rs").to_str().unwrap(), "(a/b + c) * b"));
+
+    // files are recorded relative to the directory of the ignore file
+    let mut content = String::new();
+    File::open(&path).unwrap().read_to_string(&mut content).unwrap();
+    assert!(content.ends_with("\nsrc/lib.rs: (a/b + c) * b\n"));
+
+    File::create(&path).unwrap().write_all(b"# comment\n\nsrc/lib.rs: a*a + b*b\n").unwrap();
+    let ignores = Ignores::read(&path).unwrap();
+    assert!(ignores.contains(lib, "a*a + b*b"));
+    assert!(!ignores.contains(lib, "(a/b + c) * b"));
+
+    File::create(&path).unwrap().write_all(b"src/lib.rs a*a\n").unwrap();
+    let err = Ignores::read(&path).unwrap_err().to_string();
+    assert!(err.ends_with("Herbie.ignore: line 1: expected `file: expression`"), "{}", err);
+
+    remove_dir_all(root).unwrap();
+}
@@ -0,0 +1,194 @@
+extern crate herbie_lint_core;
+
+use herbie_lint_core::lisp::{BinOp, LispExpr, Parser, ParseError, UnOp};
+use herbie_lint_core::lisp::LispExpr::*;
+
+pub fn parse(s: &str) -> Result<LispExpr, ParseError> {
+    let mut parser = Parser::new();
+    parser.parse(s)
+}
+
+#[test]
+fn test_parser() {
+    assert_eq!(parse(""), Err(ParseError::EOE));
+
+    assert_eq!(parse("0."), Ok(Lit(0.)));
+    assert_eq!(parse("herbie"), Ok(Ident(0)));
+    assert_eq!(
+        parse("(+ 0. herbie1)"),
+        Ok(Binary(BinOp::Add, Box::new(Lit(0.)), Box::new(Ident(0))))
+    );
+
+    let valid = &[
+        ("0", Lit(0.)),
+        ("herbie0", Ident(0)),
+        ("(+ herbie0 herbie1)", Binary(BinOp::Add, Box::new(Ident(0)), Box::new(Ident(1)))),
+        ("(- herbie0)", Unary(UnOp::Neg, Box::new(Ident(0)))),
+        ("(- herbie0 herbie1)", Binary(BinOp::Sub, Box::new(Ident(0)), Box::new(Ident(1)))),
+        ("(cos 1)", Fun("cos".into(), vec![Lit(1.)])),
+        ("(cos herbie0)", Fun("cos".into(), vec![Ident(0)])),
+        ("(log1p (cos herbie0))", Fun("log1p".into(), vec![Fun("cos".into(), vec![Ident(0)])])),
+        ("(fma herbie0 herbie1 1)", Fun("fma".into(), vec![Ident(0), Ident(1), Lit(1.)])),
+    ];
+
+    for &(s, ref e) in valid {
+        assert_eq!(parse(s).as_ref(), Ok(e));
+        assert_eq!(s, e.to_lisp("herbie"));
+    }
+
+
+    assert_eq!(parse("(+ 0. 0.) foobar"), Err(ParseError::EOE));
+    assert_eq!(parse("("), Err(ParseError::EOE));
+    assert_eq!(parse("0.eee"), Err(ParseError::Float));
+    assert_eq!(parse("1/0"), Err(ParseError::Float));
+
+
+    assert_eq!(parse("+"), Err(ParseError::Unexpected('+')));
+    assert_eq!(parse("(+)"), Err(ParseError::Unexpected(')')));
+    assert_eq!(parse("(+ 0.)"), Err(ParseError::Arity));
+    assert_eq!(parse("(+ 0. 0."), Err(ParseError::Expected(')')));
+    assert_eq!(parse("(+ 0. 0. 0.)"), Err(ParseError::Expected(')')));
+    assert_eq!(parse("(+ 0. 0. 0."), Err(ParseError::Expected(')')));
+
+    assert_eq!(parse("-"), Err(ParseError::Unexpected('-')));
+    assert_eq!(parse("(-)"), Err(ParseError::Unexpected(')')));
+    assert_eq!(parse("(- 0. 0. 0.)"), Err(ParseError::Expected(')')));
+
+
+    assert_eq!(parse("(cos)"), Err(ParseError::Arity));
+    assert_eq!(parse("(cos"), Err(ParseError::Expected(')')));
+    assert_eq!(parse("(cos 1. 2.)"), Err(ParseError::Arity));
+    assert_eq!(parse("(cos 1. 2. 3."), Err(ParseError::Expected(')')));
+
+    assert_eq!(parse("(foo 1.)"), Err(ParseError::Ident));
+    assert_eq!(parse("(foocos 1.)"), Err(ParseError::Ident));
+    assert_eq!(parse("(cosfoocos 1.)"), Err(ParseError::Ident));
+    assert_eq!(parse("(fma 1. 2.)"), Err(ParseError::Arity));
+
+    assert_eq!(parse("1/2"), Ok(Lit(0.5)));
+    assert_eq!(parse("-1/8"), Ok(Lit(-0.125)));
+    assert_eq!(parse("1/3"), Ok(Lit(1./3.)));
+    assert_eq!(
+        parse("(* 1/3 herbie0)"),
+        Ok(Binary(BinOp::Mul, Box::new(Lit(1./3.)), Box::new(Ident(0))))
+    );
+    assert_eq!(
+        parse("(+ herbie0 -1/8)"),
+        Ok(Binary(BinOp::Add, Box::new(Ident(0)), Box::new(Lit(-0.125))))
+    );
+    assert_eq!(parse("-3.5e-300"), Ok(Lit(-3.5e-300)));
+
+    let mut parser = Parser::new();
+    assert_eq!(parser.parse("(* (+ (/ herbie0 herbie1) herbie2) herbie1)"), Ok(
+        Binary(BinOp::Mul,
+            Box::new(Binary(BinOp::Add,
+                Box::new(Binary(BinOp::Div, Box::new(Ident(0)), Box::new(Ident(1)))),
+                Box::new(Ident(2))
+            )),
+            Box::new(Ident(1))
+        )
+    ));
+
+    assert_eq!(parser.parse("(+ (* herbie2 herbie1) herbie0)"), Ok(
+        Binary(BinOp::Add,
+            Box::new(Binary(BinOp::Mul, Box::new(Ident(2)), Box::new(Ident(1)))),
+            Box::new(Ident(0))
+        )
+    ));
+
+    assert_eq!(parse("(+ (* herbie2 herbie1) herbie0)"), Ok(
+        Binary(BinOp::Add,
+            Box::new(Binary(BinOp::Mul, Box::new(Ident(0)), Box::new(Ident(1)))),
+            Box::new(Ident(2))
+        )
+    ));
+}
+
+#[test]
+fn test_lit_to_lisp() {
+    let lits = &[
+        (0., "0"),
+        (1., "1"),
+        (-3., "-3"),
+        (0.1, "0.1"),
+        (1.7634409685515176, "1.7634409685515176"),
+        (1e-300, "1e-300"),
+        (1.5e200, "1.5e200"),
+        (1./3., "0.3333333333333333"),
+    ];
+
+    for &(f, s) in lits {
+        assert_eq!(Lit(f).to_lisp("herbie"), s);
+        assert_eq!(parse(s), Ok(Lit(f)));
+    }
+}
+
+#[test]
+fn test_to_rust_placeholder() {
+    let exprs = &[
+        ("(+ herbie0 herbie1)", "herbie0 + herbie1"),
+        ("(- herbie0 (- herbie1 herbie2))", "herbie0 - (herbie1 - herbie2)"),
+        ("(* (+ herbie0 1) herbie1)", "(herbie0 + 1.0) * herbie1"),
+        ("(sqrt (+ herbie0 1))", "(herbie0 + 1.0).sqrt()"),
+        ("(log1p 2)", "2.0f64.ln_1p()"),
+        ("(- (- herbie0))", "-(-herbie0)"),
+        ("(fma herbie0 herbie1 herbie2)", "herbie0.mul_add(herbie1, herbie2)"),
+        ("(fma (+ herbie0 1) 2 herbie1)", "(herbie0 + 1.0).mul_add(2.0, herbie1)"),
+    ];
+
+    for &(lisp, rust) in exprs {
+        assert_eq!(parse(lisp).unwrap().to_rust_placeholder("herbie").unwrap(), rust);
+    }
+
+    // Herbie functions without a Rust equivalent cannot be printed
+    let cot = Fun("cot".into(), vec![Binary(BinOp::Add, Box::new(Ident(0)), Box::new(Lit(1.)))]);
+    assert_eq!(cot.to_rust_placeholder("herbie"), None);
+    let product = Binary(BinOp::Mul, Box::new(cot), Box::new(Ident(1)));
+    assert_eq!(product.to_rust_placeholder("x"), None);
+}
+
+#[test]
+fn test_functions() {
+    let expr = parse("(+ (sqrt herbie0) (log1p (sqrt (exp herbie1))))").unwrap();
+    assert_eq!(expr.functions(), vec!["sqrt", "log1p", "exp"]);
+    assert!(parse("(+ herbie0 1)").unwrap().functions().is_empty());
+}
+
+#[test]
+fn test_canonicalize() {
+    let same = &[
+        ("(+ herbie0 herbie1)", "(+ herbie1 herbie0)"),
+        ("(+ herbie1 (sqr herbie0))", "(+ (* herbie3 herbie3) herbie2)"),
+        ("(- (sqrt (+ herbie0 1)) (sqrt herbie0))", "(- (sqrt (+ 1 herbie7)) (sqrt herbie7))"),
+        ("(- (- herbie0))", "herbie3"),
+        ("(* 2 (- 3))", "(* -3 2)"),
+        ("(+ (* herbie0 herbie1) (* herbie1 herbie1))",
+         "(+ (* herbie1 herbie1) (* herbie0 herbie1))"),
+    ];
+
+    for &(lhs, rhs) in same {
+        let (lhs, rhs) = (parse(lhs).unwrap(), parse(rhs).unwrap());
+        assert_eq!(lhs.canonicalize().0, rhs.canonicalize().0);
+        assert_eq!(lhs.canonical_hash(), rhs.canonical_hash());
+    }
+
+    let different = &[
+        ("(- (* herbie0 herbie0) herbie1)", "(- herbie1 (* herbie0 herbie0))"),
+        ("(+ herbie0 herbie0)", "(+ herbie0 herbie1)"),
+        ("(* (+ herbie0 herbie1) herbie2)", "(+ (* herbie0 herbie1) herbie2)"),
+    ];
+
+    for &(lhs, rhs) in different {
+        let (lhs, rhs) = (parse(lhs).unwrap(), parse(rhs).unwrap());
+        assert!(lhs.canonicalize().0 != rhs.canonicalize().0);
+        assert!(lhs.canonical_hash() != rhs.canonical_hash());
+    }
+
+    // the parser numbers identifiers from 0, build the expression to have other numbers
+    let square = Binary(BinOp::Mul, Box::new(Ident(3)), Box::new(Ident(3)));
+    let expr = Binary(BinOp::Add, Box::new(square), Box::new(Ident(2)));
+    let (canonical, renaming) = expr.canonicalize();
+    assert_eq!(canonical.to_lisp("herbie"), "(+ herbie0 (* herbie1 herbie1))");
+    assert_eq!(renaming[&2], 0);
+    assert_eq!(renaming[&3], 1);
+}
@@ -0,0 +1,121 @@
+extern crate herbie_lint_core;
+
+use herbie_lint_core::lisp::{self, LispExpr, Parser, Position, Prec};
+use herbie_lint_core::matcher::{self, Shape, Source};
+
+fn parse(s: &str) -> LispExpr {
+    Parser::new().parse(s).unwrap()
+}
+
+/// `LispExpr`s themselves, as a source language whose variables are the identifiers, written
+/// `x0`, `x1`, etc. Functions unknown to Herbie are opaque values.
+struct Lisp;
+
+impl Source for Lisp {
+    type Expr = LispExpr;
+    type Key = u64;
+
+    fn shape<'a>(&self, expr: &'a LispExpr) -> Shape<'a, LispExpr, u64> {
+        match *expr {
+            LispExpr::Binary(op, ref lhs, ref rhs) => Shape::Binary(op, lhs, rhs),
+            LispExpr::Fun(ref name, ref params) => {
+                match lisp::rust_name(name).and_then(|name| lisp::herbie_name(name, params.len())) {
+                    Some(name) => Shape::Fun(name, params.iter().collect()),
+                    None => Shape::Opaque,
+                }
+            }
+            LispExpr::Ident(id) => Shape::Var(id),
+            LispExpr::Lit(f) => Shape::Lit(f),
+            LispExpr::Unary(op, ref expr) => Shape::Unary(op, expr),
+        }
+    }
+
+    fn snippet(&self, expr: &LispExpr) -> String {
+        expr.to_rust_placeholder("x").unwrap()
+    }
+
+    fn prec(&self, expr: &LispExpr) -> Prec {
+        match *expr {
+            LispExpr::Binary(op, _, _) => Prec::of_binop(op),
+            LispExpr::Unary(..) => Prec::Unary,
+            _ => Prec::Max,
+        }
+    }
+}
+
+/// Parse a rewrite whose identifiers `herbie0`, `herbie1` and `herbie2` are numbered 0, 1 and 2,
+/// whatever their order.
+fn rewrite(s: &str) -> LispExpr {
+    let mut parser = Parser::new();
+    parser.parse("(+ herbie0 (+ herbie1 herbie2))").unwrap();
+    parser.parse(s).unwrap()
+}
+
+fn suggest(expr: &str, (cmdin, cmdout): (&str, &str)) -> Option<String> {
+    let expr = parse(expr);
+    matcher::match_expr(&Lisp, &expr, &parse(cmdin)).and_then(|bindings| {
+        matcher::to_rust(&Lisp, &parse(cmdout), &bindings, Position::default(), (Prec::Min, false))
+    })
+}
+
+#[test]
+fn test_match() {
+    let hypot = ("(sqrt (+ (* herbie0 herbie0) (* herbie1 herbie1)))", "(hypot herbie0 herbie1)");
+    assert_eq!(suggest("(sqrt (+ (* a a) (* b b)))", hypot), Some("x0.hypot(x1)".into()));
+    assert_eq!(suggest("(sqrt (+ (* a b) (* b b)))", hypot), None);
+
+    // the operands of `+` and `*` can be in the other order
+    let log1p = ("(log (+ 1 herbie0))", "(log1p herbie0)");
+    assert_eq!(suggest("(log (+ a 1))", log1p), Some("x0.ln_1p()".into()));
+    assert_eq!(suggest("(log (- a 1))", log1p), None);
+
+    // identifiers match any sub-expression, which is parenthesized if needed
+    let expm1 = ("(- (exp herbie0) 1)", "(expm1 herbie0)");
+    assert_eq!(suggest("(- (exp (+ a 1)) 1)", expm1), Some("(x0 + 1.0).exp_m1()".into()));
+    assert_eq!(suggest("(- (exp a) 2)", expm1), None);
+    // but such sub-expressions are not known to have the same value
+    assert_eq!(suggest("(sqrt (+ (* (+ a 1) (+ a 1)) (* b b)))", hypot), None);
+}
+
+#[test]
+fn test_from_expr() {
+    let opaque = LispExpr::Fun("cot".into(), vec![LispExpr::Ident(1)]);
+    let expr = LispExpr::Binary(
+        lisp::BinOp::Add,
+        Box::new(parse("(* a (cos b))")),
+        Box::new(LispExpr::Binary(lisp::BinOp::Mul, Box::new(LispExpr::Ident(0)), Box::new(opaque)))
+    );
+    let (lisp_expr, bindings) = matcher::from_expr(&Lisp, &expr).unwrap();
+
+    assert_eq!(lisp_expr.to_lisp("herbie"), "(+ (* herbie0 (cos herbie1)) (* herbie0 herbie2))");
+    assert_eq!(bindings.idents.len(), 3);
+    // opaque sub-expressions might have side effects
+    assert!(!bindings.is_exact_rewrite(&Lisp, &parse("(+ herbie2 herbie2)")));
+    assert!(bindings.is_exact_rewrite(&Lisp, &parse("(* herbie0 (+ herbie2 (cos herbie1)))")));
+}
+
+#[test]
+fn test_exact_rewrite_order() {
+    // `cot` is unknown to the parser and to the source, so both calls are opaque and might have
+    // side effects: `(+ (cot a) (* (cot b) 2))`
+    let cot = |id| LispExpr::Fun("cot".into(), vec![LispExpr::Ident(id)]);
+    let expr = LispExpr::Binary(
+        lisp::BinOp::Add,
+        Box::new(cot(0)),
+        Box::new(LispExpr::Binary(lisp::BinOp::Mul, Box::new(cot(1)), Box::new(LispExpr::Lit(2.))))
+    );
+    let (_, bindings) = matcher::from_expr(&Lisp, &expr).unwrap();
+    assert!(bindings.is_exact_rewrite(&Lisp, &rewrite("(+ herbie0 (* 2 herbie1))")));
+    assert!(!bindings.is_exact_rewrite(&Lisp, &rewrite("(+ (* herbie1 2) herbie0)")));
+    assert!(!bindings.is_exact_rewrite(&Lisp, &rewrite("(+ herbie0 (+ herbie1 herbie1))")));
+
+    // the order is the one of the original expression, not of the pattern
+    let bindings = matcher::match_expr(&Lisp, &expr, &parse("(+ (* herbie1 2) herbie0)")).unwrap();
+    assert!(bindings.is_exact_rewrite(&Lisp, &rewrite("(+ herbie1 (* 2 herbie0))")));
+    assert!(!bindings.is_exact_rewrite(&Lisp, &rewrite("(+ (* 2 herbie0) herbie1)")));
+
+    // a single opaque sub-expression can be moved
+    let expr = LispExpr::Binary(lisp::BinOp::Add, Box::new(LispExpr::Ident(0)), Box::new(cot(1)));
+    let (_, bindings) = matcher::from_expr(&Lisp, &expr).unwrap();
+    assert!(bindings.is_exact_rewrite(&Lisp, &rewrite("(+ herbie1 herbie0)")));
+}
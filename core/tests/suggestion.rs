@@ -0,0 +1,97 @@
+extern crate herbie_lint_core;
+extern crate rustc_serialize;
+
+use herbie_lint_core::cost::Cost;
+use herbie_lint_core::suggestion::{self, Applicability, Location, Suggestion};
+use rustc_serialize::json::Json;
+use std::env::temp_dir;
+use std::fs::{File, create_dir_all, remove_dir_all};
+use std::io::{Read, Write};
+
+/// Check that `json` has the fields of a rustc JSON diagnostic which rustfix needs.
+fn check_diagnostic(json: &Json) {
+    for field in &["message", "level", "spans", "children"] {
+        assert!(json.find(field).is_some(), "missing {} in {}", field, json);
+    }
+    assert!(json["message"].is_string());
+    assert!(json["level"].is_string());
+
+    for span in json["spans"].as_array().unwrap() {
+        for field in &["file_name", "suggested_replacement", "suggestion_applicability"] {
+            assert!(span.find(field).is_some(), "missing {} in {}", field, span);
+        }
+        for field in &["byte_start", "byte_end", "line_start", "line_end", "column_start",
+                       "column_end"] {
+            assert!(span[*field].is_u64(), "{} is not an offset in {}", field, span);
+        }
+        assert!(span["is_primary"].is_boolean());
+        for line in span["text"].as_array().unwrap() {
+            assert!(line["text"].is_string());
+            assert!(line["highlight_start"].is_u64() && line["highlight_end"].is_u64());
+        }
+    }
+
+    for child in json["children"].as_array().unwrap() {
+        check_diagnostic(child);
+    }
+}
+
+#[test]
+fn test_write_json() {
+    let root = temp_dir().join("herbie-lint-test-suggestion");
+    let _ = remove_dir_all(&root);
+    create_dir_all(&root).unwrap();
+    let lib = root.join("lib.rs");
+    let lib = lib.to_str().unwrap();
+    let fixes = root.join("fixes.json");
+
+    let code = "fn f(a: f64, b: f64) {\n    (a*a +\n     b*b).sqrt();\n}\n";
+    File::create(lib).unwrap().write_all(code.as_bytes()).unwrap();
+
+    let location = Location {
+        file_name: lib.into(),
+        byte_start: 27,
+        byte_end: 50,
+        line_start: 2,
+        line_end: 3,
+        column_start: 5,
+        column_end: 17,
+    };
+    let suggestion = Suggestion::new(
+        location, "Try this", "a.hypot(b)".into(), Applicability::MachineApplicable,
+        29.9, 0.0, Cost::default(), Cost::default()
+    );
+    suggestion::write_json(fixes.to_str().unwrap(), &[suggestion]).unwrap();
+
+    let mut content = String::new();
+    File::open(&fixes).unwrap().read_to_string(&mut content).unwrap();
+    remove_dir_all(root).unwrap();
+
+    let lines = content.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 1);
+    let json = Json::from_str(lines[0]).unwrap();
+    check_diagnostic(&json);
+
+    // the warning is on the expression, and its help has the replacement
+    assert_eq!(json["level"].as_string(), Some("warning"));
+    assert_eq!(json["spans"][0]["suggested_replacement"], Json::Null);
+    let help = &json["children"][0];
+    assert_eq!(help["level"].as_string(), Some("help"));
+    assert_eq!(help["message"].as_string(), Some("Try this"));
+
+    let span = &help["spans"][0];
+    assert_eq!(span["file_name"].as_string(), Some(lib));
+    assert_eq!(&code[27..50], "(a*a +\n     b*b).sqrt()");
+    assert_eq!(span["byte_start"].as_u64(), Some(27));
+    assert_eq!(span["byte_end"].as_u64(), Some(50));
+    assert_eq!(span["suggested_replacement"].as_string(), Some("a.hypot(b)"));
+    assert_eq!(span["suggestion_applicability"].as_string(), Some("MachineApplicable"));
+
+    let text = span["text"].as_array().unwrap();
+    assert_eq!(text.len(), 2);
+    assert_eq!(text[0]["text"].as_string(), Some("    (a*a +"));
+    assert_eq!((text[0]["highlight_start"].as_u64(), text[0]["highlight_end"].as_u64()),
+               (Some(5), Some(11)));
+    assert_eq!((text[1]["highlight_start"].as_u64(), text[1]["highlight_end"].as_u64()),
+               (Some(1), Some(17)));
+}
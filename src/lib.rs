@@ -15,23 +15,23 @@
     unused_qualifications,
 )]
 
-extern crate itertools;
-extern crate rusqlite;
+extern crate herbie_lint_core;
 #[macro_use]
 extern crate rustc;
 #[macro_use]
 extern crate rustc_plugin;
 extern crate rustc_serialize;
 extern crate syntax;
-extern crate toml;
-extern crate wait_timeout;
 
 use syntax::feature_gate::AttributeType;
 use rustc_plugin::Registry;
 
-mod conf;
+pub use herbie_lint_core::{bigfloat, check, conf, cost, db, estimate, herbie, ignore, lisp,
+                           matcher, report, sarif, suggestion};
+
 pub mod lint;
-pub mod lisp;
+pub mod range;
+pub mod source;
 mod utils;
 
 #[plugin_registrar]
@@ -39,4 +39,5 @@ pub fn plugin_registrar(reg: &mut Registry) {
     reg.register_late_lint_pass(box lint::Herbie::new());
 
     reg.register_attribute("herbie_ignore".into(), AttributeType::Whitelisted);
+    reg.register_attribute("herbie_range".into(), AttributeType::Whitelisted);
 }
@@ -1,57 +1,36 @@
+use check::{Checker, Message};
 use conf;
-use itertools::Itertools;
 use lisp::LispExpr;
-use lisp;
-use rusqlite as sql;
+use matcher::{self, Bindings};
+use range;
+use report::{self, Entry};
+use sarif;
+use source::{self, HirSource};
 use rustc::hir::*;
 use rustc::hir::map::Node;
 use rustc::lint::{LateContext, LintArray, LintContext, LintPass, LateLintPass};
 use rustc::ty::TypeVariants;
-use std::borrow::Cow;
-use std::io::{Read, Write};
-use std::process::{Command, Stdio};
-use std::time::Duration;
-use std;
+use std::collections::HashSet;
+use std::mem;
+use suggestion::{self, Suggestion};
 use syntax::ast::MetaItemKind;
-use syntax::ast::{Attribute, FloatTy};
-use wait_timeout::ChildExt;
+use syntax::ast::{Attribute, CRATE_NODE_ID, FloatTy, NodeId};
+use syntax::attr::ThinAttributesExt;
+use utils::location;
+
+pub use herbie::InitError;
 
 #[derive(Debug, Default)]
 pub struct Herbie {
-    conf: Option<conf::Conf>,
+    checker: Option<Checker>,
     initialized: bool,
-    subs: Vec<(LispExpr, LispExpr)>,
-}
-
-#[derive(Debug)]
-pub enum InitError {
-    Conf {
-        error: conf::ConfError,
-    },
-    SQL {
-        error: sql::Error,
-    },
-}
-
-impl From<conf::ConfError> for InitError {
-    fn from(err: conf::ConfError) -> InitError {
-        InitError::Conf { error: err }
-    }
-}
-
-impl From<sql::Error> for InitError {
-    fn from(err: sql::Error) -> InitError {
-        InitError::SQL { error: err }
-    }
-}
-
-impl std::fmt::Display for InitError {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
-        match *self {
-            InitError::Conf { ref error } => write!(f, "Configuration error: {}", error),
-            InitError::SQL { ref error } => write!(f, "Got SQL error: {}", error),
-        }
-    }
+    /// The warnings of the configuration, which are reported once.
+    warnings: Vec<String>,
+    suggestions: Vec<Suggestion>,
+    entries: Vec<Entry>,
+    /// The compound assignments which were reported, whose right-hand side is then not reported
+    /// on its own.
+    assignments: HashSet<NodeId>,
 }
 
 impl Herbie {
@@ -66,43 +45,9 @@ impl Herbie {
 
         self.initialized = true;
 
-        let conf = try!(conf::read_conf());
-        let connection = try!(sql::Connection::open_with_flags(
-            conf.db_path.as_ref(), sql::SQLITE_OPEN_READ_ONLY
-        ));
-        let mut query = try!(connection.prepare("SELECT * FROM HerbieResults"));
-
-        self.subs = try!(query.query(&[])).filter_map(|row| {
-            match row {
-                Ok(row) => {
-                    let cmdin : String = row.get(1);
-                    let cmdout : String = row.get(2);
-                    // row.get(3) is opts ↔ Herbies options
-                    let errin = row.get_checked(4).unwrap_or(0.);
-                    let errout = row.get_checked(5).unwrap_or(0.);
-
-                    if cmdin == cmdout || errin <= errout {
-                        return None;
-                    }
-
-                    let mut parser = lisp::Parser::new();
-                    match parser.parse(&cmdin) {
-                        Ok(cmdin) => {
-                            match parser.parse(&cmdout) {
-                                Ok(cmdout) => {
-                                    Some((cmdin, cmdout))
-                                }
-                                Err(..) => None,
-                            }
-                        }
-                        Err(..) => None,
-                    }
-                }
-                Err(..) => None,
-            }
-        }).collect();
-
-        self.conf = Some(conf);
+        let mut conf = try!(conf::read_conf());
+        self.warnings = mem::replace(&mut conf.warnings, Vec::new());
+        self.checker = Some(try!(Checker::new(conf)));
 
         Ok(())
     }
@@ -120,27 +65,26 @@ impl LintPass for Herbie {
 
 impl LateLintPass for Herbie {
     fn check_expr(&mut self, cx: &LateContext, expr: &Expr) {
-        fn is_herbie_ignore(attr: &Attribute) -> bool {
-            if let MetaItemKind::Word(ref word) = attr.node.value.node {
-                word == &"herbie_ignore"
-            }
-            else {
-                false
-            }
+        if is_ignored(cx, expr) {
+            return;
         }
 
-        let attrs = match cx.tcx.map.find(cx.tcx.map.get_parent(expr.id)) {
-            Some(Node::NodeItem(item)) => &item.attrs,
-            Some(Node::NodeTraitItem(item)) => &item.attrs,
-            Some(Node::NodeImplItem(item)) => &item.attrs,
-            _ => panic!("In herbie-lint: how did I get there?"),
-        };
-
-        if attrs.iter().any(is_herbie_ignore) {
-            return;
+        // the suggestion for `a op= b` already rewrites `b`, assignments are checked first
+        let map = &cx.tcx.map;
+        if let Some(Node::NodeExpr(parent)) = map.find(map.get_parent_node(expr.id)) {
+            if let ExprAssignOp(_, _, ref rhs) = parent.node {
+                if rhs.id == expr.id && self.assignments.contains(&parent.id) {
+                    return;
+                }
+            }
         }
 
-        let ty = cx.tcx.expr_ty(expr);
+        let ty = match expr.node {
+            // `a op= b` has type `()`, the interesting type is the one of `a`; plain assignments
+            // need no special treatment as their right-hand side is checked on its own
+            ExprAssignOp(_, ref lhs, _) => cx.tcx.expr_ty(lhs),
+            _ => cx.tcx.expr_ty(expr),
+        };
 
         if ty.sty != TypeVariants::TyFloat(FloatTy::F64) {
             return;
@@ -157,171 +101,173 @@ impl LateLintPass for Herbie {
             return;
         }
 
-        let mut got_match = false;
-        for &(ref cmdin, ref cmdout) in &self.subs {
-            if let Some(bindings) = LispExpr::match_expr(expr, cmdin) {
-                report(cx, expr, cmdout, &bindings);
-                got_match = true;
+        for warning in self.warnings.drain(..) {
+            cx.span_lint(HERBIE, cx.krate.span, &format!("Herbie configuration: {}", warning));
+        }
+
+        // there is nothing to analyse in a mere variable or literal, this is only checked now so
+        // that the errors above are reported even in crates with only such expressions
+        match expr.node {
+            ExprField(..) | ExprLit(..) | ExprPath(..) | ExprTupField(..) => return,
+            _ => (),
+        }
+
+        let checker = self.checker.as_mut().expect("Herbie should be initialized by now");
+        let known = range::known_ranges(cx, expr);
+        let outcome = checker.check(
+            &HirSource::with_ranges(cx, &known),
+            expr,
+            location(cx, expr.span),
+            |cmdout, bindings| to_rust(cx, expr, cmdout, bindings),
+            || {
+                // TODO: link to wiki about Herbie.toml
+                cx.sess().diagnostic().span_note_without_error(
+                    expr.span,
+                    "Calling Herbie on the following expression, it might take a while"
+                );
+            }
+        );
+
+        for message in outcome.messages {
+            match message {
+                Message::Warning(message) => cx.span_lint(HERBIE, expr.span, &message),
+                Message::Note(message) => {
+                    cx.sess().diagnostic().span_note_without_error(expr.span, &message);
+                }
             }
         }
 
-        let conf = self.conf.as_ref().expect("Configuration should be read by now");
-        if !got_match && conf.use_herbie != conf::UseHerbieConf::No {
-            if let Err(err) = try_with_herbie(cx, expr, &conf) {
-                cx.span_lint(HERBIE, expr.span, &err);
+        self.entries.extend(outcome.entries);
+
+        if let Some(suggestion) = outcome.suggestion {
+            cx.struct_span_lint(HERBIE, expr.span, &suggestion.lint_message())
+              .span_suggestion(expr.span,
+                               &suggestion.message,
+                               suggestion.suggested_replacement.clone())
+              .emit();
+
+            if let ExprAssignOp(..) = expr.node {
+                self.assignments.insert(expr.id);
             }
+            self.suggestions.push(suggestion);
         }
     }
-}
-
-fn try_with_herbie(cx: &LateContext, expr: &Expr, conf: &conf::Conf) -> Result<(), Cow<'static, str>> {
-    let (lisp_expr, nb_ids, bindings) = match LispExpr::from_expr(expr) {
-        Some(r) => r,
-        // not an error, the expression might for example contain a function unknown to Herbie
-        None => return Ok(()),
-    };
 
-    if lisp_expr.depth() <= 2 {
-        return Ok(());
+    fn check_attribute(&mut self, cx: &LateContext, attr: &Attribute) {
+        if let Some(Err(err)) = range::parse_attribute(attr) {
+            cx.span_lint(HERBIE, attr.span, &err);
+        }
     }
 
-    let seed: &str = &conf.herbie_seed;
-    let mut command = Command::new("herbie-inout");
-    let command = command
-        .arg("--seed").arg(seed)
-        .arg("-o").arg("rules:numerics")
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-    ;
-
-    let mut child = match command.spawn() {
-        Ok(child) => child,
-        Err(err) => {
-            return if conf.use_herbie == conf::UseHerbieConf::Yes {
-                // TODO: wiki
-                Err(format!("Could not call Herbie: {}", err).into())
+    fn check_crate_post(&mut self, cx: &LateContext, krate: &Crate) {
+        let conf = match self.checker {
+            Some(ref checker) => checker.conf(),
+            None => return,
+        };
+
+        if let Some(ref fixes_path) = conf.fixes_path {
+            if let Err(err) = suggestion::write_json(fixes_path, &self.suggestions) {
+                cx.span_lint(
+                    HERBIE,
+                    krate.span,
+                    &format!("Could not write suggestions to {}: {}", fixes_path, err)
+                );
             }
-            else {
-                Ok(())
-            };
         }
-    };
-
-    // TODO: link to wiki about Herbie.toml
-    cx.sess().diagnostic().span_note_without_error(
-        expr.span,
-        "Calling Herbie on the following expression, it might take a while"
-    );
-
-    let params = (0..nb_ids).map(|id| format!("herbie{}", id)).join(" ");
-    let cmdin = lisp_expr.to_lisp("herbie");
-    let lisp_expr = format!("(lambda ({}) {})\n", params, cmdin);
-    let lisp_expr = lisp_expr.as_bytes();
-    child.stdin
-        .as_mut().expect("Herbie-inout's stdin not captured")
-        .write(lisp_expr).expect("Could not write on herbie-inout's stdin")
-    ;
-
-    match conf.timeout {
-        Some(timeout) => {
-            match child.wait_timeout(Duration::from_secs(timeout as u64)) {
-                Ok(Some(status)) if status.success() => (),
-                Ok(Some(status)) => {
-                    return Err(format!("herbie-inout did not return successfully: status={}", status).into());
-                }
-                Ok(None) => {
-                    cx.sess().diagnostic().span_note_without_error(expr.span, "Herbie timed out");
-                    return Ok(());
-                }
-                Err(err) => {
-                    return Err(format!("herbie-inout did not return successfully: {}", err).into());
-                }
+
+        if let Some(ref sarif_path) = conf.sarif_path {
+            if let Err(err) = sarif::write(sarif_path, &self.suggestions) {
+                cx.span_lint(
+                    HERBIE,
+                    krate.span,
+                    &format!("Could not write SARIF file {}: {}", sarif_path, err)
+                );
             }
         }
-        None => {
-            match child.wait() {
-                Ok(status) if status.success() => (),
-                Ok(status) => {
-                    return Err(format!("herbie-inout did not return successfully: status={}", status).into());
-                }
-                Err(err) => {
-                    return Err(format!("herbie-inout did not return successfully: {}", err).into());
-                }
+
+        if let Some(ref report_path) = conf.report_path {
+            if let Err(err) = report::write(report_path, &self.entries) {
+                cx.span_lint(
+                    HERBIE,
+                    krate.span,
+                    &format!("Could not write report to {}: {}", report_path, err)
+                );
             }
         }
     }
+}
 
-    let mut stdout = if let Some(output) = child.stdout {
-        output
+fn is_herbie_ignore(attr: &Attribute) -> bool {
+    if let MetaItemKind::Word(ref word) = attr.node.value.node {
+        word == &"herbie_ignore"
     }
     else {
-        return Err("cannot capture herbie-inout output".into());
-    };
-
-    let mut output = String::new();
-    if let Err(err) = stdout.read_to_string(&mut output) {
-        return Err(format!("cannot read output: {}", err).into());
+        false
     }
+}
 
-    let mut output = output.lines();
-
-    let parse_error = |s: Option<&str>| -> Option<f64> {
-        match s {
-            Some(s) => {
-                match s.split(' ').last().map(str::parse::<f64>) {
-                    Some(Ok(f)) => Some(f),
-                    _ => None,
-                }
+/// Check whether `expr` or any of its ancestors, up to and including the crate itself, is marked
+/// with `#[herbie_ignore]`. Items nested in a function are not affected by the attributes of the
+/// function, but they are by those of the modules containing it.
+fn is_ignored(cx: &LateContext, expr: &Expr) -> bool {
+    let map = &cx.tcx.map;
+    let mut id = expr.id;
+    let mut in_item = false;
+    // whether an item nested in the body of a function was left, after which only the attributes
+    // of modules apply
+    let mut in_body = false;
+
+    while id != CRATE_NODE_ID {
+        let (attrs, is_item, is_mod) = match map.find(id) {
+            Some(Node::NodeItem(item)) => {
+                let is_mod = if let ItemMod(..) = item.node { true } else { false };
+                (&item.attrs[..], true, is_mod)
             }
-            _ => None,
-        }
-    };
+            Some(Node::NodeForeignItem(item)) => (&item.attrs[..], true, false),
+            Some(Node::NodeTraitItem(item)) => (&item.attrs[..], true, false),
+            Some(Node::NodeImplItem(item)) => (&item.attrs[..], true, false),
+            Some(Node::NodeVariant(variant)) => (&variant.node.attrs[..], true, false),
+            Some(Node::NodeExpr(expr)) => (expr.attrs.as_attr_slice(), false, false),
+            Some(Node::NodeStmt(stmt)) => (stmt.node.attrs(), false, false),
+            // blocks, patterns, etc.: keep looking at the parents
+            _ => (&[][..], false, false),
+        };
 
-    let (errin, errout, cmdout) = match (parse_error(output.next()), parse_error(output.next()), output.next()) {
-        (Some(errin), Some(errout), Some(cmdout)) => {
-            (errin, errout, cmdout)
-        }
-        _ => {
-            return Err("Could not parse herbie-inout output".into())
+        // the item is in the body of a function
+        if in_item && !is_item {
+            in_body = true;
         }
-    };
+        in_item |= is_item;
 
+        if (!in_body || is_mod) && attrs.iter().any(is_herbie_ignore) {
+            return true;
+        }
 
-    if errin <= errout {
-        return Ok(());
+        let parent = map.get_parent_node(id);
+        if parent == id {
+            break;
+        }
+        id = parent;
     }
 
-    let mut parser = lisp::Parser::new();
-    let cmdout = match parser.parse(cmdout) {
-        Ok(cmdout) => cmdout,
-        _ => return Err("Could not understand herbie-inout cmdout".into()),
-    };
-
-    report(cx, expr, &cmdout, &bindings);
-    save(conf, &cmdin, &cmdout, "", errin, errout)
-        .map_err(|err| format!("Could not save database, got SQL error {}", err).into())
-}
-
-fn report(cx: &LateContext, expr: &Expr, cmdout: &LispExpr, bindings: &lisp::MatchBindings) {
-    cx.struct_span_lint(HERBIE, expr.span, "Numerically unstable expression")
-      .span_suggestion(expr.span, "Try this", cmdout.to_rust(cx, &bindings))
-      .emit();
+    cx.krate.attrs.iter().any(is_herbie_ignore)
 }
 
-fn save(
-    conf: &conf::Conf,
-    cmdin: &str, cmdout: &LispExpr,
-    seed: &str,
-    errin: f64, errout: f64
-) -> Result<(), sql::Error> {
-    let connection = try!(sql::Connection::open_with_flags(
-        conf.db_path.as_ref(), sql::SQLITE_OPEN_READ_WRITE
-    ));
-
-    try!(connection.execute("INSERT INTO HerbieResults (cmdin, cmdout, opts, errin, errout)
-                             VALUES ($1, $2, $3, $4, $5)",
-                            &[&cmdin, &cmdout.to_lisp("herbie"), &seed, &errin, &errout]));
-
-    Ok(())
+/// Print `cmdout` as Rust code replacing `expr`, unless it has no Rust equivalent.
+fn to_rust(
+    cx: &LateContext,
+    expr: &Expr,
+    cmdout: &LispExpr,
+    bindings: &Bindings<Expr>
+) -> Option<String> {
+    let source = HirSource::new(cx);
+    match expr.node {
+        ExprAssignOp(_, ref lhs, _) => {
+            matcher::to_rust_assign(&source, cmdout, bindings, lhs, source::position(cx, lhs))
+        }
+        _ => {
+            let (position, prec) = (source::position(cx, expr), source::context_prec(cx, expr));
+            matcher::to_rust(&source, cmdout, bindings, position, prec)
+        }
+    }
 }
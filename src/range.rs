@@ -0,0 +1,393 @@
+//! Ranges of the values of variables, known from `#[herbie_range]` attributes and assertions.
+
+use herbie_lint_core::range::{intersect, parse_range};
+use rustc::hir::*;
+use rustc::hir::def::Def;
+use rustc::hir::intravisit::{Visitor, walk_expr};
+use rustc::hir::map::{Map, Node};
+use rustc::lint::{LateContext, LintContext};
+use rustc::ty::{UpvarBorrow, UpvarCapture, UpvarId};
+use rustc::ty::BorrowKind::{MutBorrow, UniqueImmBorrow};
+use rustc::ty::adjustment::{AutoDerefRef, AutoPtr};
+use rustc::ty::adjustment::AutoAdjustment::AdjustDerefRef;
+use std::collections::{HashMap, HashSet};
+use std;
+use syntax::ast::{Attribute, CRATE_NODE_ID, FloatTy, LitKind, MetaItemKind, NodeId};
+use syntax::attr::ThinAttributesExt;
+use syntax::codemap::ExpnFormat;
+
+/// A local variable, or a field of one, e.g. `x` or `self.a`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Var {
+    /// The pattern defining the variable.
+    pub def: NodeId,
+    /// The fields accessed on the variable, e.g. `["a"]` for `self.a`.
+    pub fields: Vec<String>,
+}
+
+/// Inclusive ranges, by variable.
+pub type Ranges = HashMap<Var, (f64, f64)>;
+
+/// Parse the ranges of a `#[herbie_range(x = "0..1", y = "-1e3..")]` attribute, by variable name,
+/// an omitted bound being infinite. Return `None` if this is another attribute.
+pub fn parse_attribute(attr: &Attribute) -> Option<Result<HashMap<String, (f64, f64)>, String>> {
+    let items = match attr.node.value.node {
+        MetaItemKind::List(ref name, ref items) if name == &"herbie_range" => items,
+        MetaItemKind::Word(ref name) | MetaItemKind::NameValue(ref name, _)
+            if name == &"herbie_range" => {
+            return Some(Err(USAGE.into()));
+        }
+        _ => return None,
+    };
+
+    let mut ranges = HashMap::new();
+    for item in items {
+        let (name, range) = match item.node {
+            MetaItemKind::NameValue(ref name, ref lit) => {
+                match lit.node {
+                    LitKind::Str(ref range, _) => (name, range),
+                    _ => return Some(Err(USAGE.into())),
+                }
+            }
+            _ => return Some(Err(USAGE.into())),
+        };
+
+        match parse_range(range) {
+            Some(range) => intersect(&mut ranges, name.to_string(), range),
+            None => return Some(Err(format!("Invalid range for `{}`: `{}`", name, range))),
+        }
+    }
+
+    Some(Ok(ranges))
+}
+
+const USAGE: &'static str = "Expected `#[herbie_range(x = \"lo..hi\")]`";
+
+/// Return the ranges known at `expr`, from the `#[herbie_range]` attributes of its ancestors and
+/// the assertions before it in the enclosing blocks, e.g. `assert!(0. <= x && x <= 1.)`. Only the
+/// attributes of the modules containing it apply to an item nested in a function, and the
+/// assertions of that function do not. An assertion no longer applies after its variables are
+/// assigned to.
+pub fn known_ranges(cx: &LateContext, expr: &Expr) -> Ranges {
+    let map = &cx.tcx.map;
+    let mut vars = Vars { cx: cx, vars: HashSet::new() };
+    vars.visit_expr(expr);
+
+    let mut ranges = Ranges::new();
+    let mut id = expr.id;
+    let mut in_item = false;
+    // whether an item nested in the body of a function was left, after which only the attributes
+    // of modules apply
+    let mut in_body = false;
+
+    while id != CRATE_NODE_ID {
+        let parent = map.get_parent_node(id);
+
+        let (attrs, is_item, is_mod) = match map.find(id) {
+            Some(Node::NodeItem(item)) => {
+                let is_mod = if let ItemMod(..) = item.node { true } else { false };
+                (&item.attrs[..], true, is_mod)
+            }
+            Some(Node::NodeForeignItem(item)) => (&item.attrs[..], true, false),
+            Some(Node::NodeTraitItem(item)) => (&item.attrs[..], true, false),
+            Some(Node::NodeImplItem(item)) => (&item.attrs[..], true, false),
+            Some(Node::NodeExpr(expr)) => (expr.attrs.as_attr_slice(), false, false),
+            Some(Node::NodeStmt(stmt)) => (stmt.node.attrs(), false, false),
+            _ => (&[][..], false, false),
+        };
+
+        if in_item && !is_item {
+            in_body = true;
+        }
+        in_item |= is_item;
+
+        if !in_body || is_mod {
+            add_attributes(cx, &mut ranges, &vars.vars, id, is_item, attrs);
+        }
+
+        if let (false, Some(Node::NodeBlock(block))) = (in_item, map.find(parent)) {
+            // only the statements before `id` are known to have been executed
+            let before = block.stmts.iter().position(|stmt| stmt_id(stmt) == id);
+
+            // the statement containing `expr` is visited as a whole, as it might be a loop
+            let mut assigned = Assigned { cx: cx, defs: HashSet::new() };
+            match (before, &block.expr) {
+                (Some(idx), _) => assigned.visit_stmt(&block.stmts[idx]),
+                (None, &Some(ref tail)) => assigned.visit_expr(tail),
+                (None, &None) => (),
+            }
+
+            for stmt in block.stmts[..before.unwrap_or(block.stmts.len())].iter().rev() {
+                if let Some(cond) = assertion(cx, stmt) {
+                    let mut asserted = Ranges::new();
+                    add_condition(cx, &mut asserted, cond);
+                    for (var, range) in asserted {
+                        if !assigned.defs.contains(&var.def) {
+                            intersect(&mut ranges, var, range);
+                        }
+                    }
+                }
+
+                assigned.visit_stmt(stmt);
+            }
+        }
+
+        if parent == id {
+            break;
+        }
+        id = parent;
+    }
+
+    add_attributes(cx, &mut ranges, &vars.vars, CRATE_NODE_ID, true, &cx.krate.attrs);
+
+    ranges
+}
+
+/// Add the ranges given by the attributes of `node` to the variables of `vars` they apply to.
+fn add_attributes(
+    cx: &LateContext,
+    ranges: &mut Ranges,
+    vars: &HashSet<Var>,
+    node: NodeId,
+    is_item: bool,
+    attrs: &[Attribute]
+) {
+    for attr in attrs {
+        // invalid attributes are reported by the lint pass
+        if let Some(Ok(attr_ranges)) = parse_attribute(attr) {
+            for var in vars {
+                if let Some(&range) = attr_ranges.get(&name(cx, var)) {
+                    if applies(&cx.tcx.map, node, is_item, var.def) {
+                        intersect(ranges, var.clone(), range);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Whether the attributes of `node` apply to the variable defined by `def`: those of a function or
+/// a closure apply to its parameters, those of another item to the parameters of the functions in
+/// it, and those of a statement or an expression to the variables declared outside of it.
+fn applies(map: &Map, node: NodeId, is_item: bool, def: NodeId) -> bool {
+    let decl = declaration(map, def);
+
+    if decl == node {
+        true
+    }
+    else if is_item {
+        match map.find(decl) {
+            Some(Node::NodeItem(..)) | Some(Node::NodeTraitItem(..))
+            | Some(Node::NodeImplItem(..)) => true,
+            _ => false,
+        }
+    }
+    else {
+        !is_descendant(map, decl, node)
+    }
+}
+
+/// Return the node declaring the variable whose pattern is `def`: a function, a closure, a `let`
+/// statement, a `match` expression, etc.
+fn declaration(map: &Map, def: NodeId) -> NodeId {
+    let mut id = def;
+    loop {
+        let parent = map.get_parent_node(id);
+        match map.find(parent) {
+            Some(Node::NodeLocal(..)) | Some(Node::NodePat(..)) if parent != id => id = parent,
+            _ => return parent,
+        }
+    }
+}
+
+/// Whether `id` is `ancestor` or one of its descendants.
+fn is_descendant(map: &Map, mut id: NodeId, ancestor: NodeId) -> bool {
+    loop {
+        if id == ancestor {
+            return true;
+        }
+
+        let parent = map.get_parent_node(id);
+        if parent == id || id == CRATE_NODE_ID {
+            return false;
+        }
+        id = parent;
+    }
+}
+
+fn stmt_id(stmt: &Stmt) -> NodeId {
+    match stmt.node {
+        StmtDecl(_, id) | StmtExpr(_, id) | StmtSemi(_, id) => id,
+    }
+}
+
+/// Return the condition of an `assert!`.
+fn assertion<'a>(cx: &LateContext, stmt: &'a Stmt) -> Option<&'a Expr> {
+    let expr = match stmt.node {
+        StmtExpr(ref expr, _) | StmtSemi(ref expr, _) => expr,
+        StmtDecl(..) => return None,
+    };
+
+    // `assert!(cond)` expands to `if !cond { panic!(..) }`
+    if let ExprIf(ref cond, _, None) = expr.node {
+        if let ExprUnary(UnNot, ref cond) = cond.node {
+            let is_assert = cx.sess().codemap().with_expn_info(expr.span.expn_id, |info| {
+                match info.map(|info| &info.callee.format) {
+                    Some(&ExpnFormat::MacroBang(name)) => &*name.as_str() == "assert",
+                    _ => false,
+                }
+            });
+
+            if is_assert {
+                return Some(cond);
+            }
+        }
+    }
+
+    None
+}
+
+/// Add the bounds of a condition made of comparisons of variables with constants, such as
+/// `0. <= x && x < 1.`. Strict comparisons are considered as non-strict.
+fn add_condition(cx: &LateContext, ranges: &mut Ranges, cond: &Expr) {
+    if let ExprBinary(op, ref lhs, ref rhs) = cond.node {
+        match op.node {
+            BiAnd => {
+                add_condition(cx, ranges, lhs);
+                add_condition(cx, ranges, rhs);
+            }
+            BiLe | BiLt => add_bound(cx, ranges, lhs, rhs),
+            BiGe | BiGt => add_bound(cx, ranges, rhs, lhs),
+            _ => (),
+        }
+    }
+}
+
+/// Add the bound given by `lhs <= rhs`, if one of them is a variable and the other a constant.
+fn add_bound(cx: &LateContext, ranges: &mut Ranges, lhs: &Expr, rhs: &Expr) {
+    if let (Some(var), Some(hi)) = (variable(cx, lhs), constant(rhs)) {
+        intersect(ranges, var, (std::f64::NEG_INFINITY, hi));
+    }
+    else if let (Some(lo), Some(var)) = (constant(lhs), variable(cx, rhs)) {
+        intersect(ranges, var, (lo, std::f64::INFINITY));
+    }
+}
+
+/// Return the variable an expression is, if it is a local variable, or a field or tuple field of
+/// one.
+pub fn variable(cx: &LateContext, expr: &Expr) -> Option<Var> {
+    match expr.node {
+        ExprPath(None, _) => {
+            match cx.tcx.def_map.borrow().get(&expr.id).map(|resolution| resolution.full_def()) {
+                Some(Def::Local(_, def)) | Some(Def::Upvar(_, def, _, _)) => {
+                    Some(Var { def: def, fields: vec![] })
+                }
+                _ => None,
+            }
+        }
+        ExprField(ref obj, ref name) => {
+            variable(cx, obj).map(|mut var| {
+                var.fields.push(name.node.to_string());
+                var
+            })
+        }
+        ExprTupField(ref obj, ref idx) => {
+            variable(cx, obj).map(|mut var| {
+                var.fields.push(idx.node.to_string());
+                var
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Return the name of a variable as written in `#[herbie_range]`, e.g. `self.a`.
+fn name(cx: &LateContext, var: &Var) -> String {
+    let mut name = cx.tcx.map.name(var.def).to_string();
+    for field in &var.fields {
+        name.push('.');
+        name.push_str(field);
+    }
+    name
+}
+
+/// Collects the variables of an expression.
+struct Vars<'a, 'b: 'a, 'tcx: 'b> {
+    cx: &'a LateContext<'b, 'tcx>,
+    vars: HashSet<Var>,
+}
+
+impl<'a, 'b, 'tcx, 'v> Visitor<'v> for Vars<'a, 'b, 'tcx> {
+    fn visit_expr(&mut self, expr: &'v Expr) {
+        if let Some(var) = variable(self.cx, expr) {
+            self.vars.insert(var);
+        }
+        walk_expr(self, expr);
+    }
+}
+
+/// Collects the variables which are assigned to or borrowed mutably, e.g. `x` in `x.a = 1.`,
+/// `f(&mut x)`, `x.push(1.)` or a closure assigning to `x`.
+struct Assigned<'a, 'b: 'a, 'tcx: 'b> {
+    cx: &'a LateContext<'b, 'tcx>,
+    defs: HashSet<NodeId>,
+}
+
+impl<'a, 'b, 'tcx> Assigned<'a, 'b, 'tcx> {
+    fn insert(&mut self, expr: &Expr) {
+        if let Some(var) = variable(self.cx, expr) {
+            self.defs.insert(var.def);
+        }
+    }
+}
+
+impl<'a, 'b, 'tcx, 'v> Visitor<'v> for Assigned<'a, 'b, 'tcx> {
+    fn visit_expr(&mut self, expr: &'v Expr) {
+        match expr.node {
+            ExprAssign(ref lhs, _) | ExprAssignOp(_, ref lhs, _) => self.insert(lhs),
+            ExprAddrOf(MutMutable, ref inner) => self.insert(inner),
+            ExprMethodCall(_, _, ref args) => {
+                // the receiver of a `&mut self` method is borrowed implicitly
+                let adjustment = self.cx.tcx.tables.borrow().adjustments.get(&args[0].id).cloned();
+                if let Some(AdjustDerefRef(AutoDerefRef {
+                    autoref: Some(AutoPtr(_, MutMutable)), ..
+                })) = adjustment {
+                    self.insert(&args[0]);
+                }
+            }
+            ExprClosure(..) => {
+                let captures = self.cx.tcx.with_freevars(expr.id, |freevars| {
+                    freevars.iter().map(|freevar| freevar.def.var_id()).collect::<Vec<_>>()
+                });
+                for var_id in captures {
+                    let upvar_id = UpvarId { var_id: var_id, closure_expr_id: expr.id };
+                    match self.cx.tcx.upvar_capture(upvar_id) {
+                        Some(UpvarCapture::ByRef(UpvarBorrow { kind: MutBorrow, .. })) |
+                        Some(UpvarCapture::ByRef(UpvarBorrow { kind: UniqueImmBorrow, .. })) => {
+                            self.defs.insert(var_id);
+                        }
+                        _ => (),
+                    }
+                }
+            }
+            _ => (),
+        }
+        walk_expr(self, expr);
+    }
+}
+
+/// Return the value of a float literal, possibly negated.
+fn constant(expr: &Expr) -> Option<f64> {
+    match expr.node {
+        ExprLit(ref lit) => {
+            match lit.node {
+                LitKind::Float(ref f, FloatTy::F64) | LitKind::FloatUnsuffixed(ref f) => {
+                    f.parse().ok()
+                }
+                _ => None,
+            }
+        }
+        ExprUnary(UnNeg, ref expr) => constant(expr).map(|f| -f),
+        _ => None,
+    }
+}
@@ -0,0 +1,276 @@
+//! The HIR of the crate being linted, as a source language for the matcher.
+
+use lisp::{self, Position, Prec, herbie_name, is_parenthesized};
+use matcher::{Shape, Source};
+use range::{self, Ranges};
+use rustc::hir::*;
+use rustc::hir::map::Node;
+use rustc::lint::LateContext;
+use rustc::ty::TypeVariants;
+use std;
+use syntax::ast::LitKind;
+use syntax::ast::{FloatTy, IntTy, Name, UintTy};
+use syntax::codemap::{BytePos, mk_sp};
+use utils::{line_indent, merge_span, snippet};
+
+#[derive(Clone, Copy)]
+pub struct HirSource<'a, 'b: 'a, 'tcx: 'b> {
+    cx: &'a LateContext<'b, 'tcx>,
+    /// The known ranges of the variables, see `range::known_ranges`.
+    ranges: Option<&'a Ranges>,
+}
+
+impl<'a, 'b, 'tcx> HirSource<'a, 'b, 'tcx> {
+    pub fn new(cx: &'a LateContext<'b, 'tcx>) -> HirSource<'a, 'b, 'tcx> {
+        HirSource { cx: cx, ranges: None }
+    }
+
+    /// A source where the variables have the known `ranges`.
+    pub fn with_ranges(
+        cx: &'a LateContext<'b, 'tcx>,
+        ranges: &'a Ranges
+    ) -> HirSource<'a, 'b, 'tcx> {
+        HirSource { cx: cx, ranges: Some(ranges) }
+    }
+}
+
+impl<'a, 'b, 'tcx> std::fmt::Debug for HirSource<'a, 'b, 'tcx> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        f.write_str("HirSource")
+    }
+}
+
+/// Identifies the variables of the HIR: paths, fields of paths, and casts to `f64` of a type with
+/// a range worth modelling.
+#[derive(Debug)]
+pub enum Key {
+    /// A cast, by its snippet.
+    Cast(String),
+    Field(Option<QSelf>, Path, Name),
+    Path(Option<QSelf>, Path),
+    TupField(Option<QSelf>, Path, usize),
+}
+
+/// Paths are compared without their spans.
+fn same_path(lhs: &Path, rhs: &Path) -> bool {
+    lhs.global == rhs.global && lhs.segments == rhs.segments
+}
+
+impl PartialEq for Key {
+    fn eq(&self, other: &Key) -> bool {
+        match (self, other) {
+            (&Key::Cast(ref lhs), &Key::Cast(ref rhs)) => lhs == rhs,
+            (&Key::Field(ref lqualif, ref lpath, lname),
+             &Key::Field(ref rqualif, ref rpath, rname)) => {
+                lqualif == rqualif && same_path(lpath, rpath) && lname == rname
+            }
+            (&Key::Path(ref lqualif, ref lpath), &Key::Path(ref rqualif, ref rpath)) => {
+                lqualif == rqualif && same_path(lpath, rpath)
+            }
+            (&Key::TupField(ref lqualif, ref lpath, lidx),
+             &Key::TupField(ref rqualif, ref rpath, ridx)) => {
+                lqualif == rqualif && same_path(lpath, rpath) && lidx == ridx
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Convert an arithmetic operator of the HIR.
+fn binop(op: BinOp_) -> Option<lisp::BinOp> {
+    match op {
+        BiAdd => Some(lisp::BinOp::Add),
+        BiSub => Some(lisp::BinOp::Sub),
+        BiMul => Some(lisp::BinOp::Mul),
+        BiDiv => Some(lisp::BinOp::Div),
+        BiRem => Some(lisp::BinOp::Rem),
+        _ => None,
+    }
+}
+
+impl<'a, 'b, 'tcx> Source for HirSource<'a, 'b, 'tcx> {
+    type Expr = Expr;
+    type Key = Key;
+
+    fn shape<'e>(&self, expr: &'e Expr) -> Shape<'e, Expr, Key> {
+        match expr.node {
+            // `a op= b` is analysed as `a op b`
+            ExprBinary(op, ref lhs, ref rhs) | ExprAssignOp(op, ref lhs, ref rhs) => {
+                binop(op.node).map_or(Shape::Unknown, |op| Shape::Binary(op, lhs, rhs))
+            }
+            ExprCast(ref inner, _) => {
+                if let Some(lit) = cast_lit(inner) {
+                    return Shape::Const(lit);
+                }
+
+                let inner_ty = self.cx.tcx.expr_ty(inner);
+                if inner_ty.sty == TypeVariants::TyFloat(FloatTy::F64) {
+                    Shape::Transparent(inner)
+                }
+                else if cast_range(&inner_ty.sty).is_some() {
+                    Shape::Var(Key::Cast(snippet(self.cx, expr.span, "..").into_owned()))
+                }
+                else {
+                    Shape::Opaque
+                }
+            }
+            ExprField(ref obj, ref name) => {
+                if let ExprPath(ref qualif, ref path) = obj.node {
+                    Shape::Var(Key::Field(qualif.clone(), path.clone(), name.node))
+                }
+                else {
+                    Shape::Opaque
+                }
+            }
+            ExprLit(ref lit) => {
+                match lit.node {
+                    LitKind::Float(ref f, FloatTy::F64) | LitKind::FloatUnsuffixed(ref f) => {
+                        f.parse().ok().map_or(Shape::Unknown, Shape::Lit)
+                    }
+                    _ => Shape::Unknown,
+                }
+            }
+            ExprMethodCall(ref fun, ref types, ref params) if types.is_empty() => {
+                match herbie_name(&fun.node.as_str(), params.len()) {
+                    Some(name) => Shape::Fun(name, params.iter().map(|param| &**param).collect()),
+                    None => Shape::Unknown,
+                }
+            }
+            ExprPath(ref qualif, ref path) => Shape::Var(Key::Path(qualif.clone(), path.clone())),
+            ExprTupField(ref tup, ref idx) => {
+                if let ExprPath(ref qualif, ref path) = tup.node {
+                    Shape::Var(Key::TupField(qualif.clone(), path.clone(), idx.node))
+                }
+                else {
+                    Shape::Opaque
+                }
+            }
+            ExprUnary(UnNeg, ref inner) => Shape::Unary(lisp::UnOp::Neg, inner),
+            ExprUnary(UnDeref, _) => Shape::Opaque,
+            _ => Shape::Unknown,
+        }
+    }
+
+    fn snippet(&self, expr: &Expr) -> String {
+        let span = match expr.node {
+            ExprField(ref obj, ref name) => {
+                if let ExprPath(_, ref path) = obj.node {
+                    merge_span(path.span, name.span)
+                }
+                else {
+                    expr.span
+                }
+            }
+            ExprPath(_, ref path) => path.span,
+            ExprTupField(ref tup, ref idx) => {
+                if let ExprPath(_, ref path) = tup.node {
+                    merge_span(path.span, idx.span)
+                }
+                else {
+                    expr.span
+                }
+            }
+            _ => expr.span,
+        };
+
+        snippet(self.cx, span, "..").into_owned()
+    }
+
+    fn prec(&self, expr: &Expr) -> Prec {
+        match expr.node {
+            ExprBinary(op, _, _) => binop(op.node).map_or(Prec::Min, Prec::of_binop),
+            ExprCast(..) | ExprType(..) => Prec::Cast,
+            ExprAddrOf(..) | ExprBox(..) | ExprUnary(..) => Prec::Unary,
+            ExprAssign(..) | ExprAssignOp(..) | ExprBlock(..) | ExprClosure(..) | ExprIf(..)
+            | ExprLoop(..) | ExprMatch(..) | ExprWhile(..) => Prec::Min,
+            _ => Prec::Max,
+        }
+    }
+
+    /// Casts have the range of the type they are casted from, and variables and fields the one
+    /// they are known to have.
+    fn range(&self, expr: &Expr) -> Option<(f64, f64)> {
+        match expr.node {
+            ExprCast(ref inner, _) => cast_range(&self.cx.tcx.expr_ty(inner).sty),
+            _ => {
+                self.ranges.and_then(|ranges| {
+                    range::variable(self.cx, expr).and_then(|var| ranges.get(&var).cloned())
+                })
+            }
+        }
+    }
+}
+
+/// Return the range of values of a type that is worth modelling when casted to `f64`.
+fn cast_range(ty: &TypeVariants) -> Option<(f64, f64)> {
+    match *ty {
+        TypeVariants::TyInt(IntTy::I8) => Some((std::i8::MIN as f64, std::i8::MAX as f64)),
+        TypeVariants::TyInt(IntTy::I16) => Some((std::i16::MIN as f64, std::i16::MAX as f64)),
+        TypeVariants::TyInt(IntTy::I32) => Some((std::i32::MIN as f64, std::i32::MAX as f64)),
+        TypeVariants::TyInt(IntTy::I64) | TypeVariants::TyInt(IntTy::Is) => {
+            Some((std::i64::MIN as f64, std::i64::MAX as f64))
+        }
+        TypeVariants::TyUint(UintTy::U8) => Some((0., std::u8::MAX as f64)),
+        TypeVariants::TyUint(UintTy::U16) => Some((0., std::u16::MAX as f64)),
+        TypeVariants::TyUint(UintTy::U32) => Some((0., std::u32::MAX as f64)),
+        TypeVariants::TyUint(UintTy::U64) | TypeVariants::TyUint(UintTy::Us) => {
+            Some((0., std::u64::MAX as f64))
+        }
+        TypeVariants::TyFloat(FloatTy::F32) => Some((std::f32::MIN as f64, std::f32::MAX as f64)),
+        _ => None,
+    }
+}
+
+/// Return the value of a constant casted to `f64`, e.g. `2 as f64` or `1.5f32 as f64`.
+fn cast_lit(expr: &Expr) -> Option<f64> {
+    if let ExprLit(ref lit) = expr.node {
+        match lit.node {
+            LitKind::Int(i, _) => Some(i as f64),
+            LitKind::Float(ref f, FloatTy::F32) => f.parse::<f32>().ok().map(|f| f as f64),
+            LitKind::Float(ref f, FloatTy::F64) | LitKind::FloatUnsuffixed(ref f) => f.parse().ok(),
+            _ => None,
+        }
+    }
+    else {
+        None
+    }
+}
+
+/// Return where the code of an expression starts.
+pub fn position(cx: &LateContext, expr: &Expr) -> Position {
+    let (indent, column) = line_indent(cx, expr.span);
+    Position { indent: indent, column: column }
+}
+
+/// Return the precedence an expression needs to have in order not to be parenthesized in its
+/// parent expression, and whether it needs to be strictly higher.
+pub fn context_prec(cx: &LateContext, expr: &Expr) -> (Prec, bool) {
+    // parentheses are not part of the HIR, but the user's ones are kept around the suggestion
+    if expr.span.lo.0 > 0 {
+        let around = mk_sp(expr.span.lo - BytePos(1), expr.span.hi + BytePos(1));
+        if is_parenthesized(&snippet(cx, around, "")) {
+            return (Prec::Min, false);
+        }
+    }
+
+    let map = &cx.tcx.map;
+
+    let parent = if let Some(Node::NodeExpr(parent)) = map.find(map.get_parent_node(expr.id)) {
+        parent
+    }
+    else {
+        return (Prec::Min, false);
+    };
+
+    match parent.node {
+        ExprBinary(op, ref lhs, _) => {
+            (binop(op.node).map_or(Prec::Min, Prec::of_binop), lhs.id != expr.id)
+        }
+        ExprAddrOf(..) | ExprBox(..) | ExprUnary(..) => (Prec::Unary, false),
+        ExprCast(..) | ExprType(..) => (Prec::Cast, false),
+        ExprField(..) | ExprTupField(..) => (Prec::Max, false),
+        ExprIndex(ref base, _) if base.id == expr.id => (Prec::Max, false),
+        ExprMethodCall(_, _, ref params) if params[0].id == expr.id => (Prec::Max, false),
+        _ => (Prec::Min, false),
+    }
+}
@@ -1,5 +1,6 @@
 use rustc::lint::LintContext;
 use std::borrow::Cow;
+use suggestion::Location;
 use syntax::codemap::{Span, mk_sp};
 
 /// Convert a span to a code snippet if available, otherwise use default, e.g.
@@ -15,3 +16,30 @@ pub fn snippet<'a, T: LintContext>(cx: &T, span: Span, default: &'a str) -> Cow<
 pub fn merge_span(begin: Span, end: Span) -> Span {
     mk_sp(begin.lo, end.hi)
 }
+
+/// Return the indentation of the line `span` starts on, and the column it starts at.
+pub fn line_indent<T: LintContext>(cx: &T, span: Span) -> (usize, usize) {
+    let loc = cx.sess().codemap().lookup_char_pos(span.lo);
+    let indent = loc.file.get_line(loc.line - 1).map_or(0, |line| {
+        line.chars().take_while(|c| c.is_whitespace()).count()
+    });
+
+    (indent, loc.col.0)
+}
+
+/// Return where the code of a span is.
+pub fn location<T: LintContext>(cx: &T, span: Span) -> Location {
+    let codemap = cx.sess().codemap();
+    let lo = codemap.lookup_char_pos(span.lo);
+    let hi = codemap.lookup_char_pos(span.hi);
+
+    Location {
+        file_name: lo.file.name.clone(),
+        byte_start: (span.lo.0 - lo.file.start_pos.0) as usize,
+        byte_end: (span.hi.0 - lo.file.start_pos.0) as usize,
+        line_start: lo.line,
+        line_end: hi.line,
+        column_start: lo.col.0 + 1,
+        column_end: hi.col.0 + 1,
+    }
+}
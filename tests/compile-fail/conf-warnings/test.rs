@@ -0,0 +1,12 @@
+#![feature(plugin)]
+//~^ERROR: Herbie configuration: unknown key `timout` in Herbie.toml at line 2, did you mean `timeout`?
+#![plugin(herbie_lint)]
+
+#![allow(unused_variables)]
+#![deny(herbie)]
+//~^NOTE: lint level defined here
+
+fn main() {
+    let (a, b) = (0., 0.);
+    a + b;
+}
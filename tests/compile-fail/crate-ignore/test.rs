@@ -0,0 +1,17 @@
+#![feature(plugin)]
+#![plugin(herbie_lint)]
+#![allow(dead_code)]
+#![herbie_ignore]
+#![deny(herbie, unused_variables)]
+
+const FOO: f64 = (1./1. + 2.) * 1.;
+
+fn main() {
+    let (a, b) = (0., 0.);
+
+    (a/b + a) * b;
+    let _ = |c: f64| (a/b + c) * b;
+
+    // Just so there actually is an error in that file for compiletest_rs
+    let c = 1; //~ERROR unused variable
+}
@@ -0,0 +1,20 @@
+#![feature(plugin)]
+#![plugin(herbie_lint)]
+
+#![allow(unused_variables)]
+#![deny(herbie)]
+
+fn main() {
+    let (a, b, c): (f64, f64, f64) = (0., 0., 0.);
+
+    a*b + c;
+    //~^ ERROR 1.0 → 0.0 bits of error, cost 2 → 2
+    //~| HELP Try this
+    //~| SUGGESTION a.mul_add(b, c)
+
+    // existing fused multiply-adds are recognized
+    a.mul_add(a, b*b).sqrt();
+    //~^ ERROR 29.1 → 0.0 bits of error
+    //~| HELP Try this
+    //~| SUGGESTION a.hypot(b)
+}
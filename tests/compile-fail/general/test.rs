@@ -1,7 +1,7 @@
-#![feature(plugin)]
+#![feature(plugin, stmt_expr_attributes)]
 #![plugin(herbie_lint)]
 
-#![allow(dead_code)]
+#![allow(dead_code, unused_assignments)]
 #![deny(herbie)]
 
 struct Foo { a: f64, b: f64 }
@@ -11,7 +11,7 @@ impl Foo {
         (self.a/self.a + self.a) * self.a;
         //~^ ERROR
         //~| HELP Try this
-        //~| SUGGESTION (self.a * self.a) + self.a
+        //~| SUGGESTION self.a * self.a + self.a
     }
 
     #[herbie_ignore]
@@ -45,7 +45,7 @@ fn attr_on_fn() {
         (0./0. + 0.) * 0.;
         //~^ ERROR
         //~| HELP Try this
-        //~| SUGGESTION (0. * 0.) + 0.
+        //~| SUGGESTION 0. * 0. + 0.
     }
 
     #[herbie_ignore]
@@ -54,6 +54,80 @@ fn attr_on_fn() {
     }
 }
 
+#[herbie_ignore]
+mod attr_on_mod {
+    fn foo() {
+        (0./0. + 0.) * 0.;
+
+        // unlike those of functions, the attributes of modules apply to the items nested in them
+        fn bar() {
+            (0./0. + 0.) * 0.;
+        }
+    }
+}
+
+const CONST: f64 = (0./1. + 2.) * 1.;
+//~^ ERROR
+//~| HELP Try this
+//~| SUGGESTION 2. * 1. + 0.
+
+#[herbie_ignore]
+const IGNORED_CONST: f64 = (0./1. + 2.) * 1.;
+
+fn closures(a: f64, b: f64) {
+    let _ = |c: f64| (a/b + c) * b;
+    //~^ ERROR
+    //~| HELP Try this
+    //~| SUGGESTION c * b + a
+
+    #[herbie_ignore]
+    let _ = |c: f64| (a/b + c) * b;
+}
+
+fn assignments(mut x: f64, y: f64, z: f64, w: f64) {
+    x += y * ((x - z) / w);
+    //~^ ERROR
+    //~| HELP Try this
+    //~| SUGGESTION x += y / w * (x - z)
+
+    x += y * ((x - z) / y);
+    //~^ ERROR
+    //~| HELP Try this
+    //~| SUGGESTION x = 2.0 * x - z
+
+    x += y * ((w - z) / w);
+
+    // the right-hand side is not reported on its own when the whole assignment is
+    x *= (y*y + 1.).sqrt();
+    //~^ ERROR
+    //~| HELP Try this
+    //~| SUGGESTION x *= 1.0f64.hypot(y)
+
+    x = (x/y + z) * y;
+    //~^ ERROR
+    //~| HELP Try this
+    //~| SUGGESTION z * y + x
+}
+
+fn casts(a: f64, n: u32, m: i64, x: f32) {
+    ((2 as f64) * a.ln()).exp();
+    //~^ ERROR
+    //~| HELP Try this
+    //~| SUGGESTION a * a
+
+    ((n as f64) + 1.).sqrt() - (n as f64).sqrt();
+    //~^ ERROR
+    //~| HELP Try this
+    //~| SUGGESTION 1. / ((n as f64 + 1.).sqrt() + (n as f64).sqrt())
+
+    ((x as f64) + 1.).sqrt() - (x as f64).sqrt();
+    //~^ ERROR
+    //~| HELP Try this
+    //~| SUGGESTION 1. / ((x as f64 + 1.).sqrt() + (x as f64).sqrt())
+
+    ((n as f64) + 1.).sqrt() - (m as f64).sqrt();
+}
+
 fn main() {
     integers();
 
@@ -64,56 +138,56 @@ fn main() {
     (a/a + a) * a;
     //~^ ERROR
     //~| HELP Try this
-    //~| SUGGESTION (a * a) + a
+    //~| SUGGESTION a * a + a
     (a/b + a) * b;
     //~^ ERROR
     //~| HELP Try this
-    //~| SUGGESTION (a * b) + a
+    //~| SUGGESTION a * b + a
     (a/b + c) * b;
     //~^ ERROR
     //~| HELP Try this
-    //~| SUGGESTION (c * b) + a
+    //~| SUGGESTION c * b + a
 
     (a/b + c) * a;
 
     (0./1. + 2.) * 1.;
     //~^ ERROR
     //~| HELP Try this
-    //~| SUGGESTION (2. * 1.) + 0.
+    //~| SUGGESTION 2. * 1. + 0.
     (1./1. + 2.) * 1.;
     //~^ ERROR
     //~| HELP Try this
-    //~| SUGGESTION (2. * 1.) + 1.
+    //~| SUGGESTION 2. * 1. + 1.
     (1./1. + 1.) * 1.;
     //~^ ERROR
     //~| HELP Try this
-    //~| SUGGESTION (1. * 1.) + 1.
+    //~| SUGGESTION 1. * 1. + 1.
 
     (0./1. + a) * 1.;
     //~^ ERROR
     //~| HELP Try this
-    //~| SUGGESTION (a * 1.) + 0.
+    //~| SUGGESTION a * 1. + 0.
     (0./a + 2.) * a;
     //~^ ERROR
     //~| HELP Try this
-    //~| SUGGESTION (2. * a) + 0.
+    //~| SUGGESTION 2. * a + 0.
 
     (a/b + get_f64()) * b;
     //~^ ERROR
     //~| HELP Try this
-    //~| SUGGESTION ((get_f64()) * b) + a
+    //~| SUGGESTION get_f64() * b + a
     (a/b + (4.5f64).sqrt()) * b;
     //~^ ERROR
     //~| HELP Try this
-    //~| SUGGESTION (((4.5f64).sqrt()) * b) + a
+    //~| SUGGESTION (4.5f64).sqrt() * b + a
     (a/b + (42 as f64)) * b;
     //~^ ERROR
     //~| HELP Try this
-    //~| SUGGESTION (((42 as f64)) * b) + a
+    //~| SUGGESTION 42 as f64 * b + a
     (a/b + { 42. }) * b;
     //~^ ERROR
     //~| HELP Try this
-    //~| SUGGESTION (({ 42. }) * b) + a
+    //~| SUGGESTION ({ 42. }) * b + a
 
     (a/get_f64() + c) * get_f64();
     (a/{ 42. } + c) * { 42. };
@@ -130,12 +204,12 @@ fn main() {
     (a/b + c.floor()) * b;
     //~^ ERROR
     //~| HELP Try this
-    //~| SUGGESTION ((c.floor()) * b) + a
+    //~| SUGGESTION c.floor() * b + a
 
     (a/b + (0.5 + c).floor()) * b;
     //~^ ERROR
     //~| HELP Try this
-    //~| SUGGESTION (((0.5 + c).floor()) * b) + a
+    //~| SUGGESTION (0.5 + c).floor() * b + a
 
     let d = (0., 0.);
     let d2 = (0., 0.);
@@ -143,17 +217,17 @@ fn main() {
     (a/b + d.0) * b;
     //~^ ERROR
     //~| HELP Try this
-    //~| SUGGESTION (d.0 * b) + a
+    //~| SUGGESTION d.0 * b + a
 
     (a/b + get_tup().0) * b;
     //~^ ERROR
     //~| HELP Try this
-    //~| SUGGESTION ((get_tup().0) * b) + a
+    //~| SUGGESTION get_tup().0 * b + a
 
     (a/d.0 + c) * d.0;
     //~^ ERROR
     //~| HELP Try this
-    //~| SUGGESTION (c * d.0) + a
+    //~| SUGGESTION c * d.0 + a
 
     (a/d.0 + c) * d2.0;
     (a/d.0 + c) * a;
@@ -168,17 +242,17 @@ fn main() {
     (a/b + e.a) * b;
     //~^ ERROR
     //~| HELP Try this
-    //~| SUGGESTION (e.a * b) + a
+    //~| SUGGESTION e.a * b + a
 
     (a/b + get_struct().a) * b;
     //~^ ERROR
     //~| HELP Try this
-    //~| SUGGESTION ((get_struct().a) * b) + a
+    //~| SUGGESTION get_struct().a * b + a
 
     (a/e.a + c) * e.a;
     //~^ ERROR
     //~| HELP Try this
-    //~| SUGGESTION (c * e.a) + a
+    //~| SUGGESTION c * e.a + a
 
     (a/e.a + c) * e2.a;
 
@@ -188,12 +262,12 @@ fn main() {
     1. - a.cos();
     //~^ ERROR
     //~| HELP Try this
-    //~| SUGGESTION (a.sin() * a.sin()) / a.cos().ln_1p().exp()
+    //~| SUGGESTION a.sin() * a.sin() / a.cos().ln_1p().exp()
 
     (a+b).sin() - (a+b).cos();
     //~^ ERROR
     //~| HELP Try this
-    //~| SUGGESTION (b.sin() * (a.sin() + a.cos())) - ((a.cos() - a.sin()) * b.cos())
+    //~| SUGGESTION b.sin() * (a.sin() + a.cos()) - (a.cos() - a.sin()) * b.cos()
 
     (a+1.).ln();
     //~^ ERROR
@@ -12,5 +12,5 @@ fn main() {
     //~^ NOTE Calling Herbie on the following expression, it might take a while
     //~| ERROR
     //~| HELP Try this
-    //~| SUGGESTION b - (b / a);
+    //~| SUGGESTION b - b / a;
 }
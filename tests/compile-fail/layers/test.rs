@@ -0,0 +1,27 @@
+#![feature(plugin)]
+#![plugin(herbie_lint)]
+
+#![allow(unused_variables)]
+#![deny(herbie)]
+
+fn main() {
+    let (a, b): (f64, f64) = (0., 0.);
+
+    // from vendor.db
+    (a*a + b*b).sqrt();
+    //~^ ERROR Numerically unstable expression
+    //~| HELP Try this
+    //~| SUGGESTION a.hypot(b)
+
+    // from Herbie.db
+    a.exp() - 1.;
+    //~^ ERROR Numerically unstable expression
+    //~| HELP Try this
+    //~| SUGGESTION a.exp_m1()
+
+    // in both, vendor.db takes precedence
+    (a + 1.).ln();
+    //~^ ERROR Numerically unstable expression
+    //~| HELP Try this
+    //~| SUGGESTION a.ln_1p()
+}
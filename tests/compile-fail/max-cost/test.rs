@@ -0,0 +1,17 @@
+#![feature(plugin)]
+#![plugin(herbie_lint)]
+
+#![allow(unused_variables)]
+#![deny(herbie)]
+
+fn main() {
+    let (a, b): (f64, f64) = (0., 0.);
+
+    // the suggestion, `a.hypot(b)`, costs 7 → 20
+    (a*a + b*b).sqrt();
+
+    a.exp() - 1.;
+    //~^ ERROR 40.3 → 0.0 bits of error, cost 21 → 20 (1 addition, 1 function call → 1 function call)
+    //~| HELP Try this
+    //~| SUGGESTION a.exp_m1()
+}
@@ -0,0 +1,17 @@
+#![feature(plugin)]
+#![plugin(herbie_lint)]
+
+#![allow(unused_variables)]
+#![deny(herbie)]
+
+fn main() {
+    let (a, b, c): (f64, f64, f64) = (0., 0., 0.);
+
+    // 7.6 → 0.0 bits of error
+    (a/b + c) * b;
+
+    (a*a + b*b).sqrt();
+    //~^ ERROR Numerically unstable expression: 29.9 → 0.0 bits of error
+    //~| HELP Try this
+    //~| SUGGESTION a.hypot(b)
+}
@@ -0,0 +1,172 @@
+#![feature(plugin)]
+#![plugin(herbie_lint)]
+
+#![allow(dead_code, unused_variables)]
+#![deny(herbie)]
+
+// The database rewrites `exp(a) - 1` as `expm1(a/2) * (exp(a/2) + 1)`, which is better on
+// average but worse for `a` in `[1, 2]`.
+
+fn unconstrained(a: f64) {
+    a.exp() - 1.;
+    //~^ ERROR Numerically unstable expression
+    //~| HELP Try this
+}
+
+#[herbie_range(a = "1..2")]
+fn attribute(a: f64) {
+    a.exp() - 1.;
+    //~^ NOTE it does not improve the accuracy on the known range of the inputs
+}
+
+fn assertion(a: f64) {
+    assert!(1. <= a && a <= 2.);
+    a.exp() - 1.;
+    //~^ NOTE it does not improve the accuracy on the known range of the inputs
+}
+
+fn helps_on_range(a: f64) {
+    assert!(-1e-10 < a && a < 1e-10);
+    a.exp() - 1.;
+    //~^ ERROR Numerically unstable expression
+    //~| HELP Try this
+}
+
+fn assertion_after(a: f64) {
+    a.exp() - 1.;
+    //~^ ERROR Numerically unstable expression
+    //~| HELP Try this
+    assert!(1. <= a && a <= 2.);
+}
+
+struct Foo { a: f64 }
+
+fn field(foo: Foo) {
+    assert!(1. <= foo.a && foo.a <= 2.);
+    foo.a.exp() - 1.;
+    //~^ NOTE it does not improve the accuracy on the known range of the inputs
+}
+
+#[herbie_range(a = "1..2")]
+mod module {
+    fn function(a: f64) {
+        a.exp() - 1.;
+        //~^ NOTE it does not improve the accuracy on the known range of the inputs
+    }
+}
+
+fn reassigned(mut a: f64) {
+    assert!(1. <= a && a <= 2.);
+    a = a * 1e-12;
+    a.exp() - 1.;
+    //~^ ERROR Numerically unstable expression
+    //~| HELP Try this
+}
+
+fn reassigned_in_loop(mut a: f64) {
+    assert!(1. <= a && a <= 2.);
+    loop {
+        a.exp() - 1.;
+        //~^ ERROR Numerically unstable expression
+        //~| HELP Try this
+        a *= 1e-12;
+    }
+}
+
+fn shrink(a: &mut f64) {
+    *a *= 1e-12;
+}
+
+fn borrowed_mutably(mut a: f64) {
+    assert!(1. <= a && a <= 2.);
+    shrink(&mut a);
+    a.exp() - 1.;
+    //~^ ERROR Numerically unstable expression
+    //~| HELP Try this
+}
+
+struct Bar { a: f64 }
+
+impl Bar {
+    fn shrink(&mut self) {
+        self.a *= 1e-12;
+    }
+}
+
+fn borrowed_by_method(mut bar: Bar) {
+    assert!(1. <= bar.a && bar.a <= 2.);
+    bar.shrink();
+    bar.a.exp() - 1.;
+    //~^ ERROR Numerically unstable expression
+    //~| HELP Try this
+}
+
+fn borrowed_by_closure(mut a: f64) {
+    assert!(1. <= a && a <= 2.);
+    {
+        let mut f = || shrink(&mut a);
+        f();
+    }
+    a.exp() - 1.;
+    //~^ ERROR Numerically unstable expression
+    //~| HELP Try this
+}
+
+fn borrowed_immutably(a: f64) {
+    assert!(1. <= a && a <= 2.);
+    let b = &a;
+    a.exp() - 1.;
+    //~^ NOTE it does not improve the accuracy on the known range of the inputs
+}
+
+fn shadowed(a: f64) {
+    assert!(1. <= a && a <= 2.);
+    let a = a * 1e-12;
+    a.exp() - 1.;
+    //~^ ERROR Numerically unstable expression
+    //~| HELP Try this
+}
+
+#[herbie_range(a = "1..2")]
+fn attribute_shadowed(a: f64) {
+    let a = a * 1e-12;
+    a.exp() - 1.;
+    //~^ ERROR Numerically unstable expression
+    //~| HELP Try this
+}
+
+#[herbie_range(a = "1..2")]
+fn attribute_outer(a: f64) {
+    assert!(1. <= a && a <= 2.);
+
+    fn inner(a: f64) {
+        a.exp() - 1.;
+        //~^ ERROR Numerically unstable expression
+        //~| HELP Try this
+    }
+}
+
+fn contradictory(a: f64) {
+    assert!(5. < a && a < 1.);
+    a.exp() - 1.;
+    //~^ NOTE the range of `a`, 5 to 1, is empty
+    //~| ERROR Numerically unstable expression
+    //~| HELP Try this
+}
+
+#[herbie_range(a = "1..2")]
+fn attribute_contradicted(a: f64) {
+    assert!(a > 3.);
+    a.exp() - 1.;
+    //~^ NOTE the range of `a`, 3 to 2, is empty
+    //~| ERROR Numerically unstable expression
+    //~| HELP Try this
+}
+
+#[herbie_range(a)]
+//~^ ERROR Expected `#[herbie_range(x = "lo..hi")]`
+fn invalid(a: f64) {
+}
+
+fn main() {
+}
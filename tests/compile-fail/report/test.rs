@@ -0,0 +1,21 @@
+#![feature(plugin)]
+#![plugin(herbie_lint)]
+
+#![allow(unused_variables)]
+#![deny(herbie)]
+
+fn main() {
+    let (a, b, c): (f64, f64, f64) = (0., 0., 0.);
+
+    (a/b + c) * b;
+    //~^ ERROR
+    //~| HELP Try this
+    //~| SUGGESTION c * b + a
+
+    a.floor() * b;
+
+    (a*a + b*b).sqrt();
+    //~^ ERROR
+    //~| HELP Try this
+    //~| SUGGESTION a.hypot(b)
+}
@@ -0,0 +1,14 @@
+#![feature(plugin)]
+#![plugin(herbie_lint)]
+
+#![allow(unused_variables)]
+#![deny(herbie)]
+
+fn main() {
+    let (a, b, c) = (0., 0., 0.);
+
+    (a/b + c) * b;
+    //~^ ERROR
+    //~| HELP Try this
+    //~| SUGGESTION c * b + a
+}
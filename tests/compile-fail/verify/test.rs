@@ -0,0 +1,33 @@
+#![feature(plugin)]
+#![plugin(herbie_lint)]
+
+#![allow(unused_variables)]
+#![deny(herbie)]
+
+fn main() {
+    let (a, b): (f64, f64) = (0., 0.);
+
+    // the rewriting is as accurate as the expression
+    a*a + b;
+    //~^ ERROR Ignoring entry #3 of the Herbie database
+
+    // the rewriting is `(expm1 (* 2 herbie0))`
+    a.exp() - 1.;
+    //~^ ERROR Ignoring entry #2 of the Herbie database
+
+    // a correct entry, whose operand `a*a + b*b` matches the quarantined entry #3
+    (a*a + b*b).sqrt();
+    //~^ ERROR Numerically unstable expression
+    //~| HELP Try this
+    //~| SUGGESTION a.hypot(b)
+
+    // `%` cannot be evaluated, so the entry is used without being verified
+    (a % b).exp() - 1.;
+    //~^ NOTE Using entry #4 of the Herbie database
+    //~| ERROR Numerically unstable expression
+    //~| HELP Try this
+    //~| SUGGESTION (a % b).exp_m1()
+
+    // quarantined entries are only reported once
+    b*b + a;
+}
@@ -1,10 +1,66 @@
 extern crate compiletest_rs as compiletest;
+extern crate rustc_serialize;
 
-use std::env::{current_dir, set_current_dir, set_var, var};
-use std::fs::{copy, read_dir, remove_file};
+use rustc_serialize::json::Json;
+use std::env::{current_dir, remove_var, set_current_dir, set_var, var};
+use std::fs::{File, copy, read_dir, remove_file};
+use std::io::Read;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Output};
 
+/// Remove the file names from JSON objects, as they depend on where the tests are run.
+fn remove_file_names(json: &mut Json) {
+    match *json {
+        Json::Array(ref mut array) => {
+            for json in array {
+                remove_file_names(json);
+            }
+        }
+        Json::Object(ref mut object) => {
+            object.remove("file_name");
+            object.remove("uri");
+            for (_, json) in object.iter_mut() {
+                remove_file_names(json);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Read a file of JSON suggestions, one per line.
+fn read_fixes<P: AsRef<Path>>(path: P) -> Vec<Json> {
+    let mut buffer = String::new();
+    File::open(path).unwrap().read_to_string(&mut buffer).unwrap();
+
+    buffer.lines().map(|line| {
+        let mut json = Json::from_str(line).unwrap();
+        remove_file_names(&mut json);
+        json
+    }).collect()
+}
+
+/// Read the results of a SARIF file, or a list of expected results.
+fn read_sarif_results<P: AsRef<Path>>(path: P) -> Json {
+    let json = read_report(path);
+    let results = json.find("runs").map(|runs| {
+        runs.as_array().unwrap()[0].find("results").unwrap().clone()
+    });
+
+    results.unwrap_or(json)
+}
+
+fn read_report<P: AsRef<Path>>(path: P) -> Json {
+    let mut buffer = String::new();
+    File::open(path).unwrap().read_to_string(&mut buffer).unwrap();
+
+    let mut json = Json::from_str(&buffer).unwrap();
+    remove_file_names(&mut json);
+    json
+}
+
+/// The columns of the database to compare, the `hash` column is computed from `cmdin`.
+const SELECT_RESULTS: &'static str = "select id, cmdin, cmdout, opts, errin, errout from HerbieResults;";
+
 fn run_mode(mode: &'static str, dir: PathBuf, target_dir: &Path) {
     let mut config = compiletest::default_config();
 
@@ -28,20 +84,22 @@ fn run_mode(mode: &'static str, dir: PathBuf, target_dir: &Path) {
     }
 
     set_var("PATH", format!("{}:.", var("PATH").unwrap()));
+    // the configuration is looked up from the test directory, not from this crate
+    remove_var("CARGO_MANIFEST_DIR");
 
     compiletest::run_tests(&config);
 
     if has_db_orig {
         let Output { status: status_dest, stdout: stdout_dest, stderr: stderr_dest } =
             Command::new("sqlite3")
-            .arg("Herbie.dest.db").arg("select * from HerbieResults;")
+            .arg("Herbie.dest.db").arg(SELECT_RESULTS)
             .output()
             .unwrap()
         ;
 
         let Output { status, stdout, stderr } =
             Command::new("sqlite3")
-            .arg("Herbie.db").arg("select * from HerbieResults;")
+            .arg("Herbie.db").arg(SELECT_RESULTS)
             .output()
             .unwrap()
         ;
@@ -56,6 +114,23 @@ fn run_mode(mode: &'static str, dir: PathBuf, target_dir: &Path) {
 
         remove_file("Herbie.db").unwrap();
     }
+
+    if Path::new("fixes.expected.json").exists() {
+        assert_eq!(read_fixes("fixes.expected.json"), read_fixes("fixes.json"));
+        remove_file("fixes.json").unwrap();
+    }
+
+    if Path::new("report.expected.json").exists() {
+        assert_eq!(read_report("report.expected.json"), read_report("report.json"));
+        assert!(Path::new("report.html").exists());
+        remove_file("report.json").unwrap();
+        remove_file("report.html").unwrap();
+    }
+
+    if Path::new("sarif.expected.json").exists() {
+        assert_eq!(read_sarif_results("sarif.expected.json"), read_sarif_results("sarif.json"));
+        remove_file("sarif.json").unwrap();
+    }
 }
 
 #[test]